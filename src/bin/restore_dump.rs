@@ -0,0 +1,33 @@
+use std::env;
+use std::fs;
+
+use prost::Message;
+use tonic::Request;
+use tonic::transport::Channel;
+
+use crate::chord_proto::chord_client::ChordClient;
+use crate::chord_proto::KvBatchMsg;
+
+pub mod chord_proto {
+    tonic::include_proto!("chord");
+}
+
+/// Re-imports a kv_store dump written on panic (see the dump_on_panic config) into the given node
+/// via the handoff RPC. Usage: restore_dump <dump path> <node url>
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        panic!("Provide the dump path and the url of the node to restore to")
+    }
+
+    let batch = KvBatchMsg::decode(fs::read(&args[1]).unwrap().as_slice()).unwrap();
+    let pair_count = batch.pairs.len();
+    let mut client: ChordClient<Channel> = ChordClient::connect(args[2].clone())
+        .await
+        .unwrap();
+    client.handoff(Request::new(tokio_stream::iter(vec![batch])))
+        .await
+        .unwrap();
+    eprintln!("Restored {} pairs", pair_count);
+}
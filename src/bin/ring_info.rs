@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+use std::env;
+
+use serde::Serialize;
+use tonic::Request;
+use tonic::transport::ClientTlsConfig;
+
+use chord::utils::config::{tool_flag_value, tool_positional_args};
+use chord::utils::crypto;
+use chord::utils::tls::{connect_channel, load_tls_configs};
+use chord::utils::types::{Address, HashPos};
+
+use crate::chord_proto::chord_client::ChordClient;
+use crate::chord_proto::Empty;
+
+pub mod chord_proto {
+    tonic::include_proto!("chord");
+}
+
+/// upper bound for the successor walk, protects against successor pointers that never lead back
+const MAX_RING_SIZE: usize = 1024;
+
+#[derive(Serialize)]
+struct NodeInfo {
+    address: Address,
+    pos: HashPos,
+    predecessor: Option<Address>,
+    successor: Address,
+    /// number of positions in (predecessor, pos] according to the discovered ring
+    owned_range_size: HashPos,
+    /// only available if the node runs with dev_mode = true
+    key_count: Option<u32>,
+    /// density of the node's range relative to the ring average, above 1 means a hot range
+    relative_key_density: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct RingInfo {
+    node_count: usize,
+    total_key_count: Option<u64>,
+    nodes: Vec<NodeInfo>,
+    inconsistencies: Vec<String>,
+}
+
+/// flags that are followed by a value
+const VALUE_FLAGS: [&str; 4] = ["--cluster-salt", "--tls-cert", "--tls-key", "--tls-ca"];
+
+/// Walks the ring along the successor pointers starting at the given node and prints a summary of
+/// every node found on the way. Pass --json to get machine readable output, and the salt of the
+/// cluster with --cluster-salt <salt> if it runs with one, otherwise the positions are wrong.
+/// A cluster with TLS is reached with --tls-cert, --tls-key and --tls-ca like the nodes.
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let json = args.iter().any(|arg| arg == "--json");
+    if let Some(cluster_salt) = tool_flag_value(&args, "--cluster-salt") {
+        crypto::set_hash_salt(cluster_salt.as_bytes());
+    }
+    let start_url = *tool_positional_args(&args, &VALUE_FLAGS).first()
+        .expect("Provide the url of one node of the ring");
+
+    let tls_config_option = match (tool_flag_value(&args, "--tls-cert"), tool_flag_value(&args, "--tls-key"), tool_flag_value(&args, "--tls-ca")) {
+        (Some(cert_path), Some(key_path), Some(ca_path)) => Some(load_tls_configs(cert_path, key_path, ca_path).unwrap().1),
+        (None, None, None) => None,
+        _ => panic!("Provide all of --tls-cert, --tls-key and --tls-ca to use TLS")
+    };
+
+    // the nodes are walked by their addresses, so the scheme of the start url is implied by the TLS flags
+    let start_address = start_url.trim_start_matches("http://").trim_start_matches("https://").to_string();
+    let ring_info = collect_ring_info(&start_address, tls_config_option.as_ref()).await;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&ring_info).unwrap());
+    } else {
+        print_ring_info(&ring_info);
+    }
+}
+
+async fn collect_ring_info(node_address: &Address, tls_config_option: Option<&ClientTlsConfig>) -> RingInfo {
+    let mut client = ChordClient::new(connect_channel(node_address, tls_config_option).await.unwrap());
+    let start_address: Address = client.get_successor_list(Request::new(Empty {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .own_address
+        .unwrap()
+        .address;
+
+    let mut nodes = Vec::new();
+    let mut inconsistencies = Vec::new();
+    let salt_fingerprint = client.get_cluster_info(Request::new(Empty {}))
+        .await
+        .unwrap()
+        .into_inner()
+        .salt_fingerprint;
+    if salt_fingerprint != crypto::hash_salt_fingerprint() {
+        inconsistencies.push("The cluster runs with a different salt, pass it with --cluster-salt to get the right positions".to_string());
+    }
+    let mut visited = HashSet::new();
+    let mut current_address = start_address.clone();
+    loop {
+        if nodes.len() == MAX_RING_SIZE {
+            inconsistencies.push(format!("Stopped the successor walk after {} nodes", MAX_RING_SIZE));
+            break;
+        }
+        visited.insert(current_address.clone());
+        let mut client = ChordClient::new(connect_channel(&current_address, tls_config_option).await.unwrap());
+        let successor = client.get_successor_list(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .successors
+            .first()
+            .map(|successor| successor.address.clone())
+            .unwrap_or_default();
+        let predecessor = client.get_predecessor(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .address_optional
+            .map(|address| address.address);
+        let key_count = client.get_kv_store_size(Request::new(Empty {}))
+            .await
+            .ok()
+            .map(|response| response.into_inner().size);
+
+        nodes.push(NodeInfo {
+            pos: crypto::hash(current_address.as_bytes()),
+            address: current_address,
+            predecessor,
+            successor: successor.clone(),
+            owned_range_size: 0,
+            key_count,
+            relative_key_density: None,
+        });
+
+        if successor == start_address {
+            break;
+        }
+        if visited.contains(&successor) {
+            inconsistencies.push(format!("Successor walk ran into a cycle at {} that doesn't contain {}", successor, start_address));
+            break;
+        }
+        current_address = successor;
+    }
+
+    nodes.sort_by_key(|node| node.pos);
+    let node_count = nodes.len();
+    for i in 0..node_count {
+        let expected_predecessor = nodes[(i + node_count - 1) % node_count].address.clone();
+        let expected_successor = nodes[(i + 1) % node_count].address.clone();
+        let predecessor_pos = nodes[(i + node_count - 1) % node_count].pos;
+        let node = &mut nodes[i];
+        node.owned_range_size = if node_count == 1 { HashPos::MAX } else { node.pos.wrapping_sub(predecessor_pos) };
+
+        if node.predecessor.as_ref() != Some(&expected_predecessor) {
+            inconsistencies.push(format!("Node {} has predecessor {:?}, but {} precedes it in the ring", node.address, node.predecessor, expected_predecessor));
+        }
+        if node.successor != expected_successor {
+            inconsistencies.push(format!("Node {} has successor {}, but {} follows it in the ring", node.address, node.successor, expected_successor));
+        }
+    }
+
+    let total_key_count: Option<u64> = nodes.iter()
+        .map(|node| node.key_count.map(|key_count| key_count as u64))
+        .sum();
+    if let Some(total_key_count) = total_key_count.filter(|total_key_count| *total_key_count > 0) {
+        for node in nodes.iter_mut() {
+            let key_share = node.key_count.unwrap() as f64 / total_key_count as f64;
+            let owned_share = node.owned_range_size as f64 / HashPos::MAX as f64;
+            node.relative_key_density = Some(key_share / owned_share);
+        }
+    }
+    RingInfo { node_count, total_key_count, nodes, inconsistencies }
+}
+
+fn print_ring_info(ring_info: &RingInfo) {
+    println!("Nodes: {}", ring_info.node_count);
+    match ring_info.total_key_count {
+        Some(total_key_count) => println!("Keys: {}", total_key_count),
+        None => println!("Keys: unknown, not all nodes run with dev_mode = true"),
+    }
+    println!();
+    println!("{:<24} {:>20} {:>10} {:>8} {:>8}", "address", "position", "owned", "keys", "density");
+    for node in &ring_info.nodes {
+        let owned_share = node.owned_range_size as f64 / HashPos::MAX as f64 * 100.0;
+        let key_count = node.key_count.map_or("-".to_string(), |key_count| key_count.to_string());
+        let density = node.relative_key_density.map_or("-".to_string(), |density| format!("{:.2}", density));
+        println!("{:<24} {:>20} {:>9.2}% {:>8} {:>8}", node.address, node.pos, owned_share, key_count, density);
+    }
+    println!();
+    if ring_info.inconsistencies.is_empty() {
+        println!("Looks good!");
+    } else {
+        for inconsistency in &ring_info.inconsistencies {
+            println!("{}", inconsistency);
+        }
+        println!("Ring is inconsistent!");
+    }
+}
@@ -0,0 +1,64 @@
+use std::env;
+
+use tonic::Request;
+use tonic::transport::Channel;
+
+use chord::utils::config::{tool_flag_value, tool_positional_args};
+use chord::utils::crypto::{hash_salt_fingerprint, set_hash_salt};
+
+use crate::chord_proto::chord_client::ChordClient;
+use crate::chord_proto::Empty;
+
+pub mod chord_proto {
+    tonic::include_proto!("chord");
+}
+
+/// Asks each given node to check its own invariants and prints the violations it reports.
+/// The nodes have to run with dev_mode = true. Pass the salt of the cluster with
+/// --cluster-salt <salt> if it runs with one, a node hashing with another salt is reported.
+#[tokio::main]
+async fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Some(cluster_salt) = tool_flag_value(&args, "--cluster-salt") {
+        set_hash_salt(cluster_salt.as_bytes());
+    }
+    let hosts: Vec<&String> = tool_positional_args(&args, &["--cluster-salt"]);
+    if hosts.is_empty() {
+        panic!("Provide at least one node url")
+    }
+
+    let mut is_valid = true;
+    for host in hosts {
+        let mut client: ChordClient<Channel> = ChordClient::connect(host.clone())
+            .await
+            .unwrap();
+        let mut violations = client.self_check(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .violations;
+        let salt_fingerprint = client.get_cluster_info(Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .salt_fingerprint;
+        if salt_fingerprint != hash_salt_fingerprint() {
+            violations.push("Node runs with a different cluster salt".to_string());
+        }
+
+        for violation in violations {
+            if is_valid {
+                eprintln!("-----");
+                is_valid = false;
+            }
+            eprintln!("Node {}: {}", host, violation);
+            eprintln!("-----");
+        }
+    }
+
+    if is_valid {
+        eprintln!("Looks good!")
+    } else {
+        eprintln!("Node invariants violated!")
+    }
+}
@@ -5,121 +5,94 @@ use tokio::time::{Duration, sleep};
 use tonic::Request;
 use tonic::transport::Channel;
 
-use chord::utils;
-use chord::utils::types::HashPos;
-use utils::crypto;
-
-use crate::chord_proto::{Empty, NodeSummaryMsg, SuccessorListMsg, HashPosMsg};
-use crate::chord_proto::chord_client::ChordClient;
-
-pub mod chord_proto {
-    tonic::include_proto!("chord");
-}
+use chord::chord_proto::{Empty, NodeSummaryMsg, StatsMsg};
+use chord::chord_proto::chord_client::ChordClient;
+use chord::utils::config::{tool_flag_value, tool_positional_args};
+use chord::utils::crypto::set_hash_salt;
+use chord::utils::validation::{validate, ValidationReport};
 
 const DURATION: Duration = Duration::from_secs(20 as u64);
 
+/// Fetches the summaries of the given nodes and checks that predecessors, fingers and successor
+/// lists match the ring they form. Pass --json to print a machine readable report to stdout, and
+/// the salt of the cluster with --cluster-salt <salt> if it runs with one, otherwise the expected
+/// fingers are computed from the wrong positions.
 #[tokio::main]
 async fn main() {
-    let mut node_summaries: Vec<NodeSummaryMsg> = Vec::new();
-    {
-        let mut args: Vec<String> = env::args().collect();
-        if args.len() == 1 {
-            panic!("Provide at least one node url")
-        }
-
-        for host in args.iter().skip(1) {
-            let mut client: ChordClient<Channel> = ChordClient::connect(host.clone())
-                .await
-                .unwrap();
-            let summary: NodeSummaryMsg = client.get_node_summary(Request::new(Empty {}))
-                .await
-                .unwrap().get_ref().clone();
-
-            node_summaries.push(summary);
-        }
-        // child_handles getting out of scope will shut down nodes due to .kill_on_drop(true)
+    let args: Vec<String> = env::args().skip(1).collect();
+    let json = args.iter().any(|arg| arg == "--json");
+    if let Some(cluster_salt) = tool_flag_value(&args, "--cluster-salt") {
+        set_hash_salt(cluster_salt.as_bytes());
     }
-
-    node_summaries.sort_by(|a: &NodeSummaryMsg, b: &NodeSummaryMsg| {
-        foo(a.pos.clone().unwrap()).cmp(&foo(b.pos.clone().unwrap()))
-    });
-
-    let node_ids: Vec<HashPos> = node_summaries.iter()
-        .map(|node_summary: &NodeSummaryMsg| {
-            // let bytes_b: [u8; std::mem::size_of::<HashPos>()] = node_summary.pos.unwrap().key.try_into().unwrap();
-            // HashPos::from_be_bytes(bytes_b)
-            foo(node_summary.pos.clone().unwrap())
-        })
-        .collect::<Vec<HashPos>>();
-
-    // check predecessors
-    for i in 0..node_summaries.len() {
-        let current_node: &String = &node_summaries[i].url;
-        let next_node_pred: &String = &node_summaries[(i + 1) % node_summaries.len()].predecessor.clone().unwrap().address;
-
-        if current_node.ne(next_node_pred) {
-            panic!("Node {} has wrong predecessor: {}", current_node, next_node_pred)
-        }
+    let hosts: Vec<&String> = tool_positional_args(&args, &["--cluster-salt"]);
+    if hosts.is_empty() {
+        panic!("Provide at least one node url")
     }
 
-    // validate finger entries
-    let mut is_valid = true;
-    for i in 0..node_summaries.len() {
-        let fingers = &node_summaries[i].finger_entries;
-        for (j, finger) in fingers.iter().enumerate() {
-            let finger_key: HashPos = finger.id.parse::<HashPos>().unwrap();
-            let node_key_pointed_to = crypto::hash(&finger.address.as_bytes());
-            let actually_responsible_node_key = get_responsible_node_for_key(finger_key, &node_ids);
-            let actually_responsible_node_address = get_node_address_for_key(&actually_responsible_node_key, &node_summaries);
-            if node_key_pointed_to.ne(&actually_responsible_node_key) {
-                if is_valid {
-                    eprintln!("-----");
-                    is_valid = false;
-                }
-                eprintln!("Node ({}, {}): Wrong finger entry! ", foo(node_summaries[i].pos.clone().unwrap()), node_summaries[i].url);
-                eprintln!("{}-th Finger {} points to node ({}, {}) ", j, finger_key, node_key_pointed_to, &finger.address);
-                eprintln!("But node ({}, {}) is responsible for {}", actually_responsible_node_key, actually_responsible_node_address, finger_key);
-                eprintln!("-----");
-            }
-        }
+    let mut node_summaries: Vec<NodeSummaryMsg> = Vec::new();
+    let mut key_densities: Vec<(String, f64)> = Vec::new();
+    for host in hosts {
+        let mut client: ChordClient<Channel> = ChordClient::connect(host.clone())
+            .await
+            .unwrap();
+        let summary: NodeSummaryMsg = client.get_node_summary(Request::new(Empty {}))
+            .await
+            .unwrap().get_ref().clone();
+
+        node_summaries.push(summary);
+
+        let stats: StatsMsg = client.get_stats(Request::new(Empty {}))
+            .await
+            .unwrap().into_inner();
+        key_densities.push((host.clone(), stats.key_density));
     }
 
-    // validate predecessor list
-    for (i, node_summary) in node_summaries.iter().enumerate() {
-        let successor_list = node_summary.successor_list.as_ref();
-        for (j, successor_according_to_list) in successor_list.unwrap().successors.iter().enumerate() {
-            let actual_successor_address = &node_summaries[(i + j + 1) % node_summaries.len()].url;
-            if successor_according_to_list.address.ne(actual_successor_address) {
-                eprintln!("-----");
-                eprintln!("Node ({}, {}): Wrong successor list! ", foo(node_summaries[i].pos.clone().unwrap()), node_summaries[i].url);
-                eprintln!("Actual successor address: {}, but was {}", actual_successor_address, successor_according_to_list.address);
-                eprintln!("-----");
-                is_valid = false;
-            }
-        }
+    let report = validate(&node_summaries);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+        return;
     }
+    print_report(&report);
 
+    // a node far above the average density serves a hot range, not just a large one
+    let average_key_density = key_densities.iter().map(|(_, key_density)| key_density).sum::<f64>() / key_densities.len() as f64;
+    for (host, key_density) in &key_densities {
+        eprintln!("Node {}: key density {:.3e} ({:.2}x average)", host, key_density, key_density / average_key_density);
+    }
 
-    if is_valid {
+    if report.valid {
         eprintln!("Looks good!")
     } else {
         eprintln!("Cluster is invalid!")
     }
 }
 
-fn get_responsible_node_for_key(key: HashPos, other_nodes: &Vec<HashPos>) -> HashPos {
-    *other_nodes.iter()
-        .filter(|&node| key <= *node)
-        .min()
-        .unwrap_or(other_nodes.iter().min().unwrap())
-}
-
-fn get_node_address_for_key(key: &HashPos, node_summaries: &Vec<NodeSummaryMsg>) -> String {
-    node_summaries.iter()
-        .find(|node_summary| foo(node_summary.pos.clone().unwrap()).eq(key))
-        .unwrap()
-        .url
-        .clone()
+fn print_report(report: &ValidationReport) {
+    for malformed_summary in &report.malformed_summaries {
+        eprintln!("-----");
+        eprintln!("Node {}: Malformed summary! {}", malformed_summary.node, malformed_summary.reason);
+        eprintln!("-----");
+    }
+    for check in report.predecessors.iter().filter(|check| !check.correct) {
+        eprintln!("-----");
+        eprintln!("Node {} has wrong predecessor: {}, expected {}",
+                  check.node, check.predecessor.as_deref().unwrap_or("none"), check.expected_predecessor);
+        eprintln!("-----");
+    }
+    for wrong_finger in &report.wrong_fingers {
+        eprintln!("-----");
+        eprintln!("Node {}: Wrong finger entry! ", wrong_finger.node);
+        eprintln!("{}-th Finger {} points to node {} ", wrong_finger.index, wrong_finger.finger_key, wrong_finger.actual);
+        eprintln!("But node {} is responsible for {}", wrong_finger.expected, wrong_finger.finger_key);
+        eprintln!("-----");
+    }
+    for mismatch in &report.successor_list_mismatches {
+        eprintln!("-----");
+        eprintln!("Node {}: Wrong successor list! ", mismatch.node);
+        eprintln!("Actual {}-th successor address: {}, but was {}",
+                  mismatch.index + 1, mismatch.expected, mismatch.actual.as_deref().unwrap_or("none"));
+        eprintln!("-----");
+    }
 }
 
 async fn start_up_nodes(node_count: usize) -> (Vec<u16>, Vec<Child>) {
@@ -181,8 +154,3 @@ fn get_base_node_start_up_command(tcp_node_port: u16, grpc_node_port: u16, peer_
     }
         .expect("failed to start process")
 }
-
-fn foo(pos_msg: HashPosMsg) -> HashPos {
-    let bytes_b: [u8; std::mem::size_of::<HashPos>()] = pos_msg.key.try_into().unwrap();
-    HashPos::from_be_bytes(bytes_b)
-}
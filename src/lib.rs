@@ -1 +1,5 @@
 pub mod utils;
+
+pub mod chord_proto {
+    tonic::include_proto!("chord");
+}
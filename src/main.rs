@@ -1,28 +1,38 @@
 use std::error::Error;
 use std::process::exit;
 use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
 
 use actix_web::{App, get, HttpResponse, HttpServer, post, Responder, web};
-use log::{error, info};
+use clap::Parser;
+use log::{error, info, warn};
 use tokio::net::TcpListener;
 use tokio::sync::oneshot;
+use tokio::time::sleep;
 use tonic::transport::Server;
 
-use chord::utils::config::Config;
+use chord::utils;
+use chord::utils::config::{Cli, ClientConfig, Config};
+use chord::utils::constants::GRPC_DRAIN_TIMEOUT_MILLIS;
+use chord::utils::crypto::set_hash_salt;
+use chord::utils::tls::load_tls_configs;
 
+use crate::node::tls::set_client_tls_config;
+use crate::threads::announce::announce_periodically;
 use crate::threads::chord::{ChordService, connect_with_retry};
 use crate::threads::chord::chord_proto::chord_server::ChordServer;
-use crate::threads::client_api::handle_client_connection;
+use crate::threads::client_api::{handle_client_connection, run_client_command};
 use crate::threads::fix_fingers::fix_fingers_periodically;
 use crate::threads::health::check_predecessor_health_periodically;
+use crate::threads::partition::{detect_foreign_ring_periodically, first_reachable_seed_node};
+use crate::threads::replication::replicate_periodically;
 use crate::threads::setup::setup;
 use crate::threads::shutdown_handoff::shutdown_handoff;
 use crate::threads::stabilize::stabilize_periodically;
 use crate::threads::successor_list::check_successor_list_periodically;
-use crate::threads::web::index;
+use crate::threads::web::{index, kv_delete, kv_get, kv_put, metrics, ready};
 
 mod node;
-mod utils;
 mod threads;
 
 #[allow(warnings, unused, unused_imports, unused_import_braces, re)]
@@ -33,21 +43,87 @@ pub mod chord_proto {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let config = Config::load().unwrap();
+    let cli = Cli::parse();
+    if let Some(command) = cli.command.clone() {
+        // keys are hashed and nodes are reached like within the cluster, so the client needs the
+        // same salt and TLS settings before the command runs
+        let client_config = ClientConfig::load(&cli)?;
+        if let Some(cluster_salt) = &client_config.cluster_salt {
+            set_hash_salt(cluster_salt.as_bytes());
+        }
+        if let (Some(cert_path), Some(key_path), Some(ca_path)) = (&client_config.tls_cert, &client_config.tls_key, &client_config.tls_ca) {
+            let (_, client_tls_config) = load_tls_configs(cert_path, key_path, ca_path)?;
+            set_client_tls_config(client_tls_config);
+        }
+        match run_client_command(command).await {
+            Ok(output) => println!("{}", output),
+            Err(status) => {
+                eprintln!("Request failed: {}", status.message());
+                exit(1)
+            }
+        }
+        return Ok(());
+    }
+    let config = Config::load(cli)?;
 
     simple_logger::SimpleLogger::new()
         .env()
         .with_level(config.log_level_filter)
         .init()
         .unwrap();
+
+    // lets tokio-console attach to inspect task states and poll times, e.g. to find a task stuck on a lock
+    if config.profile {
+        #[cfg(feature = "profile")]
+        {
+            console_subscriber::init();
+            info!("Profiling enabled, attach with tokio-console");
+        }
+        #[cfg(not(feature = "profile"))]
+        log::warn!("Profiling requested, but the node was built without the profile feature");
+    }
     let config_clone = config.clone();
+    let grpc_service_config = config.clone();
+
+    // all nodes of a cluster need to share the same salt, otherwise they disagree on ring positions
+    if let Some(cluster_salt) = &config.cluster_salt {
+        set_hash_salt(cluster_salt.as_bytes());
+    }
+
+    // the client side is set globally, so every connection to other nodes uses https
+    let server_tls_config_option = match (&config.tls_cert, &config.tls_key, &config.tls_ca) {
+        (Some(cert_path), Some(key_path), Some(ca_path)) => {
+            let (server_tls_config, client_tls_config) = load_tls_configs(cert_path, key_path, ca_path)
+                .expect("Unable to load TLS certificates");
+            set_client_tls_config(client_tls_config);
+            info!("TLS enabled for the gRPC transport");
+            Some(server_tls_config)
+        }
+        _ => None
+    };
 
     let api_address = config.api_address;
     let p2p_address = config.p2p_address;
     let web_address = config.web_address;
+    let metrics_address_option = config.metrics_address.clone();
     let join_address_option = config.join_address;
+    let seed_nodes = config.seed_nodes.clone();
+    let partition_check_interval = Duration::from_millis(config.partition_check_interval_millis);
     let pow_difficulty = config.pow_difficulty;
-    let dev_mode = config.dev_mode;
+    let pow_threads = config.pow_threads;
+    let seed_all_fingers = config.seed_all_fingers;
+    let dump_on_panic_path_option = config.dump_on_panic.clone();
+    let data_dir_option = config.data_dir.clone();
+    let snapshot_interval = Duration::from_millis(config.snapshot_interval_millis);
+    let successor_grace_period = Duration::from_millis(config.successor_grace_period_millis);
+    let successor_stable_period = Duration::from_millis(config.successor_stable_period_millis);
+    let announce_interval = Duration::from_millis(config.announce_interval_millis);
+    let predecessor_failure_threshold = config.predecessor_failure_threshold;
+    let replication_interval = Duration::from_millis(config.replication_interval_millis);
+    let successor_list_length = config.successor_list_length;
+    let stabilize_interval = Duration::from_millis(config.stabilize_interval_millis);
+    let fix_fingers_interval = Duration::from_millis(config.fix_fingers_interval_millis);
+    let health_interval = Duration::from_millis(config.health_interval_millis);
 
     let mut thread_handles = Vec::new();
 
@@ -59,24 +135,33 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let cloned_grpc_addr_4 = p2p_address.clone();
     let cloned_grpc_addr_5 = p2p_address.clone();
     let cloned_grpc_addr_6 = p2p_address.clone();
-    let cloned_grpc_addr_7 = p2p_address.clone();
-    let own_grpc_address_8 = p2p_address.clone();
     let own_grpc_address_9 = p2p_address.clone();
+    let own_grpc_address_11 = p2p_address.clone();
+    let own_grpc_address_12 = p2p_address.clone();
 
     // tokio one-shot-channels used for communication between threads
     let (tx1, rx_grpc_service) = oneshot::channel();
-    let (tx2, rx_shutdown_handoff) = oneshot::channel();
+    let (tx_shutdown_handoff, rx_shutdown_handoff) = oneshot::channel();
     let (tx3, rx_check_predecessor) = oneshot::channel();
     let (tx4, rx_successor_list) = oneshot::channel();
     let (tx5, rx_web_interface) = oneshot::channel();
+    let (tx_grpc_shutdown, rx_grpc_shutdown) = oneshot::channel::<()>();
 
 
     // the main thread starts up all other threads and finally awaits them
 
+    let setup_seed_nodes = seed_nodes.clone();
     thread_handles.push(tokio::spawn(async move {
-        setup(join_address_option, &cloned_grpc_addr_1, tx1, tx2, tx3, tx4, tx5)
-            .await
-            .unwrap();
+        let join_address_option = match join_address_option {
+            Some(join_address) => Some(join_address),
+            None => first_reachable_seed_node(&setup_seed_nodes, &cloned_grpc_addr_1).await
+        };
+        // e.g. a rejected join, the other threads wait for the setup forever, so the node stops
+        if let Err(err) = setup(join_address_option, &cloned_grpc_addr_1, tx1, tx3, tx4, tx5, pow_difficulty, pow_threads, seed_all_fingers,
+                                dump_on_panic_path_option, data_dir_option, snapshot_interval, successor_list_length).await {
+            error!("Unable to set up the node: {}", err);
+            exit(1)
+        }
     }));
 
 
@@ -85,62 +170,134 @@ async fn main() -> Result<(), Box<dyn Error>> {
         let listener = TcpListener::bind(api_address).await.unwrap();
         loop {
             let grpc_address = cloned_grpc_addr_3.clone();
-            let (socket, _) = listener.accept().await.unwrap();
+            let socket = match listener.accept().await {
+                Ok((socket, _)) => socket,
+                Err(err) => {
+                    warn!("Unable to accept client connection: {}", err);
+                    continue;
+                }
+            };
             info!("New client connection established");
-            tokio::spawn(async move { handle_client_connection(socket, &grpc_address).await.unwrap() });
+            tokio::spawn(async move {
+                // a malformed message only closes the connection of the client that sent it
+                if let Err(err) = handle_client_connection(socket, &grpc_address).await {
+                    warn!("Closing client connection: {}", err);
+                }
+            });
         }
     }));
 
 
     thread_handles.push(tokio::spawn(async move {
-        let chord_service = ChordServer::new(ChordService::new(rx_grpc_service, &cloned_grpc_addr_2, pow_difficulty, dev_mode).await);
+        let chord_service = match ChordService::new(rx_grpc_service, &grpc_service_config).await {
+            Ok(chord_service) => Arc::new(chord_service),
+            Err(err) => {
+                error!("Unable to start the gRPC service: {}", err);
+                exit(1)
+            }
+        };
+        // leaving is not exposed as an rpc, the shutdown handoff calls it on the service directly
+        let _ = tx_shutdown_handoff.send(chord_service.clone());
+        chord_service.spawn_expiration_sweep();
+        // announcing makes the node mark neighbors as dead, so it runs on the service directly
+        tokio::spawn(announce_periodically(chord_service.clone(), announce_interval));
+        // partition detection makes the node rejoin, so it runs on the service directly as well
+        if !seed_nodes.is_empty() {
+            tokio::spawn(detect_foreign_ring_periodically(chord_service.clone(), partition_check_interval));
+        }
+        let chord_service = ChordServer::from_arc(chord_service);
         info!("Starting up gRPC service on {}", cloned_grpc_addr_2);
 
-        let reflection_service = tonic_reflection::server::Builder::configure()
-            .register_encoded_file_descriptor_set(chord_proto::FILE_DESCRIPTOR_SET)
-            .build()
-            .unwrap();
-        Server::builder()
+        // reflection exposes the whole service schema, so it can be turned off in production
+        let reflection_service_option = if grpc_service_config.reflection {
+            Some(tonic_reflection::server::Builder::configure()
+                .register_encoded_file_descriptor_set(chord_proto::FILE_DESCRIPTOR_SET)
+                .build()
+                .unwrap())
+        } else {
+            None
+        };
+        let mut server_builder = match server_tls_config_option {
+            Some(server_tls_config) => Server::builder().tls_config(server_tls_config).unwrap(),
+            None => Server::builder(),
+        };
+        server_builder
             .add_service(chord_service)
-            .add_service(reflection_service)
-            .serve(cloned_grpc_addr_2.parse().unwrap())
+            .add_optional_service(reflection_service_option)
+            // stops accepting connections once the shutdown handoff is done, in-flight rpcs complete
+            .serve_with_shutdown(cloned_grpc_addr_2.parse().unwrap(), async {
+                let _ = rx_grpc_shutdown.await;
+            })
             .await
             .unwrap();
+        info!("gRPC service drained, exiting");
+        exit(0)
     }));
 
 
     thread_handles.push(tokio::spawn(async move {
-        shutdown_handoff(own_grpc_address_8.clone(), rx_shutdown_handoff).await.unwrap();
+        // writes are rejected as unavailable from the start of the handoff on, so clients retry
+        // them on the new owner, the remaining rpcs are drained below
+        shutdown_handoff(rx_shutdown_handoff).await.unwrap();
+        let _ = tx_grpc_shutdown.send(());
+        // long running rpcs must not keep the node alive forever
+        sleep(Duration::from_millis(GRPC_DRAIN_TIMEOUT_MILLIS)).await;
+        warn!("gRPC service not drained after {} ms, exiting", GRPC_DRAIN_TIMEOUT_MILLIS);
         exit(0)
     }));
 
 
     thread_handles.push(tokio::spawn(async move {
-        fix_fingers_periodically(cloned_grpc_addr_4)
+        fix_fingers_periodically(cloned_grpc_addr_4, fix_fingers_interval)
+            .await
+    }));
+
+
+    thread_handles.push(tokio::spawn(async move {
+        stabilize_periodically(cloned_grpc_addr_5, stabilize_interval)
             .await
     }));
 
 
     thread_handles.push(tokio::spawn(async move {
-        stabilize_periodically(cloned_grpc_addr_5)
+        replicate_periodically(own_grpc_address_11, replication_interval)
             .await
     }));
 
 
     thread_handles.push(tokio::spawn(async move {
-        check_predecessor_health_periodically(cloned_grpc_addr_6, rx_check_predecessor)
+        check_predecessor_health_periodically(cloned_grpc_addr_6, rx_check_predecessor, predecessor_failure_threshold, health_interval)
             .await
     }));
 
 
     thread_handles.push(tokio::spawn(async move {
-        check_successor_list_periodically(cloned_grpc_addr_7, rx_successor_list)
+        check_successor_list_periodically(rx_successor_list, health_interval, successor_grace_period, successor_stable_period)
             .await
     }));
 
     // Setup for web interface
 
 
+    let serve_metrics_on_web_interface = metrics_address_option.is_none();
+    if let Some(metrics_address) = metrics_address_option {
+        thread_handles.push(tokio::spawn(async move {
+            info!("Starting up metrics endpoint on {}", metrics_address);
+            let server = HttpServer::new(move || {
+                App::new()
+                    .app_data(web::Data::new(own_grpc_address_12.clone()))
+                    .service(metrics)
+            })
+                .bind(metrics_address)
+                .unwrap()
+                .run();
+            if let Err(e) = server.await {
+                error!("Metrics server error: {}", e);
+            }
+        }));
+    }
+
+
     thread_handles.push(tokio::spawn(async move {
         info!("Starting up web interface  thread on {}", web_address);
         let finger_table_arc = rx_web_interface.await.unwrap();
@@ -150,6 +307,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .app_data(web::Data::new(config_clone.clone()))
                 .app_data(web::Data::new(own_grpc_address_9.clone()))
                 .service(index)
+                .service(ready)
+                .service(kv_get)
+                .service(kv_put)
+                .service(kv_delete)
+                .configure(|service_config| {
+                    if serve_metrics_on_web_interface {
+                        service_config.service(metrics);
+                    }
+                })
         })
             .bind(web_address)
             .unwrap()
@@ -0,0 +1,33 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::sync::Mutex;
+
+use crate::utils::time::now;
+use crate::utils::types::HashPos;
+
+/// Append-only audit trail of mutating operations, kept apart from the debug log. Every record is
+/// one tab separated line of timestamp (ms), operation, key hash and result. Raw keys are never
+/// written. Each line is synced to disk before the operation is acknowledged.
+#[derive(Debug)]
+pub struct AuditLog {
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    pub fn open(path: &str) -> io::Result<AuditLog> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(AuditLog { file: Mutex::new(file) })
+    }
+
+    pub fn record(&self, operation: &str, key_hash: HashPos, result: &str) -> io::Result<()> {
+        let line = format!("{}\t{}\t{:016x}\t{}\n", now().as_millis(), operation, key_hash, result);
+        let mut file_guard = self.file.lock().unwrap();
+        // a single write per line keeps concurrent appends from interleaving
+        file_guard.write_all(line.as_bytes())?;
+        file_guard.sync_data()
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tonic::{Code, Status};
+use tonic::transport::Channel;
+
+use crate::threads::chord::chord_proto::chord_client::ChordClient;
+use crate::threads::chord::connect_with_retry;
+use crate::utils::sync::LockOrRecover;
+use crate::utils::types::Address;
+
+/// Clients of other nodes by address. A tonic channel multiplexes all calls over one connection
+/// and is cheap to clone, so calls that fire repeatedly reuse it instead of connecting anew.
+/// Entries are evicted once a call fails on the connection or the node misses a heartbeat.
+#[derive(Default, Debug)]
+pub struct ClientPool {
+    clients: Mutex<HashMap<Address, ChordClient<Channel>>>,
+    /// clients cached since startup, each of them opened a new connection
+    connections_opened: AtomicU64,
+}
+
+impl ClientPool {
+    /// returns the cached client of the node, or connects to it and caches the new client
+    pub async fn get(&self, address: &Address) -> Result<ChordClient<Channel>, Status> {
+        if let Some(client) = self.cached(address) {
+            return Ok(client);
+        }
+        // the lock is not held while connecting, concurrent misses may both connect and the
+        // last one is kept
+        let client = connect_with_retry(address).await?;
+        self.insert(address, client.clone());
+        Ok(client)
+    }
+
+    pub fn cached(&self, address: &Address) -> Option<ChordClient<Channel>> {
+        self.clients.lock_or_recover().get(address).cloned()
+    }
+
+    /// caches a client that was connected outside of the pool
+    pub fn insert(&self, address: &Address, client: ChordClient<Channel>) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+        self.clients.lock_or_recover().insert(address.clone(), client);
+    }
+
+    pub fn evict(&self, address: &Address) {
+        self.clients.lock_or_recover().remove(address);
+    }
+
+    /// Evicts the client of the node if the call failed because the connection is broken, e.g.
+    /// as the node went down. Returns whether it was evicted.
+    pub fn evict_on_connection_error(&self, address: &Address, status: &Status) -> bool {
+        let connection_error = matches!(status.code(), Code::Unavailable | Code::Unknown | Code::DeadlineExceeded);
+        if connection_error {
+            self.evict(address);
+        }
+        connection_error
+    }
+
+    pub fn connections_opened(&self) -> u64 {
+        self.connections_opened.load(Ordering::Relaxed)
+    }
+}
@@ -1,9 +1,13 @@
+use std::mem::size_of;
+
 use crate::node::finger_entry::FingerEntry;
+use crate::node::stats::LatencyHistogram;
 use crate::node::successor_list::SuccessorList;
-use crate::threads::chord::chord_proto::{AddressMsg, FingerEntryDebugMsg, FingerEntryMsg, HashPosMsg, PowTokenMsg, SuccessorListMsg};
+use crate::threads::chord::chord_proto::{AddressMsg, FingerEntryDebugMsg, FingerEntryMsg, HashPosMsg, LatencyMsg, PowTokenMsg, SuccessorListMsg};
 use crate::utils::crypto;
+use crate::utils::error::ChordError;
 use crate::utils::proof_of_work::PowToken;
-use crate::utils::types::{Address, HashPos};
+use crate::utils::types::{Address, HashPos, Key};
 
 
 /// This file contains conversion implementations, mostly from proto-generated message structs to application structs
@@ -155,18 +159,6 @@ impl Into<HashPosMsg> for &HashPos {
 }
 
 
-impl Into<HashPos> for HashPosMsg {
-    fn into(self) -> HashPos {
-        HashPos::from_be_bytes(self.key.try_into().unwrap())
-    }
-}
-
-impl Into<HashPos> for &HashPosMsg {
-    fn into(self) -> HashPos {
-        self.clone().into()
-    }
-}
-
 impl Into<HashPosMsg> for &mut FingerEntry {
     fn into(self) -> HashPosMsg {
         HashPosMsg {
@@ -231,6 +223,7 @@ impl Into<SuccessorList> for SuccessorListMsg {
         SuccessorList {
             own_address: self.own_address.unwrap().into(),
             successors: self.successors.iter().map(|succ| succ.into()).collect(),
+            ..SuccessorList::default()
         }
     }
 }
@@ -254,3 +247,30 @@ impl Into<PowToken> for PowTokenMsg {
         }
     }
 }
+
+impl Into<LatencyMsg> for &LatencyHistogram {
+    fn into(self) -> LatencyMsg {
+        LatencyMsg {
+            count: self.count(),
+            p50_micros: self.percentile_micros(50.0),
+            p90_micros: self.percentile_micros(90.0),
+            p99_micros: self.percentile_micros(99.0),
+            max_micros: self.max_micros(),
+        }
+    }
+}
+
+/// converts a position received from a peer, a message of the wrong length is rejected instead of
+/// panicking
+pub fn try_into_hash_pos(hash_pos_msg: &HashPosMsg) -> Result<HashPos, ChordError> {
+    let bytes: [u8; size_of::<HashPos>()] = hash_pos_msg.key.as_slice().try_into()
+        .map_err(|_| ChordError::InvalidLength { field: "Position", expected: size_of::<HashPos>(), actual: hash_pos_msg.key.len() })?;
+    Ok(HashPos::from_be_bytes(bytes))
+}
+
+/// converts the key bytes received from a peer into a key, unless they have the wrong length
+pub fn try_into_key(key: Vec<u8>) -> Result<Key, ChordError> {
+    let actual = key.len();
+    key.try_into()
+        .map_err(|_| ChordError::InvalidLength { field: "Key", expected: size_of::<Key>(), actual })
+}
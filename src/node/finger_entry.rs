@@ -1,11 +1,11 @@
 use std::fmt::Debug;
 use std::fmt;
-use serde::Serialize;
-use crate::utils::types::{Address, HashPos};
+use serde::{Deserialize, Serialize};
+use crate::utils::types::{Address, HashPos, is_uninitialized};
 
 
 /// An entry in the FingerTable
-#[derive(Clone, Default, Serialize)]
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct FingerEntry {
     pub(crate) key: HashPos,
     pub(crate) address: Address,
@@ -39,4 +39,9 @@ impl FingerEntry {
     pub fn get_address_mut(&mut self) -> &mut Address {
         &mut self.address
     }
+
+    /// true as long as the entry doesn't point to a node
+    pub fn is_uninitialized(&self) -> bool {
+        is_uninitialized(&self.address)
+    }
 }
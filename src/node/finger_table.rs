@@ -1,25 +1,30 @@
 use std::fmt::Debug;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::utils::crypto::{HashRingKey};
+use crate::utils::crypto::{DefaultRing, RingParams};
 use crate::node::finger_entry::FingerEntry;
 use crate::utils::types::{Address, HashPos};
 
 
 /// The data structure that contains the routing information used for efficient node look up
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FingerTable {
     pub fingers: Vec<FingerEntry>,
 }
 
 impl FingerTable {
     pub fn new(key: &HashPos) -> FingerTable {
+        FingerTable::new_in_ring::<DefaultRing>(key)
+    }
+
+    /// builds the finger table of the node at key in a ring of the given width
+    pub fn new_in_ring<R: RingParams>(key: &HashPos) -> FingerTable {
         let mut fingers = Vec::new();
-        for i in 0..HashPos::finger_count() {
+        for i in 0..R::finger_count() {
             fingers.push(FingerEntry {
-                // key: (key + 2u128.pow(i as u32)) % 2u128.pow(finger_count as u32),
-                key: key.overflowing_add(HashPos::one().overflowing_shl(i as u32).0).0,
+                key: finger_position::<R>(key, i),
+                // uninitialized until fix_fingers resolves the responsible node
                 address: Address::default(),
             });
         };
@@ -28,4 +33,74 @@ impl FingerTable {
 
 }
 
+/// position of the i-th finger of the node at key: (key + 2^i) % 2^finger_count
+pub fn finger_position<R: RingParams>(key: &HashPos, i: usize) -> HashPos {
+    R::wrap(key.wrapping_add(R::one() << i))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::crypto::is_between;
+
+    use super::*;
+
+    struct SixteenBitRing;
+
+    impl RingParams for SixteenBitRing {
+        const BITS: u32 = 16;
+    }
+
+    #[test]
+    fn finger_positions_wrap_around_a_small_ring() {
+        let finger_table = FingerTable::new_in_ring::<SixteenBitRing>(&0xfff0);
+        let keys: Vec<HashPos> = finger_table.fingers.iter().map(|finger| finger.key).collect();
+        assert_eq!(keys.len(), 16);
+        assert_eq!(keys[0], 0xfff1);
+        assert_eq!(keys[3], 0xfff8);
+        // 0xfff0 + 2^4 wraps to the start of the ring
+        assert_eq!(keys[4], 0x0000);
+        assert_eq!(keys[15], 0x7ff0);
+        assert!(keys.iter().all(|key| *key <= SixteenBitRing::max()));
+    }
 
+    #[test]
+    fn positions_in_a_small_ring_stay_within_its_width() {
+        let node_pos = SixteenBitRing::hash(b"127.0.0.1:5601");
+        assert!(node_pos <= SixteenBitRing::max());
+        let finger_table = FingerTable::new_in_ring::<SixteenBitRing>(&node_pos);
+        // the first finger lies right behind the node, the last one half way around the ring
+        assert_eq!(finger_table.fingers[0].key, SixteenBitRing::wrap(node_pos + 1));
+        assert_eq!(finger_table.fingers[15].key, SixteenBitRing::wrap(node_pos + 0x8000));
+        // a key at distance d from the node lies between finger floor(log2(d)) and the next one
+        let key_pos = SixteenBitRing::hash(b"some key");
+        assert!(key_pos <= SixteenBitRing::max());
+        let distance = SixteenBitRing::wrap(key_pos.wrapping_sub(node_pos));
+        assert_ne!(distance, 0);
+        let index = (HashPos::BITS - 1 - distance.leading_zeros()) as usize;
+        let next_finger_key = finger_table.fingers.get(index + 1).map_or(node_pos, |finger| finger.key);
+        assert!(is_between(key_pos, finger_table.fingers[index].key, next_finger_key, false, true));
+        assert!(!is_between(key_pos, node_pos, finger_table.fingers[index].key, true, true));
+    }
+
+    #[test]
+    fn default_ring_keeps_the_full_width() {
+        assert_eq!(DefaultRing::max(), HashPos::MAX);
+        let finger_table = FingerTable::new(&HashPos::MAX);
+        assert_eq!(finger_table.fingers.len(), HashPos::BITS as usize);
+        assert_eq!(finger_table.fingers[0].key, 0);
+        assert_eq!(finger_table.fingers[63].key, HashPos::MAX.wrapping_add(1 << 63));
+    }
+
+    #[test]
+    fn populated_finger_table_survives_a_serde_round_trip() {
+        let mut finger_table = FingerTable::new(&0x1234);
+        for (i, finger) in finger_table.fingers.iter_mut().enumerate() {
+            finger.address = format!("127.0.0.1:{}", 5600 + i % 4);
+        }
+
+        let serialized = serde_json::to_string(&finger_table).unwrap();
+        let deserialized: FingerTable = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, finger_table);
+    }
+}
@@ -0,0 +1,155 @@
+use std::fs;
+use std::io;
+use std::panic;
+use std::sync::{Arc, Mutex, TryLockError};
+use std::time::Duration;
+
+use log::{debug, error, info};
+use prost::Message;
+use tokio::time::sleep;
+
+use crate::threads::chord::chord_proto::{KvBatchMsg, KvPairMsg};
+use crate::utils::sync::LockOrRecover;
+use crate::utils::time::has_expired;
+use crate::utils::types::{Key, KvStore};
+
+/// name of the snapshot file within the configured data dir
+pub const SNAPSHOT_FILE_NAME: &str = "kv_store.pb";
+
+/// Writes all pairs of the store to path as a single protobuf encoded KvBatchMsg. The dump is
+/// written to a temporary file first and then renamed, so an existing dump is never left half
+/// overwritten.
+pub fn write_kv_dump(kv_store: &KvStore, path: &str) -> io::Result<usize> {
+    let batch = KvBatchMsg {
+        pairs: kv_store.iter()
+            .map(|(key, (value, expiration_date))| KvPairMsg {
+                key: key.to_vec(),
+                value: value.clone(),
+                expiration_date: *expiration_date,
+            })
+            .collect(),
+    };
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, batch.encode_to_vec())?;
+    fs::rename(&tmp_path, path)?;
+    Ok(batch.pairs.len())
+}
+
+/// Reads a dump written by write_kv_dump. Pairs that expired in the meantime are dropped.
+pub fn read_kv_dump(path: &str) -> io::Result<KvStore> {
+    let batch = KvBatchMsg::decode(fs::read(path)?.as_slice())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let mut kv_store = KvStore::new();
+    for pair in batch.pairs {
+        if has_expired(&pair.expiration_date) {
+            continue;
+        }
+        let key: Key = pair.key.try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Key must be 32 bytes long"))?;
+        kv_store.insert(key, (pair.value, pair.expiration_date));
+    }
+    Ok(kv_store)
+}
+
+/// Snapshots the store to path every interval, so a restarted node can reload its pairs. The
+/// store is copied under the lock and written after releasing it.
+pub async fn snapshot_periodically(kv_store_arc: Arc<Mutex<KvStore>>, path: String, snapshot_interval: Duration) -> ! {
+    info!("Starting up periodic snapshot thread, writing to {}", path);
+    loop {
+        sleep(snapshot_interval).await;
+        let kv_store = kv_store_arc.lock_or_recover().clone();
+        match write_kv_dump(&kv_store, &path) {
+            Ok(pair_count) => debug!("Snapshotted {} pairs to {}", pair_count, path),
+            Err(err) => error!("Unable to snapshot kv_store to {}: {}", path, err),
+        }
+    }
+}
+
+/// Safety net for the many unwraps in the request handlers: whenever a thread panics, the store is
+/// dumped to path before the panic unwinds, so its data can be re-imported with the restore_dump tool.
+/// If the panicking thread itself holds the store lock, the store can't be dumped without
+/// deadlocking and the dump is skipped.
+pub fn dump_kv_store_on_panic(kv_store_arc: Arc<Mutex<KvStore>>, path: String) {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        previous_hook(panic_info);
+        dump_kv_store_without_blocking(&kv_store_arc, &path);
+    }));
+}
+
+/// Dumps the store unless it is locked, a lock poisoned by an earlier panic doesn't prevent the dump
+fn dump_kv_store_without_blocking(kv_store_arc: &Mutex<KvStore>, path: &str) {
+    let dump_result = match kv_store_arc.try_lock() {
+        Ok(kv_store_guard) => write_kv_dump(&kv_store_guard, path),
+        Err(TryLockError::Poisoned(poison_error)) => write_kv_dump(&poison_error.into_inner(), path),
+        Err(TryLockError::WouldBlock) => {
+            error!("Unable to dump kv_store on panic, it is locked");
+            return;
+        }
+    };
+    match dump_result {
+        Ok(pair_count) => info!("Dumped {} pairs to {} on panic", pair_count, path),
+        Err(err) => error!("Unable to dump kv_store to {} on panic: {}", path, err),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn store_poisoned_by_a_panic_produces_a_loadable_dump() {
+        let dump_path = std::env::temp_dir().join(format!("chord-dump-{}.pb", std::process::id()));
+        let dump_path = dump_path.to_str().unwrap().to_string();
+        let kv_store_arc = Arc::new(Mutex::new(KvStore::new()));
+        kv_store_arc.lock().unwrap().insert([1; 32], ("first".to_string(), 100));
+        kv_store_arc.lock().unwrap().insert([2; 32], ("second".to_string(), u64::MAX));
+        // the global panic hook is left alone, the dump function is called like the hook would
+        let kv_store_arc_clone = kv_store_arc.clone();
+        let _ = thread::spawn(move || {
+            let _kv_store_guard = kv_store_arc_clone.lock().unwrap();
+            panic!("deliberate panic while holding the store lock");
+        }).join();
+        assert!(kv_store_arc.is_poisoned());
+
+        dump_kv_store_without_blocking(&kv_store_arc, &dump_path);
+
+        let dump = KvBatchMsg::decode(fs::read(&dump_path).unwrap().as_slice()).unwrap();
+        fs::remove_file(&dump_path).unwrap();
+        let restored_kv_store: KvStore = dump.pairs.into_iter()
+            .map(|pair| (pair.key.try_into().unwrap(), (pair.value, pair.expiration_date)))
+            .collect();
+        assert_eq!(restored_kv_store, *kv_store_arc.lock_or_recover());
+    }
+
+    #[test]
+    fn locked_store_is_not_dumped() {
+        let dump_path = std::env::temp_dir().join(format!("chord-locked-dump-{}.pb", std::process::id()));
+        let dump_path = dump_path.to_str().unwrap().to_string();
+        let kv_store_arc = Arc::new(Mutex::new(KvStore::new()));
+
+        let _kv_store_guard = kv_store_arc.lock().unwrap();
+        dump_kv_store_without_blocking(&kv_store_arc, &dump_path);
+        assert!(!std::path::Path::new(&dump_path).exists());
+    }
+
+    #[test]
+    fn snapshot_is_reloaded_without_expired_pairs() {
+        let snapshot_path = std::env::temp_dir().join(format!("chord-snapshot-{}.pb", std::process::id()));
+        let snapshot_path = snapshot_path.to_str().unwrap().to_string();
+        let mut kv_store = KvStore::new();
+        kv_store.insert([1; 32], ("first".to_string(), u64::MAX));
+        kv_store.insert([2; 32], ("second".to_string(), u64::MAX - 1));
+        kv_store.insert([3; 32], ("expired".to_string(), 100));
+
+        assert_eq!(write_kv_dump(&kv_store, &snapshot_path).unwrap(), 3);
+        let reloaded_kv_store = read_kv_dump(&snapshot_path).unwrap();
+        fs::remove_file(&snapshot_path).unwrap();
+
+        kv_store.remove(&[3; 32]);
+        assert_eq!(reloaded_kv_store, kv_store);
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::utils::types::Address;
+
+/// Liveness information about other nodes, fed by the heartbeat gossip between neighbors. Nodes
+/// that could not be reached are marked dead, so lookups can skip them until the mark expires.
+#[derive(Default, Debug)]
+pub struct Liveness {
+    /// highest heartbeat sequence number received from each node and when it arrived
+    pub last_heartbeats: HashMap<Address, (u64, Instant)>,
+    /// nodes our heartbeat could not be delivered to and since when
+    pub dead_since: HashMap<Address, Instant>,
+}
+
+impl Liveness {
+    /// Records a heartbeat of the given node. Returns false for outdated heartbeats, i.e. those with
+    /// a sequence number that is not higher than the last one received from that node.
+    pub fn record_heartbeat(&mut self, address: &Address, sequence: u64) -> bool {
+        if let Some((last_sequence, _)) = self.last_heartbeats.get(address) {
+            if sequence <= *last_sequence {
+                return false;
+            }
+        }
+        self.last_heartbeats.insert(address.clone(), (sequence, Instant::now()));
+        self.mark_alive(address);
+        true
+    }
+
+    pub fn mark_alive(&mut self, address: &Address) {
+        self.dead_since.remove(address);
+    }
+
+    pub fn mark_dead(&mut self, address: &Address) {
+        self.dead_since.entry(address.clone()).or_insert_with(Instant::now);
+    }
+
+    /// dead marks expire after the given duration, as the node might have come back by then
+    pub fn is_dead(&self, address: &Address, expiry: Duration) -> bool {
+        self.dead_since.get(address)
+            .map(|dead_since| dead_since.elapsed() < expiry)
+            .unwrap_or(false)
+    }
+}
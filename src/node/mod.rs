@@ -2,3 +2,11 @@ pub mod finger_table;
 pub mod finger_entry;
 pub mod conversions;
 pub mod successor_list;
+pub mod stats;
+pub mod liveness;
+pub mod audit_log;
+pub mod kv_dump;
+pub mod replica_store;
+pub mod tls;
+pub mod client_pool;
+pub mod routing_snapshot;
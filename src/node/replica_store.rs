@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use crate::utils::crypto::{hash, is_between};
+use crate::utils::types::{Address, ExpirationDate, HashPos, Key, KvStore, Value};
+
+/// Copies of the pairs owned by the nodes preceding this node, kept per primary. Replicas are not
+/// served, they only become regular pairs once this node takes over the range of their primary.
+#[derive(Default, Debug)]
+pub struct ReplicaStore {
+    pub replicas_by_primary: HashMap<Address, KvStore>,
+}
+
+impl ReplicaStore {
+    /// replaces all replicas of the primary by its latest copy, so pairs the primary dropped in
+    /// the meantime are dropped here as well
+    pub fn replace(&mut self, primary: &Address, replicas: KvStore) {
+        self.replicas_by_primary.insert(primary.clone(), replicas);
+    }
+
+    /// stores a single replica of the primary, as forwarded by a replicated put
+    pub fn insert(&mut self, primary: &Address, key: Key, pair: (Value, ExpirationDate)) {
+        self.replicas_by_primary.entry(primary.clone()).or_default().insert(key, pair);
+    }
+
+    /// sets the expiration date of the primary's replica of the key, false if there is none
+    pub fn touch(&mut self, primary: &Address, key: &Key, expiration_date: ExpirationDate) -> bool {
+        match self.replicas_by_primary.get_mut(primary).and_then(|replicas| replicas.get_mut(key)) {
+            Some((_, stored_expiration_date)) => {
+                *stored_expiration_date = expiration_date;
+                true
+            }
+            None => false
+        }
+    }
+
+    /// removes the primary's replica of the key
+    pub fn remove(&mut self, primary: &Address, key: &Key) -> bool {
+        let removed = self.replicas_by_primary.get_mut(primary)
+            .is_some_and(|replicas| replicas.remove(key).is_some());
+        self.replicas_by_primary.retain(|_, replicas| !replicas.is_empty());
+        removed
+    }
+
+    /// removes and returns the replicas of all primaries whose keys lie within [lower, upper]
+    pub fn take_in_range(&mut self, lower: HashPos, upper: HashPos) -> KvStore {
+        let mut taken = KvStore::new();
+        for replicas in self.replicas_by_primary.values_mut() {
+            replicas.retain(|key, pair| {
+                if is_between(hash(key), lower, upper, false, false) {
+                    taken.insert(*key, pair.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.replicas_by_primary.retain(|_, replicas| !replicas.is_empty());
+        taken
+    }
+
+    pub fn len(&self) -> usize {
+        self.replicas_by_primary.values().map(|replicas| replicas.len()).sum()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_drops_replicas_the_primary_no_longer_has() {
+        let mut replica_store = ReplicaStore::default();
+        let primary = "127.0.0.1:6000".to_string();
+        replica_store.replace(&primary, KvStore::from([([1; 32], ("first".to_string(), u64::MAX)), ([2; 32], ("second".to_string(), u64::MAX))]));
+        replica_store.replace(&primary, KvStore::from([([2; 32], ("second".to_string(), u64::MAX))]));
+        assert_eq!(replica_store.len(), 1);
+
+        let taken = replica_store.take_in_range(HashPos::MIN, HashPos::MAX);
+        assert_eq!(taken.keys().collect::<Vec<_>>(), vec![&[2; 32]]);
+        assert_eq!(replica_store.len(), 0);
+    }
+}
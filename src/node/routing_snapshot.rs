@@ -0,0 +1,90 @@
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use crate::node::finger_table::FingerTable;
+use crate::node::successor_list::SuccessorList;
+use crate::utils::sync::LockOrRecover;
+
+/// name of the routing snapshot file within the configured data dir
+pub const ROUTING_SNAPSHOT_FILE_NAME: &str = "routing.json";
+
+/// Routing state of the last run. A restarted node starts out with it instead of resolving every
+/// finger again, fix_fingers and stabilize correct whatever changed in the meantime.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoutingSnapshot {
+    pub finger_table: FingerTable,
+    pub successor_list: SuccessorList,
+}
+
+impl RoutingSnapshot {
+    /// a snapshot only applies to the node that wrote it, in a ring of the same width
+    pub fn belongs_to(&self, own_address: &str, finger_table: &FingerTable) -> bool {
+        self.successor_list.own_address == own_address
+            && self.finger_table.fingers.len() == finger_table.fingers.len()
+            && self.finger_table.fingers.iter().zip(&finger_table.fingers).all(|(snapshot_finger, finger)| snapshot_finger.key == finger.key)
+    }
+}
+
+/// Writes the snapshot as JSON to a temporary file first and renames it, like write_kv_dump.
+pub fn write_routing_snapshot(routing_snapshot: &RoutingSnapshot, path: &str) -> io::Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, serde_json::to_vec(routing_snapshot)?)?;
+    fs::rename(&tmp_path, path)
+}
+
+pub fn read_routing_snapshot(path: &str) -> io::Result<RoutingSnapshot> {
+    serde_json::from_slice(&fs::read(path)?)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Snapshots finger table and successor list to path every interval. Both are copied under their
+/// lock, one after the other, and written after releasing them.
+pub async fn snapshot_routing_periodically(finger_table_arc: Arc<Mutex<FingerTable>>, successor_list_arc: Arc<Mutex<SuccessorList>>,
+                                           path: String, snapshot_interval: Duration) -> ! {
+    info!("Starting up periodic routing snapshot thread, writing to {}", path);
+    loop {
+        sleep(snapshot_interval).await;
+        let routing_snapshot = RoutingSnapshot {
+            finger_table: finger_table_arc.lock_or_recover().clone(),
+            successor_list: successor_list_arc.lock_or_recover().clone(),
+        };
+        match write_routing_snapshot(&routing_snapshot, &path) {
+            Ok(_) => debug!("Snapshotted routing state to {}", path),
+            Err(err) => error!("Unable to snapshot routing state to {}: {}", path, err),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_is_reloaded_for_the_same_node_only() {
+        let snapshot_path = std::env::temp_dir().join(format!("chord-routing-{}.json", std::process::id()));
+        let snapshot_path = snapshot_path.to_str().unwrap().to_string();
+        let mut finger_table = FingerTable::new(&0x1234);
+        finger_table.fingers[0].address = "127.0.0.1:5602".to_string();
+        let mut successor_list = SuccessorList::new(&"127.0.0.1:5601".to_string(), &"127.0.0.1:5602".to_string(), 3);
+        successor_list.successors.push("127.0.0.1:5603".to_string());
+        successor_list.frozen = true;
+
+        write_routing_snapshot(&RoutingSnapshot { finger_table: finger_table.clone(), successor_list }, &snapshot_path).unwrap();
+        let routing_snapshot = read_routing_snapshot(&snapshot_path).unwrap();
+        fs::remove_file(&snapshot_path).unwrap();
+
+        assert_eq!(routing_snapshot.finger_table, finger_table);
+        assert_eq!(routing_snapshot.successor_list.successors, vec!["127.0.0.1:5602", "127.0.0.1:5603"]);
+        assert!(!routing_snapshot.successor_list.frozen);
+        assert!(routing_snapshot.belongs_to("127.0.0.1:5601", &FingerTable::new(&0x1234)));
+        assert!(!routing_snapshot.belongs_to("127.0.0.1:5604", &FingerTable::new(&0x1234)));
+        assert!(!routing_snapshot.belongs_to("127.0.0.1:5601", &FingerTable::new(&0x4321)));
+    }
+}
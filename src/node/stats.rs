@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::utils::constants::RING_CHANGE_WINDOW_MILLIS;
+
+/// Counters about the node's operation that are exposed to operators via the get_stats RPC.
+/// All counters are cumulative since startup.
+#[derive(Default, Debug)]
+pub struct NodeStats {
+    /// key and value bytes sent to other nodes during notify data handoff
+    pub handoff_bytes_sent: AtomicU64,
+    /// key and value bytes received from other nodes via notify or handoff
+    pub handoff_bytes_received: AtomicU64,
+    /// latency of find_successor calls this node answered itself
+    pub local_lookup_latency: LatencyHistogram,
+    /// latency of find_successor calls forwarded to other nodes
+    pub forwarded_lookup_latency: LatencyHistogram,
+    /// notify handoffs currently waiting for one of the max_concurrent_handoffs slots
+    pub handoffs_queued: AtomicU64,
+    /// changes of the predecessor within the last RING_CHANGE_WINDOW_MILLIS
+    pub predecessor_changes: Mutex<ChangeWindow>,
+    /// get requests answered by this node, including those of expired keys
+    pub get_total: AtomicU64,
+    /// get requests that found an expired pair
+    pub get_expired_total: AtomicU64,
+    pub put_total: AtomicU64,
+    /// runs of fix_fingers, each fixes a single finger
+    pub fix_fingers_iterations_total: AtomicU64,
+    pub stabilize_total: AtomicU64,
+    /// expired pairs the last expiration sweep found, it removed them unless serve_stale is set
+    pub expired_kv_store_size: AtomicU64,
+}
+
+impl NodeStats {
+    pub fn record_handoff_sent(&self, key: &[u8], value: &str) {
+        self.handoff_bytes_sent.fetch_add(pair_size(key, value), Ordering::Relaxed);
+    }
+
+    pub fn record_handoff_received(&self, key: &[u8], value: &str) {
+        self.handoff_bytes_received.fetch_add(pair_size(key, value), Ordering::Relaxed);
+    }
+}
+
+fn pair_size(key: &[u8], value: &str) -> u64 {
+    (key.len() + value.len()) as u64
+}
+
+/// Times of the changes within the last RING_CHANGE_WINDOW_MILLIS, older changes are forgotten.
+/// Frequent changes of a node's neighbors indicate an unstable ring.
+#[derive(Default, Debug, Clone)]
+pub struct ChangeWindow {
+    changes: VecDeque<Instant>,
+}
+
+impl ChangeWindow {
+    pub fn record(&mut self) {
+        let window = Duration::from_millis(RING_CHANGE_WINDOW_MILLIS);
+        while self.changes.front().is_some_and(|change| change.elapsed() > window) {
+            self.changes.pop_front();
+        }
+        self.changes.push_back(Instant::now());
+    }
+
+    pub fn count(&self) -> usize {
+        let window = Duration::from_millis(RING_CHANGE_WINDOW_MILLIS);
+        self.changes.iter().filter(|change| change.elapsed() <= window).count()
+    }
+}
+
+const LATENCY_BUCKET_COUNT: usize = 32;
+
+/// Latency histogram with exponentially growing buckets, so memory stays fixed no matter how many
+/// latencies are recorded. Bucket 0 counts latencies below 1µs, bucket i > 0 those in
+/// [2^(i-1), 2^i) µs. The last bucket also holds everything above its lower bound.
+#[derive(Debug)]
+pub struct LatencyHistogram {
+    buckets: [AtomicU64; LATENCY_BUCKET_COUNT],
+    max_micros: AtomicU64,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            max_micros: AtomicU64::new(0),
+        }
+    }
+}
+
+impl LatencyHistogram {
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        self.buckets[bucket_index(micros)].fetch_add(1, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|bucket| bucket.load(Ordering::Relaxed)).sum()
+    }
+
+    pub fn max_micros(&self) -> u64 {
+        self.max_micros.load(Ordering::Relaxed)
+    }
+
+    pub fn bucket_count(&self, index: usize) -> u64 {
+        self.buckets[index].load(Ordering::Relaxed)
+    }
+
+    /// Upper bound in µs of the bucket that contains the given percentile (0-100), never larger
+    /// than the maximum recorded latency. Returns 0 if nothing was recorded.
+    pub fn percentile_micros(&self, percentile: f64) -> u64 {
+        let count = self.count();
+        if count == 0 {
+            return 0;
+        }
+        let rank = ((percentile / 100.0) * count as f64).ceil().max(1.0) as u64;
+        let mut cumulative_count = 0;
+        for (index, bucket) in self.buckets.iter().enumerate() {
+            cumulative_count += bucket.load(Ordering::Relaxed);
+            if cumulative_count >= rank && index < LATENCY_BUCKET_COUNT - 1 {
+                return bucket_upper_bound(index).min(self.max_micros());
+            }
+        }
+        self.max_micros()
+    }
+}
+
+fn bucket_index(micros: u64) -> usize {
+    ((u64::BITS - micros.leading_zeros()) as usize).min(LATENCY_BUCKET_COUNT - 1)
+}
+
+fn bucket_upper_bound(index: usize) -> u64 {
+    (1u64 << index) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recorded_latencies_populate_expected_buckets() {
+        let histogram = LatencyHistogram::default();
+        histogram.record(Duration::from_nanos(500));
+        histogram.record(Duration::from_micros(1));
+        histogram.record(Duration::from_micros(3));
+        histogram.record(Duration::from_micros(100));
+        histogram.record(Duration::from_secs(100_000));
+
+        assert_eq!(histogram.bucket_count(0), 1);
+        assert_eq!(histogram.bucket_count(1), 1);
+        assert_eq!(histogram.bucket_count(2), 1);
+        // 64µs <= 100µs < 128µs
+        assert_eq!(histogram.bucket_count(7), 1);
+        assert_eq!(histogram.bucket_count(LATENCY_BUCKET_COUNT - 1), 1);
+        assert_eq!(histogram.count(), 5);
+
+        assert_eq!(histogram.percentile_micros(50.0), 3);
+        assert_eq!(histogram.percentile_micros(80.0), 127);
+        assert_eq!(histogram.percentile_micros(100.0), 100_000_000_000);
+    }
+}
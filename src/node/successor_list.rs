@@ -1,30 +1,137 @@
-use crate::utils::types::Address;
+use std::collections::HashMap;
+use std::iter::once;
+use std::time::{Duration, Instant};
 
-pub const SUCCESSOR_LIST_SIZE: usize = 3;
+use serde::{Deserialize, Serialize};
 
-/// Instead of only the direct successor, each node stores the urls of its 3 next successors.
-/// This makes the cluster more robust and allows it to continue running even if 2 direct neighbor 
-/// fail at the same time.
+use crate::node::stats::ChangeWindow;
+use crate::utils::constants::SUCCESSOR_LIST_LENGTH_DEFAULT;
+use crate::utils::types::Address;
 
-#[derive(Default, Debug, Clone)]
+/// Instead of only the direct successor, each node stores the urls of its next successors, 3 by
+/// default. This makes the cluster more robust and allows it to continue running even if all but
+/// one of them fail at the same time.
+/// Only the successors are persisted in routing snapshots, the health check and testing state
+/// starts out fresh.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SuccessorList {
     pub own_address: Address,
     pub successors: Vec<Address>,
+    /// maximum number of successors kept, at least 1
+    pub length: usize,
+    /// set by the force_successor testing RPCs, a frozen list is not touched by stabilization
+    #[serde(skip)]
+    pub frozen: bool,
+    /// time of the first missed health check of each successor, cleared once it answers again
+    #[serde(skip)]
+    pub suspected_since: HashMap<Address, Instant>,
+    /// time at which a successor got demoted after missing health checks for the grace period
+    #[serde(skip)]
+    pub demoted_at: HashMap<Address, Instant>,
+    /// changes of the first successor, whoever made them
+    #[serde(skip)]
+    pub successor_changes: ChangeWindow,
+}
+
+impl Default for SuccessorList {
+    fn default() -> Self {
+        SuccessorList {
+            own_address: Address::default(),
+            successors: Vec::new(),
+            length: SUCCESSOR_LIST_LENGTH_DEFAULT,
+            frozen: false,
+            suspected_since: HashMap::new(),
+            demoted_at: HashMap::new(),
+            successor_changes: ChangeWindow::default(),
+        }
+    }
 }
 
 impl SuccessorList {
-    pub fn new(own_address: &Address, direct_successor: &Address) -> Self {
+    pub fn new(own_address: &Address, direct_successor: &Address, length: usize) -> Self {
         SuccessorList {
             own_address: own_address.clone(),
             successors: vec![direct_successor.clone()],
+            length: length.max(1),
+            frozen: false,
+            suspected_since: HashMap::new(),
+            demoted_at: HashMap::new(),
+            successor_changes: ChangeWindow::default(),
+        }
+    }
+
+    pub fn set_first_successor(&mut self, address: &Address) {
+        let previous_successor = self.successors.first().cloned();
+        self.successors[0] = address.clone();
+        self.record_first_successor_change(previous_successor);
+    }
+
+    fn record_first_successor_change(&mut self, previous_successor: Option<Address>) {
+        if self.successors.first() != previous_successor.as_ref() {
+            self.successor_changes.record();
         }
     }
 
+    /// Replaces the list by the successor followed by the successor's own list, up to length
+    /// entries. In a ring with fewer nodes than that, the list ends once it wrapped around to this
+    /// node, so no node is listed twice.
     pub fn update_with_other_succ_list(&mut self, other_list: SuccessorList) -> () {
-        self.successors = other_list.successors.clone();
-        self.successors.insert(0, other_list.own_address.clone());
-        if self.successors.len() > SUCCESSOR_LIST_SIZE {
-            self.successors.pop();
+        if self.frozen {
+            return;
+        }
+        let previous_successor = self.successors.first().cloned();
+        let mut successors: Vec<Address> = Vec::new();
+        for successor in once(other_list.own_address).chain(other_list.successors) {
+            if successors.contains(&successor) {
+                continue;
+            }
+            let wrapped_around = successor == self.own_address;
+            successors.push(successor);
+            if wrapped_around || successors.len() >= self.length.max(1) {
+                break;
+            }
+        }
+        self.successors = successors;
+        self.record_first_successor_change(previous_successor);
+    }
+
+    /// Records a missed health check of the given successor. Returns true if the successor has been
+    /// failing for at least the grace period, in which case it is demoted.
+    pub fn suspect(&mut self, address: &Address, grace_period: Duration) -> bool {
+        let suspected_since = *self.suspected_since.entry(address.clone()).or_insert_with(Instant::now);
+        if suspected_since.elapsed() < grace_period {
+            return false;
+        }
+        self.suspected_since.remove(address);
+        self.demoted_at.insert(address.clone(), Instant::now());
+        true
+    }
+
+    pub fn clear_suspicion(&mut self, address: &Address) {
+        self.suspected_since.remove(address);
+    }
+
+    /// a demoted successor is only promoted again once the stable period since its demotion passed
+    pub fn is_quarantined(&self, address: &Address, stable_period: Duration) -> bool {
+        self.demoted_at.get(address)
+            .map(|demoted_at| demoted_at.elapsed() < stable_period)
+            .unwrap_or(false)
+    }
+
+    /// Removes quarantined successors from the list, unless that would leave the list empty.
+    pub fn drop_quarantined(&mut self, stable_period: Duration) {
+        if self.frozen {
+            return;
+        }
+        self.demoted_at.retain(|_, demoted_at| demoted_at.elapsed() < stable_period);
+        let promotable: Vec<Address> = self.successors.iter()
+            .filter(|successor| !self.is_quarantined(successor, stable_period))
+            .cloned()
+            .collect();
+        if !promotable.is_empty() {
+            let previous_successor = self.successors.first().cloned();
+            self.successors = promotable;
+            self.record_first_successor_change(previous_successor);
         }
     }
 }
@@ -0,0 +1,63 @@
+use std::sync::OnceLock;
+
+use tonic::transport::ClientTlsConfig;
+
+/// TLS settings used for all outbound connections to other nodes. They are set once at startup if
+/// TLS is configured, otherwise nodes talk plain http
+static CLIENT_TLS_CONFIG: OnceLock<ClientTlsConfig> = OnceLock::new();
+
+pub fn set_client_tls_config(client_tls_config: ClientTlsConfig) {
+    CLIENT_TLS_CONFIG.set(client_tls_config).expect("Client TLS config can only be set once");
+}
+
+pub fn client_tls_config() -> Option<&'static ClientTlsConfig> {
+    CLIENT_TLS_CONFIG.get()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::Request;
+    use tonic::transport::Server;
+
+    use crate::threads::chord::chord_proto::chord_server::ChordServer;
+    use crate::threads::chord::chord_proto::Empty;
+    use crate::threads::chord::connect_with_tls;
+    use crate::threads::chord::tests::new_test_service;
+    use crate::utils::tls::load_tls_configs;
+
+    use super::*;
+
+    const CERT_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test/certs/node.pem");
+    const KEY_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test/certs/node.key");
+    const CA_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test/certs/ca.pem");
+
+    #[tokio::test]
+    async fn health_call_succeeds_over_tls() {
+        let (server_tls_config, client_tls_config) = load_tls_configs(CERT_PATH, KEY_PATH, CA_PATH).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (service, _node) = new_test_service(&address, &address).await;
+        tokio::spawn(async move {
+            Server::builder()
+                .tls_config(server_tls_config)
+                .unwrap()
+                .add_service(ChordServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        });
+
+        let mut client = connect_with_tls(&address, Some(&client_tls_config)).await.unwrap();
+        client.health(Request::new(Empty {})).await.unwrap();
+
+        // peers that don't speak TLS are turned away
+        let plain_health_result = match connect_with_tls(&address, None).await {
+            Ok(mut plain_client) => plain_client.health(Request::new(Empty {})).await.map(|_| ()),
+            Err(err) => Err(tonic::Status::unavailable(err.to_string())),
+        };
+        assert!(plain_health_result.is_err());
+    }
+}
@@ -0,0 +1,17 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::info;
+use tokio::time::sleep;
+
+use crate::threads::chord::ChordService;
+
+/// periodic heartbeat gossip: makes the local node push a heartbeat to its predecessor and
+/// successors every interval, so failed neighbors are noticed before the next stabilize cycle
+pub async fn announce_periodically(chord_service: Arc<ChordService>, announce_interval: Duration) -> ! {
+    info!("Starting up periodic announce thread");
+    loop {
+        chord_service.announce().await;
+        sleep(announce_interval).await;
+    }
+}
@@ -1,27 +1,48 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::mem::size_of;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use log::{debug, error, info, warn};
-use tokio::sync::mpsc;
+use rand::Rng;
+use tokio::sync::{mpsc, RwLock, RwLockReadGuard, Semaphore};
 use tokio::sync::oneshot::Receiver;
-use tokio::time::sleep;
+use tokio::time::{sleep, sleep_until, timeout};
 use tokio_stream::Stream;
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tonic::{Request, Response, Status, Streaming};
-use tonic::transport::Channel;
+use tonic::{Code, Request, Response, Status, Streaming};
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::transport::{Channel, ClientTlsConfig};
 
-use chord::utils::types::{Address, HashPos, Key, KvStore};
+use chord::utils::config::{Config, FingerFixStrategy, KeyEncoding, LookupStrategy};
+use chord::utils::types::{Address, HashPos, is_uninitialized, Key, KvStore, Value};
 
+use crate::node::audit_log::AuditLog;
+use crate::node::client_pool::ClientPool;
+use crate::node::conversions::{try_into_hash_pos, try_into_key};
 use crate::node::finger_entry::FingerEntry;
-use crate::node::finger_table::FingerTable;
+use crate::node::finger_table::{finger_position, FingerTable};
+use crate::node::liveness::Liveness;
+use crate::node::replica_store::ReplicaStore;
+use crate::node::stats::NodeStats;
 use crate::node::successor_list::SuccessorList;
-use crate::threads::chord::chord_proto::{AddressMsg, Empty, FingerEntryMsg, GetKvStoreDataResponse, GetKvStoreSizeResponse, GetPredecessorResponse, GetRequest, GetResponse, GetStatus, HashPosMsg, KvPairDebugMsg, KvPairMsg, NodeSummaryMsg, NotifyRequest, PowTokenMsg, PutRequest, SuccessorListMsg};
+use crate::node::tls::client_tls_config;
+use crate::threads::chord::chord_proto::{AddressListMsg, AddressMsg, ClusterInfoMsg, CompactResponse, DeleteIfRequest, DeleteIfResponse, DeleteRequest, DeleteResponse, DeleteStatus, Empty, FindSuccessorResponse, FingerEntryMsg, GetKvStoreDataResponse, HeartbeatMsg, KvBatchMsg, GetKvStoreSizeResponse, ReplicaBatchMsg, GetPredecessorResponse, GetRequest, GetResponse, GetStatus, HandoffAckMsg, HashPosListMsg, HashPosMsg, JoinRequest, JoinResponse, KvPairDebugMsg, LeaveNoticeMsg, KvPairMsg, LinkRttMsg, NodeSummaryMsg, NotifyRequest, PingMsg, PowTokenMsg, PutIfRequest, PutIfResponse, PutManyRequest, PutManyResponse, PutRequest, PutResponse, TouchRequest, TouchResponse, TouchStatus, TtlUnit, ReadinessResponse, ReverseLookupResponse, ScanPageRequest, ScanPageResponse, ScanRequest, SelfCheckResponse, StatsMsg, SuccessorListMsg, SuccessorTimerMsg, TraceLookupResponse};
 use crate::threads::chord::chord_proto::chord_client::ChordClient;
-use crate::utils::constants::DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE;
-use crate::utils::crypto::{hash, HashRingKey, is_between};
+use crate::threads::chord::chord_proto::chord_server::Chord;
+use crate::threads::expiration::sweep_expired_periodically;
+use crate::threads::shutdown_handoff::handoff_to_first_available_successor;
+use crate::utils::constants::{DEAD_MARK_EXPIRY_MILLIS, DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE, NOTIFY_RATE_THRESHOLD, NOTIFY_RATE_WINDOW_MILLIS, POW_DIFFICULTY_MAX, RPC_TIMEOUT_MILLIS};
+use crate::utils::crypto::{DefaultRing, hash, hash_salt_fingerprint, is_between, RingParams};
+use crate::utils::error::ChordError;
 use crate::utils::proof_of_work::PowToken;
-use crate::utils::time::{has_expired, now};
+use crate::utils::sync::LockOrRecover;
+use crate::utils::time::{has_expired, now_millis};
+use crate::utils::tls::connect_channel;
 use crate::utils::types::ExpirationDate;
 
 pub mod chord_proto {
@@ -30,6 +51,11 @@ pub mod chord_proto {
 
 
 /// The struct representing the running node. 
+///
+/// Lock order: whenever more than one of the locks below is held at the same time, they are
+/// acquired in the order finger_table -> predecessor_option -> successor_list -> liveness -> kv_store.
+/// kv_store is always the innermost lock, so no code path waits on another lock while holding it.
+/// replica_store, link_rtts and pending_handoffs are never held together with any other lock.
 pub struct ChordService {
     /// gRPC address of the node
     address: String,
@@ -43,31 +69,118 @@ pub struct ChordService {
     kv_store: Arc<Mutex<KvStore>>,
     /// round-robin pointer to the current finger updated by the fix_finger procedure  
     fix_finger_index: Arc<Mutex<usize>>,
+    /// decides which finger fix_fingers refreshes next
+    finger_fix_strategy: FingerFixStrategy,
+    /// decides whether lookups are forwarded recursively or resolved iteratively by this node
+    lookup_strategy: LookupStrategy,
     /// list of the next n successor
     successor_list: Arc<Mutex<SuccessorList>>,
-    /// required number of trailing 0 bytes for a POW token to be valid
-    pow_difficulty: usize,
+    /// required number of trailing 0 bytes for a POW token to be valid, raised during notify floods
+    pow_difficulty: Arc<Mutex<usize>>,
+    /// configured difficulty, used for this node's own tokens and as the lower bound of pow_difficulty
+    base_pow_difficulty: usize,
+    /// times of the notify calls within the last NOTIFY_RATE_WINDOW_MILLIS
+    notify_calls: Arc<Mutex<VecDeque<Instant>>>,
+    /// number of parallel workers searching a POW token
+    pow_threads: usize,
     /// flag that enables debugging RPCs
-    dev_mode: bool
+    dev_mode: bool,
+    /// upper bound for the ttl of stored pairs, larger ttls are clamped
+    max_ttl: Option<u64>,
+    /// upper bound for the size of stored values in bytes, larger puts are rejected
+    max_value_bytes: usize,
+    /// operational counters exposed via get_stats
+    stats: Arc<NodeStats>,
+    /// time a demoted successor has to be stable before it may become successor again
+    successor_stable_period: Duration,
+    /// liveness of other nodes as learned from the heartbeat gossip
+    liveness: Arc<Mutex<Liveness>>,
+    /// sequence number of the next heartbeat this node sends
+    heartbeat_sequence: Arc<AtomicU64>,
+    /// interval between two heartbeats, also bounds the time a single heartbeat may take
+    announce_interval: Duration,
+    /// maximum number of pairs packed into a single handoff stream message
+    handoff_batch_size: usize,
+    /// time a successor gets to accept all pairs when this node leaves, before the next one is tried
+    handoff_deadline: Duration,
+    /// limits the number of notify handoffs running at the same time, further handoffs are queued
+    handoff_semaphore: Arc<Semaphore>,
+    max_concurrent_handoffs: usize,
+    /// keep serving locally stored pairs while no predecessor is known, e.g. during a partition
+    serve_when_isolated: bool,
+    /// fetch gets for keys of other nodes from the responsible node instead of failing
+    proxy_reads: bool,
+    /// store puts for keys of other nodes on the responsible node instead of locally
+    proxy_writes: bool,
+    /// keep expired pairs on get, so they are served with the expired status until compacted
+    serve_stale: bool,
+    /// pause between two expiration sweeps
+    expiration_sweep_interval: Duration,
+    /// rendering of keys in debug output
+    key_encoding: KeyEncoding,
+    /// append-only record of mutating operations, only kept if configured
+    audit_log: Option<AuditLog>,
+    /// copies of the pairs owned by the preceding nodes, promoted once this node takes over their range
+    replica_store: Arc<Mutex<ReplicaStore>>,
+    /// connected clients reused by lookups and stabilization
+    client_pool: Arc<ClientPool>,
+    /// last round-trip time measured by stabilize to each node of the successor list
+    link_rtts: Arc<Mutex<HashMap<Address, Duration>>>,
+    /// nodes whose ring this node rejoins if it finds itself in a separate ring
+    seed_nodes: Vec<Address>,
+    /// keys sent by notify handoffs that the receiver didn't acknowledge yet, with the receiver.
+    /// They are tentative: kept until the ack, but not sent to the same receiver again
+    pending_handoffs: Arc<Mutex<HashMap<Key, Address>>>,
+    /// set once the node starts to leave the ring, writes hold a read guard while they are applied
+    leaving: Arc<RwLock<bool>>,
 }
 
 const MAX_RETRIES: u64 = 15;
 const CONNECTION_RETRY_SLEEP: u64 = 100;
 
+/// response metadata of find_successor that counts how often the lookup was forwarded
+const HOPS_METADATA_KEY: &str = "chord-hops";
+
+/// request metadata of key-addressed requests that a node forwarded to the responsible node
+const FORWARDED_METADATA_KEY: &str = "chord-forwarded";
+
+/// metadata of a pow token rejection that carries the difficulty the node currently requires
+const POW_DIFFICULTY_METADATA_KEY: &str = "chord-pow-difficulty";
+
+/// unpacks a forwarded find_successor response and adds the hop to the next node, nodes that
+/// don't report hops count as a single hop
+fn into_address_and_hops(response: Response<AddressMsg>) -> (AddressMsg, u32) {
+    let hops = response.metadata().get(HOPS_METADATA_KEY)
+        .and_then(|hops| hops.to_str().ok())
+        .and_then(|hops| hops.parse::<u32>().ok())
+        .unwrap_or(0);
+    (response.into_inner(), hops + 1)
+}
+
 /// connection helper functions
 
+/// all outbound connections go through here, so they use https as soon as TLS is configured
 pub(crate) async fn connect(address: &Address) -> Result<ChordClient<Channel>, tonic::transport::Error> {
-    ChordClient::connect(format!("http://{}", address)).await
+    connect_with_tls(address, client_tls_config()).await
+}
+
+pub(crate) async fn connect_with_tls(address: &Address, tls_config_option: Option<&ClientTlsConfig>) -> Result<ChordClient<Channel>, tonic::transport::Error> {
+    Ok(ChordClient::new(connect_channel(address, tls_config_option).await?))
 }
 
-pub(crate) async fn connect_without_retry(address: &Address) -> ChordClient<Channel> {
-    connect(address).await.unwrap()
+/// connects to the address, giving up once the timeout passed
+pub(crate) async fn connect_with_timeout(address: &Address, connect_timeout: Duration) -> Result<ChordClient<Channel>, Status> {
+    match timeout(connect_timeout, connect(address)).await {
+        Ok(Ok(client)) => Ok(client),
+        Ok(Err(err)) => Err(ChordError::from(err).into()),
+        Err(_) => Err(Status::deadline_exceeded(format!("Connecting to {} timed out", address)))
+    }
 }
 
 pub(crate) async fn connect_with_retry(address: &Address) -> Result<ChordClient<Channel>, Status> {
     let mut retries = 0;
     loop {
-        match connect(address).await {
+        match connect_with_timeout(address, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
             Ok(client) => return Ok(client),
             Err(e) => {
                 retries += 1;
@@ -83,7 +196,52 @@ pub(crate) async fn connect_with_retry(address: &Address) -> Result<ChordClient<
     }
 }
 
+fn nanos_since_epoch() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
+}
+
+/// measures the round-trip time to the node by sending it a timestamp that it echoes back
+pub(crate) async fn ping(client: &mut ChordClient<Channel>) -> Result<Duration, Status> {
+    let ping_msg = PingMsg { timestamp_nanos: nanos_since_epoch() };
+    let echoed_timestamp_nanos = with_rpc_timeout(client.ping(request_with_timeout(ping_msg))).await?
+        .into_inner()
+        .timestamp_nanos;
+    Ok(Duration::from_nanos(nanos_since_epoch().saturating_sub(echoed_timestamp_nanos)))
+}
+
+/// packs the pairs into stream messages of at most batch_size pairs each
+pub(crate) fn into_batches(pairs: Vec<KvPairMsg>, batch_size: usize) -> Vec<KvBatchMsg> {
+    pairs.chunks(batch_size.max(1))
+        .map(|chunk| KvBatchMsg { pairs: chunk.to_vec() })
+        .collect()
+}
+
+/// Continuation token of scan_page: the position of the last returned pair followed by its key
+pub(crate) fn encode_continuation_token(key: &Key) -> Vec<u8> {
+    [hash(key).to_be_bytes().as_slice(), key.as_slice()].concat()
+}
+
+/// reverses encode_continuation_token, rejecting tokens of any other layout
+pub(crate) fn decode_continuation_token(token: &[u8]) -> Result<(HashPos, Key), Status> {
+    let invalid_token = || Status::invalid_argument("Invalid continuation token");
+    if token.len() != size_of::<HashPos>() + size_of::<Key>() {
+        return Err(invalid_token());
+    }
+    let (pos_bytes, key_bytes) = token.split_at(size_of::<HashPos>());
+    let pos = HashPos::from_be_bytes(pos_bytes.try_into().map_err(|_| invalid_token())?);
+    let key: Key = key_bytes.try_into().map_err(|_| invalid_token())?;
+    Ok((pos, key))
+}
+
+/// Connects to the first reachable address of the list. Every address gets a single attempt
+/// first, so dead nodes at the front of the list don't use up the whole retry budget before a live
+/// node further back is tried. Only once all of them failed, they are retried one after another.
 pub(crate) async fn connect_to_first_reachable_node(address_list: &Vec<Address>) -> Option<(ChordClient<Channel>, Address)> {
+    for address in address_list {
+        if let Ok(successor_client) = connect_with_timeout(address, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
+            return Some((successor_client, address.clone()));
+        }
+    };
     for address in address_list {
         if let Ok(successor_client) = connect_with_retry(address).await {
             return Some((successor_client, address.clone()));
@@ -93,435 +251,4038 @@ pub(crate) async fn connect_to_first_reachable_node(address_list: &Vec<Address>)
 }
 
 
+/// remaining time until the deadline the caller set via the grpc-timeout header, if any
+fn remaining_time_until_deadline<T>(request: &Request<T>) -> Option<Duration> {
+    let grpc_timeout = request.metadata().get("grpc-timeout")?.to_str().ok()?;
+    let (value, unit) = grpc_timeout.split_at(grpc_timeout.len().checked_sub(1)?);
+    let value: u64 = value.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(value * 3600)),
+        "M" => Some(Duration::from_secs(value * 60)),
+        "S" => Some(Duration::from_secs(value)),
+        "m" => Some(Duration::from_millis(value)),
+        "u" => Some(Duration::from_micros(value)),
+        "n" => Some(Duration::from_nanos(value)),
+        _ => None
+    }
+}
+
+/// wraps the message into a request that carries the time left until the deadline, or the
+/// default rpc timeout if there is none
+fn request_with_deadline<T>(message: T, deadline_option: Option<Instant>) -> Request<T> {
+    let mut request = Request::new(message);
+    match deadline_option {
+        Some(deadline) => request.set_timeout(deadline.saturating_duration_since(Instant::now())),
+        None => request.set_timeout(Duration::from_millis(RPC_TIMEOUT_MILLIS)),
+    }
+    request
+}
+
+/// wraps the message into a request that carries the default rpc timeout, so the peer abandons
+/// the call once nobody waits for it anymore
+pub(crate) fn request_with_timeout<T>(message: T) -> Request<T> {
+    request_with_deadline(message, None)
+}
+
+/// request for the responsible node, marked as forwarded so it isn't passed on again
+fn forwarded_request<T>(message: T) -> Request<T> {
+    let mut request = request_with_timeout(message);
+    request.metadata_mut().insert(FORWARDED_METADATA_KEY, MetadataValue::from_static("true"));
+    request
+}
+
+/// Awaits an outbound call for at most the default rpc timeout. A peer that accepts the
+/// connection but never answers fails the call with deadline_exceeded instead of blocking the
+/// caller forever.
+pub(crate) async fn with_rpc_timeout<T>(call: impl Future<Output=Result<T, Status>>) -> Result<T, Status> {
+    with_rpc_deadline(call, None).await
+}
+
+/// Awaits an outbound call until the deadline, or for the default rpc timeout if there is none.
+/// The grpc-timeout of the request runs out at the same time at the earliest. The timer is
+/// polled first, so the call fails with deadline_exceeded rather than with tonic's cancellation.
+async fn with_rpc_deadline<T>(call: impl Future<Output=Result<T, Status>>, deadline_option: Option<Instant>) -> Result<T, Status> {
+    let deadline = deadline_option.unwrap_or_else(|| Instant::now() + Duration::from_millis(RPC_TIMEOUT_MILLIS));
+    tokio::select! {
+        biased;
+        _ = sleep_until(deadline.into()) => Err(Status::deadline_exceeded("Outbound call timed out")),
+        result = call => result,
+    }
+}
+
+/// rejects the request unless it carries an unexpired pow token of the required difficulty that
+/// was generated for the caller's address
+fn validate_pow_token(pow_token_msg_option: Option<PowTokenMsg>, caller_address: &Address, required_pow_difficulty: usize) -> Result<(), Status> {
+    let pow_token: PowToken = pow_token_msg_option
+        .ok_or(Status::invalid_argument("Pow token required"))?
+        .into();
+    let (has_expired, valid) = pow_token.validate(caller_address, required_pow_difficulty);
+    if has_expired {
+        return Err(Status::cancelled("Pow token expired"))
+    }
+    if !valid {
+        let mut metadata = MetadataMap::new();
+        metadata.insert(POW_DIFFICULTY_METADATA_KEY, (required_pow_difficulty as u32).into());
+        let message = format!("Invalid pow token for {}, difficulty {} required: {}", caller_address, required_pow_difficulty, pow_token);
+        return Err(Status::with_metadata(Code::Cancelled, message, metadata))
+    }
+    Ok(())
+}
+
+/// Returns the difficulty a node reported when rejecting a pow token, if it lies above the
+/// difficulty the token was generated with. The caller regenerates its token at that level.
+pub(crate) fn raised_pow_difficulty(status: &Status, pow_difficulty: usize) -> Option<usize> {
+    let required_pow_difficulty: usize = status.metadata().get(POW_DIFFICULTY_METADATA_KEY)?
+        .to_str().ok()?
+        .parse().ok()?;
+    // no node raises the difficulty above POW_DIFFICULTY_MAX, a larger one isn't worth the work
+    (required_pow_difficulty > pow_difficulty && required_pow_difficulty <= POW_DIFFICULTY_MAX).then_some(required_pow_difficulty)
+}
+
+/// Joins the ring at the node behind the client. The pow token is regenerated as long as the
+/// node reports a raised difficulty during a notify flood.
+pub(crate) async fn join_with_pow(successor_client: &mut ChordClient<Channel>, own_address: &Address, pow_difficulty: usize,
+                                  pow_threads: usize) -> Result<JoinResponse, Status> {
+    let mut pow_difficulty = pow_difficulty;
+    loop {
+        let join_result = successor_client.join(Request::new(JoinRequest {
+            address: Some(own_address.clone().into()),
+            pow_token: Some(PowToken::generate(own_address, pow_difficulty, pow_threads).into()),
+        })).await;
+        match join_result {
+            Ok(response) => return Ok(response.into_inner()),
+            Err(status) => match raised_pow_difficulty(&status, pow_difficulty) {
+                Some(required_pow_difficulty) => {
+                    info!("Join requires pow difficulty {}, regenerating the token", required_pow_difficulty);
+                    pow_difficulty = required_pow_difficulty;
+                }
+                None => return Err(status)
+            }
+        }
+    }
+}
+
+/// Whether a request with the given remote address arrived from the host of the given node
+/// address. Requests aren't signed, so this only ties a claimed address to the sender's IP, with
+/// TLS only nodes holding a certificate of the cluster CA connect at all. Calls that didn't arrive
+/// over the network have no remote address and are accepted.
+fn sent_from(remote_addr_option: Option<SocketAddr>, address: &Address) -> bool {
+    match remote_addr_option {
+        Some(remote_addr) => address.parse::<SocketAddr>().is_ok_and(|socket_addr| socket_addr.ip() == remote_addr.ip()),
+        None => true
+    }
+}
+
 impl ChordService {
-    pub async fn new(rx: Receiver<(Arc<Mutex<FingerTable>>, Arc<Mutex<Option<FingerEntry>>>, Arc<Mutex<KvStore>>, Arc<Mutex<SuccessorList>>)>, url: &String, pow_difficulty: usize, dev_mode: bool) -> ChordService {
+    pub async fn new(rx: Receiver<(Arc<Mutex<FingerTable>>, Arc<Mutex<Option<FingerEntry>>>, Arc<Mutex<KvStore>>, Arc<Mutex<SuccessorList>>)>, config: &Config) -> Result<ChordService, ChordError> {
         let (finger_table_arc, predecessor_option_arc, kv_store_arc, successor_list_arc) = rx.await.unwrap();
-        ChordService {
-            address: url.clone(),
-            pos: hash(&url.as_bytes()),
+        Ok(ChordService {
+            address: config.p2p_address.clone(),
+            pos: hash(&config.p2p_address.as_bytes()),
             finger_table: finger_table_arc,
             predecessor_option: predecessor_option_arc,
             kv_store: kv_store_arc,
             fix_finger_index: Arc::new(Mutex::new(0)),
+            finger_fix_strategy: config.finger_fix_strategy,
+            lookup_strategy: config.lookup_strategy,
             successor_list: successor_list_arc,
-            pow_difficulty,
-            dev_mode
-        }
-    }
-
-    pub async fn get_successor_address(&self) -> Address {
-        self.successor_list.lock().unwrap().successors[0].clone()
-    }
-
-    pub async fn set_successor(&self, new_successor_address: &Address) -> () {
-        self.successor_list.lock().unwrap().successors[0] = new_successor_address.clone();
-        self.finger_table.lock().unwrap().fingers[0].address = new_successor_address.clone();
+            pow_difficulty: Arc::new(Mutex::new(config.pow_difficulty)),
+            base_pow_difficulty: config.pow_difficulty,
+            notify_calls: Arc::new(Mutex::new(VecDeque::new())),
+            pow_threads: config.pow_threads,
+            dev_mode: config.dev_mode,
+            max_ttl: config.max_ttl,
+            max_value_bytes: config.max_value_bytes,
+            stats: Arc::new(NodeStats::default()),
+            successor_stable_period: Duration::from_millis(config.successor_stable_period_millis),
+            liveness: Arc::new(Mutex::new(Liveness::default())),
+            heartbeat_sequence: Arc::new(AtomicU64::new(0)),
+            announce_interval: Duration::from_millis(config.announce_interval_millis),
+            handoff_batch_size: config.handoff_batch_size,
+            handoff_deadline: Duration::from_millis(config.handoff_deadline_millis),
+            handoff_semaphore: Arc::new(Semaphore::new(config.max_concurrent_handoffs)),
+            max_concurrent_handoffs: config.max_concurrent_handoffs,
+            serve_when_isolated: config.serve_when_isolated,
+            proxy_reads: config.proxy_reads,
+            proxy_writes: config.proxy_writes,
+            serve_stale: config.serve_stale,
+            expiration_sweep_interval: Duration::from_millis(config.expiration_sweep_interval_millis),
+            key_encoding: config.key_encoding,
+            audit_log: config.audit_log.as_ref()
+                .map(|audit_log_path| AuditLog::open(audit_log_path))
+                .transpose()?,
+            replica_store: Arc::new(Mutex::new(ReplicaStore::default())),
+            client_pool: Arc::new(ClientPool::default()),
+            link_rtts: Arc::new(Mutex::new(HashMap::new())),
+            seed_nodes: config.seed_nodes.clone(),
+            pending_handoffs: Arc::new(Mutex::new(HashMap::new())),
+            leaving: Arc::new(RwLock::new(false)),
+        })
     }
 
+    /// Makes the caller the new predecessor if it lies between the current predecessor and this
+    /// node. Returns the previous predecessor and the range (lower, upper] the caller takes over,
+    /// or None if the caller doesn't become the predecessor.
+    fn update_predecessor_to(&self, caller_address: &Address) -> Option<(Option<FingerEntry>, HashPos, HashPos)> {
+        let caller_pos = hash(caller_address.as_bytes());
+        let mut predecessor_option_guard = self.predecessor_option.lock_or_recover();
 
-    pub async fn get_client_for_closest_successor(&self) -> (ChordClient<Channel>, Address) {
-        let successors = {
-            self.successor_list.lock().unwrap().successors.clone()
+        let (lower, upper) = match *predecessor_option_guard {
+            Some(ref prev_predecessor) => {
+                let lower = hash(prev_predecessor.address.as_bytes());
+                if !is_between(caller_pos, lower.wrapping_add(1), self.pos, false, true) {
+                    return None;
+                }
+                (lower, caller_pos)
+            }
+            None => (self.pos.wrapping_add(1), caller_pos)
         };
-        if let Some(client_and_address) = connect_to_first_reachable_node(&successors).await {
-            return client_and_address;
-        } else {
-            panic!("All successor in successor list are unreachable")
+
+        let prev_predecessor = predecessor_option_guard.replace(FingerEntry {
+            key: caller_pos,
+            address: caller_address.clone(),
+        });
+        if prev_predecessor.as_ref().map(|predecessor| &predecessor.address) != Some(caller_address) {
+            self.stats.predecessor_changes.lock_or_recover().record();
         }
+        debug!("Updated predecessor to {}", caller_address);
+        Some((prev_predecessor, lower, upper))
     }
 
-    pub async fn get_predecessor_client(&self) -> Option<ChordClient<Channel>> {
-        let predecessor_option_clone = {
-            self.predecessor_option.lock().unwrap().clone()
+    /// Graceful leave, called by the node itself on shutdown: all pairs are handed over to the
+    /// first successor that accepts them, then successor and predecessor are told to link up with
+    /// each other. The node keeps running, it is up to the caller to stop it. Not exposed as an
+    /// RPC, so no one else can make the node hand away its pairs.
+    pub(crate) async fn leave(&self) -> Result<(), Status> {
+        // no write is admitted from now on, and the write lock is only granted once the writes in
+        // flight are applied, so the handoff below sees all of them
+        *self.leaving.write().await = true;
+        let predecessor_option = self.predecessor_option.lock_or_recover().clone();
+        let successors: Vec<Address> = {
+            let mut successors = self.successor_list.lock_or_recover().successors.clone();
+            successors.retain(|successor| *successor != self.address);
+            successors.dedup();
+            successors
         };
-        if let Some(ref predecessor) = predecessor_option_clone {
-            Some(connect_with_retry(&predecessor.address).await.unwrap())
-        } else {
-            None
+        if successors.is_empty() {
+            info!("Leaving as the last node of the ring, no one takes over the pairs");
+            return Ok(());
         }
-    }
-}
 
+        let handed_over_pairs: Vec<(Key, (Value, ExpirationDate))> = self.kv_store.lock_or_recover().iter()
+            .map(|(key, pair)| (*key, pair.clone()))
+            .collect();
+        let pairs: Vec<KvPairMsg> = handed_over_pairs.iter()
+            .map(|(key, (value, expiration_date))| KvPairMsg {
+                key: key.to_vec(),
+                value: value.clone(),
+                expiration_date: *expiration_date,
+            })
+            .collect();
+        let new_owner = handoff_to_first_available_successor(&successors, pairs, self.handoff_deadline, self.handoff_batch_size)
+            .await
+            .ok_or(Status::unavailable("No successor accepted the handoff"))?;
+        // pairs written during the handoff were not handed over and are kept
+        {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            for (key, handed_over_pair) in handed_over_pairs {
+                if kv_store_guard.get(&key) == Some(&handed_over_pair) {
+                    kv_store_guard.remove(&key);
+                }
+            }
+        }
 
-#[tonic::async_trait]
-impl chord_proto::chord_server::Chord for ChordService {
-    
-    /// finds the next responsible node for a given position in the hash ring
-    async fn find_successor(
-        &self,
-        request: Request<chord_proto::HashPosMsg>,
-    ) -> Result<Response<chord_proto::AddressMsg>, Status> {
-        let key: HashPos = request.into_inner().into();
-
-        let direct_successor_address = self.get_successor_address().await;
-        let successor_pos: HashPos = hash(direct_successor_address.as_bytes());
-        let key_pos_msg: HashPosMsg = HashPosMsg {
-            key: key.to_be_bytes().to_vec()
+        let leave_notice = LeaveNoticeMsg {
+            leaving: Some(self.address.clone().into()),
+            predecessor: predecessor_option.as_ref().map(|predecessor| predecessor.address.clone().into()),
+            successor: Some(new_owner.clone().into()),
         };
+        let mut neighbors = vec![new_owner];
+        if let Some(predecessor) = predecessor_option {
+            if predecessor.address != self.address && !neighbors.contains(&predecessor.address) {
+                neighbors.push(predecessor.address);
+            }
+        }
+        for neighbor in neighbors {
+            let notice_result = match connect_with_timeout(&neighbor, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
+                Ok(mut neighbor_client) => with_rpc_timeout(neighbor_client.leave_notice(request_with_timeout(leave_notice.clone()))).await,
+                Err(status) => Err(status),
+            };
+            // stabilization repairs the ring anyway, the notice only speeds it up
+            if let Err(status) = notice_result {
+                warn!("Unable to notify {} about leaving: {}", neighbor, status);
+            }
+        }
+        info!("Left the ring");
+        Ok(())
+    }
 
-        let successor_address_msg: AddressMsg = if is_between(key, self.pos + 1, successor_pos, false, false) {
-            direct_successor_address.into()
-        } else {
-            let closest_preceding_node_address = self.find_closest_preceding_finger(Request::new(key_pos_msg.clone()))
-                .await
-                .unwrap().into_inner();
-
-            match connect_with_retry(&closest_preceding_node_address.address).await {
-                Ok(mut closest_preceding_node_client) => {
-                    closest_preceding_node_client.find_successor(Request::new(key.into()))
-                        .await?
-                        .into_inner()
+    /// Checks whether this node got separated from the rest of the cluster, e.g. because its join
+    /// peer became unreachable during join and it started a ring of its own. Asks each reachable
+    /// seed node for the successor of this node's position. In the seed's ring that is either this
+    /// node or a node that knows this node as its predecessor from a join or notify. Otherwise the
+    /// node joins the seed's ring at that successor, with a pow token and the handoff of its range
+    /// like on startup, stabilize then merges the two rings. Returns whether the node rejoined.
+    /// Not exposed as an RPC, only the node's partition detection runs it.
+    pub(crate) async fn detect_foreign_ring(&self) -> Result<bool, Status> {
+        for seed_address in self.seed_nodes.iter().filter(|seed_address| **seed_address != self.address) {
+            let mut seed_client = match connect_with_timeout(seed_address, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
+                Ok(seed_client) => seed_client,
+                Err(status) => {
+                    debug!("Seed node {} unreachable: {}", seed_address, status);
+                    continue;
                 }
+            };
+            let own_pos_msg: HashPosMsg = self.pos.into();
+            let seed_ring_successor: Address = match with_rpc_timeout(seed_client.find_successor(request_with_timeout(own_pos_msg))).await {
+                Ok(response) => response.into_inner().into(),
                 Err(status) => {
-                    // if node returned by closest_preceding_node_address is unavailable, delegate find_successor call to predecessor
-                    let mut counter = 0;
-                    loop {
-                        if let Some(mut predecessor_client) = self.get_predecessor_client().await {
-                            return predecessor_client.find_successor(Request::new(key.into())).await;
-                        }
-                        if counter > 20 {
-                            return Err(status);
-                        }
-                        counter += 1;
-                    }
+                    debug!("Seed node {} failed to look up this node's position: {}", seed_address, status);
+                    continue;
                 }
+            };
+            // the seed's ring routes to this node, stabilize repairs the rest
+            if seed_ring_successor == self.address {
+                return Ok(false);
+            }
+            let mut successor_client = match self.client_pool.get(&seed_ring_successor).await {
+                Ok(successor_client) => successor_client,
+                Err(status) => {
+                    debug!("Successor {} in the ring of seed node {} unreachable: {}", seed_ring_successor, seed_address, status);
+                    continue;
+                }
+            };
+            let successor_predecessor_option: Option<Address> = match with_rpc_timeout(successor_client.get_predecessor(request_with_timeout(Empty {}))).await {
+                Ok(response) => response.into_inner().address_optional.map(|address_msg| address_msg.into()),
+                Err(status) => {
+                    self.client_pool.evict_on_connection_error(&seed_ring_successor, &status);
+                    debug!("Unable to get the predecessor of {}: {}", seed_ring_successor, status);
+                    continue;
+                }
+            };
+            if successor_predecessor_option.as_ref() == Some(&self.address) {
+                return Ok(false);
             }
-        };
-
-        debug!("Received find_successor call for {:?}, successor is {:?}", key, successor_address_msg);
-        Ok(Response::new(successor_address_msg))
-    }
-
 
-    /// returns current node's value of the predecessor handle
-    async fn get_predecessor(&self, _request: Request<Empty>) -> Result<Response<GetPredecessorResponse>, Status> {
-        let predecessor = match *self.predecessor_option.lock().unwrap() {
-            Some(ref predecessor) => {
-                debug!("Received get predecessor call, predecessor is {:?}", predecessor.address);
-                predecessor.address.clone()
+            warn!("Node is not part of the ring of seed node {}, rejoining with successor {}", seed_address, seed_ring_successor);
+            let join_response = join_with_pow(&mut successor_client, &self.address, self.base_pow_difficulty, self.pow_threads)
+                .await
+                .inspect_err(|status| { self.client_pool.evict_on_connection_error(&seed_ring_successor, status); })?;
+            if let Some(predecessor_address_msg) = join_response.predecessor {
+                let predecessor_address: Address = predecessor_address_msg.into();
+                *self.predecessor_option.lock_or_recover() = Some(predecessor_address.into());
             }
-            None => {
-                debug!("Received get predecessor call, predecessor is Nil");
-                Address::default()
+            let mut received_keys = Vec::new();
+            {
+                let mut kv_store_guard = self.kv_store.lock_or_recover();
+                for pair in join_response.pairs {
+                    let key: Key = try_into_key(pair.key)?;
+                    self.stats.record_handoff_received(&key, &pair.value);
+                    kv_store_guard.insert(key, (pair.value, pair.expiration_date));
+                    received_keys.push(key.to_vec());
+                }
             }
-        };
-        Ok(Response::new(GetPredecessorResponse { address_optional: Some(predecessor.into()) }))
+            let handoff_ack = HandoffAckMsg { keys: received_keys, address: Some(self.address.clone().into()), rejected_keys: vec![] };
+            if let Err(status) = with_rpc_timeout(successor_client.ack_handoff(request_with_timeout(handoff_ack))).await {
+                warn!("Unable to acknowledge the join handoff to {}: {}", seed_ring_successor, status);
+            }
+            self.set_successor(&seed_ring_successor).await;
+            return Ok(true);
+        }
+        Ok(false)
     }
 
-    /// returns the current node's successor list
-    async fn get_successor_list(&self, _: Request<Empty>) -> Result<Response<SuccessorListMsg>, Status> {
-        Ok(Response::new(self.successor_list.lock().unwrap().clone().into()))
-    }
+    /// Pushes a heartbeat to the predecessor and all successors. Neighbors that don't accept it
+    /// within the announce interval are marked dead in the liveness cache. Not exposed as an RPC,
+    /// only the node's announce thread runs it.
+    pub(crate) async fn announce(&self) {
+        let mut neighbors = self.successor_list.lock_or_recover().successors.clone();
+        if let Some(predecessor) = self.predecessor_option.lock_or_recover().clone() {
+            neighbors.push(predecessor.address);
+        }
+        neighbors.retain(|neighbor| !neighbor.eq(&self.address));
+        neighbors.sort();
+        neighbors.dedup();
 
+        let heartbeat = HeartbeatMsg {
+            address: self.address.clone(),
+            sequence: self.heartbeat_sequence.fetch_add(1, Ordering::Relaxed),
+        };
+        let handles: Vec<_> = neighbors.into_iter()
+            .map(|neighbor| {
+                let heartbeat = heartbeat.clone();
+                let announce_interval = self.announce_interval;
+                tokio::spawn(async move {
+                    let delivered = timeout(announce_interval, async {
+                        connect(&neighbor).await.ok()?.heartbeat(Request::new(heartbeat)).await.ok()
+                    }).await;
+                    (neighbor, matches!(delivered, Ok(Some(_))))
+                })
+            })
+            .collect();
 
-    /// find the finger in the finger table that closest precedes the hash position given in the request
-    async fn find_closest_preceding_finger(&self, request: Request<HashPosMsg>) -> Result<Response<FingerEntryMsg>, Status> {
-        let key = HashPos::from_be_bytes(request.get_ref().clone().key.try_into().unwrap());
-        for finger in self.finger_table.lock().unwrap().fingers.iter().rev() {
-            if finger.address.eq(&Address::default()) {
-                // ignore yet uninitialized entries
-                continue;
-            }
-            let node_pos = hash(finger.get_address().as_bytes());
-            if is_between(node_pos, self.pos, key, true, true) {
-                return Ok(Response::new(FingerEntryMsg {
-                    id: node_pos.to_be_bytes().to_vec(),
-                    address: finger.clone().get_address().into(),
-                }));
+        for handle in handles {
+            // a failed heartbeat task says nothing about the neighbor, it is checked again next time
+            let (neighbor, delivered) = match handle.await {
+                Ok(neighbor_and_delivered) => neighbor_and_delivered,
+                Err(err) => {
+                    error!("Heartbeat task failed: {}", err);
+                    continue;
+                }
+            };
+            let mut liveness_guard = self.liveness.lock_or_recover();
+            if delivered {
+                liveness_guard.mark_alive(&neighbor);
+            } else {
+                warn!("Heartbeat to {} failed, marking it as dead", neighbor);
+                liveness_guard.mark_dead(&neighbor);
+                self.client_pool.evict(&neighbor);
             }
         }
-        Ok(Response::new(FingerEntryMsg {
-            id: self.pos.to_be_bytes().to_vec(),
-            address: self.address.clone().into(),
-        }))
     }
 
-    /// returns a human readable node summary (requires dev_mode = true)
-    async fn get_node_summary(&self, _: Request<Empty>) -> Result<Response<NodeSummaryMsg>, Status> {
-        if !self.dev_mode {
-            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+    /// Starts the periodic expiration sweep of kv_store, with serve_stale it only counts the
+    /// expired pairs for get_stats
+    pub(crate) fn spawn_expiration_sweep(&self) {
+        tokio::spawn(sweep_expired_periodically(self.kv_store.clone(), self.expiration_sweep_interval, !self.serve_stale, self.stats.clone()));
+    }
+
+    /// Admits a write unless the node is leaving. The guard has to be held until the write is
+    /// applied to kv_store.
+    async fn admit_write(&self) -> Result<RwLockReadGuard<'_, bool>, Status> {
+        let leaving_guard = self.leaving.read().await;
+        if *leaving_guard {
+            return Err(Status::unavailable("Node is leaving the ring"));
         }
-        let finger_table_guard = self.finger_table.lock().unwrap();
-        let predecessor_option = self.predecessor_option.lock().unwrap();
-        let successor_list = self.successor_list.lock().unwrap();
+        Ok(leaving_guard)
+    }
 
-        Ok(Response::new(NodeSummaryMsg {
-            url: self.address.clone(),
-            pos: Some(self.pos.into()),
-            predecessor: match predecessor_option.clone() {
-                Some(predecessor) => Some(predecessor.into()),
-                None => None
-            },
-            finger_entries: finger_table_guard.fingers.iter()
-                .map(|finger| finger.clone())
-                .map(|finger| finger.into())
-                .collect(),
-            successor_list: Some(successor_list.clone().into()),
-        }))
+    /// the pow difficulty notify and join currently require
+    fn required_pow_difficulty(&self) -> usize {
+        self.adapt_pow_difficulty(false)
     }
-    
-    /// returns the number of key value pairs stored in storage (dev_mode = true)
-    async fn get_kv_store_size(&self, _: Request<Empty>) -> Result<Response<GetKvStoreSizeResponse>, Status> {
-        if !self.dev_mode {
-            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+
+    /// Records a notify call that passed validation. Rejected calls are not counted, otherwise
+    /// anyone could raise the difficulty with invalid tokens that cost nothing to generate.
+    fn record_notify_call(&self) {
+        self.adapt_pow_difficulty(true);
+    }
+
+    /// Adapts the required pow difficulty to the notify rate: every NOTIFY_RATE_THRESHOLD calls
+    /// within the window raise it by one above the configured difficulty, up to POW_DIFFICULTY_MAX.
+    /// As old calls leave the window it drops back.
+    fn adapt_pow_difficulty(&self, record_call: bool) -> usize {
+        let window = Duration::from_millis(NOTIFY_RATE_WINDOW_MILLIS);
+        let notify_call_count = {
+            let mut notify_calls_guard = self.notify_calls.lock_or_recover();
+            while notify_calls_guard.front().is_some_and(|call| call.elapsed() > window) {
+                notify_calls_guard.pop_front();
+            }
+            if record_call {
+                notify_calls_guard.push_back(Instant::now());
+            }
+            notify_calls_guard.len()
+        };
+
+        let pow_difficulty = (self.base_pow_difficulty + notify_call_count / NOTIFY_RATE_THRESHOLD)
+            .min(POW_DIFFICULTY_MAX.max(self.base_pow_difficulty));
+        let mut pow_difficulty_guard = self.pow_difficulty.lock_or_recover();
+        if *pow_difficulty_guard != pow_difficulty {
+            info!("{} notify calls within {:?}, changed pow difficulty from {} to {}", notify_call_count, window, *pow_difficulty_guard, pow_difficulty);
+            *pow_difficulty_guard = pow_difficulty;
         }
-        Ok(Response::new(GetKvStoreSizeResponse {
-            size: self.kv_store.lock().unwrap().len() as u32
-        }))
+        pow_difficulty
     }
 
-    /// returns the full data stored in storage (dev_mode = true)
-    async fn get_kv_store_data(&self, _: Request<Empty>) -> Result<Response<GetKvStoreDataResponse>, Status> {
-        if !self.dev_mode {
-            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+    /// Pings the successor and records the round-trip time. Nodes that left the successor list
+    /// are dropped, so the recorded times only cover the current neighbors.
+    async fn measure_rtt(&self, successor_client: &mut ChordClient<Channel>, successor_address: &Address) {
+        let rtt_result = ping(successor_client).await;
+        let successors = self.successor_list.lock_or_recover().successors.clone();
+        let mut link_rtts_guard = self.link_rtts.lock_or_recover();
+        link_rtts_guard.retain(|address, _| successors.contains(address));
+        match rtt_result {
+            Ok(rtt) => { link_rtts_guard.insert(successor_address.clone(), rtt); }
+            Err(status) => debug!("Unable to measure the round-trip time to {}: {}", successor_address, status),
         }
-        let kv_pairs = {
-            let one = HashPos::one();
-            self.kv_store.lock().unwrap()
-                .iter()
-                .filter(move |(key, _)| is_between(hash(*key), one + 1, one, false, false))
-                .map(|(key, value)| KvPairDebugMsg {
-                    key: key.map(|b| b.to_string()).join(" "),
-                    value: value.0.clone(),
-                }).collect()
-        };
-        Ok(Response::new(GetKvStoreDataResponse { kv_pairs }))
     }
 
-    /// GET operation on the key value storage 
-    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
-        let key: Key = request.into_inner().key.try_into().unwrap();
-        let predecessor_pos = {
-            if let Some(finger_entry) = self.predecessor_option.lock().unwrap().clone() {
-                hash(finger_entry.address.as_bytes())
-            } else {
-                return Err(Status::internal("Predecessor not set"));
-            }
-        };
-        if is_between(hash(&key), predecessor_pos + 1, self.pos, false, false) {
-            let mut kv_store_guard = self.kv_store.lock().unwrap();
+    /// looks up the key in the local storage, expired pairs are removed unless serve_stale is set
+    fn get_local(&self, key: &Key) -> GetResponse {
+        let mut kv_store_guard = self.kv_store.lock_or_recover();
 
-            match kv_store_guard.get(&key).cloned() {
-                Some((value, expiration_date)) => {
-                    if has_expired(&expiration_date) {
-                        let since = now().as_secs() - expiration_date;
-                        info!("Received GET request for key {:?}, but value is expired since {} seconds!", key, since);
-                        kv_store_guard.remove(&key);
-                        info!("Removed expired key {:?}", &key);
-                        return Ok(Response::new(GetResponse {
-                            value: value.clone(),
-                            status: GetStatus::Expired.into(),
-                        }));
-                    } else {
-                        info!("Received GET request for key {:?}, value is: {}", key, value);
-                        return Ok(Response::new(GetResponse {
-                            value: value.clone(),
-                            status: GetStatus::Ok.into(),
-                        }));
+        match kv_store_guard.get(key).cloned() {
+            Some((value, expiration_date)) => {
+                if has_expired(&expiration_date) {
+                    self.stats.get_expired_total.fetch_add(1, Ordering::Relaxed);
+                    let since = now_millis() - expiration_date;
+                    info!("Received GET request for key {:?}, but value is expired since {} ms!", key, since);
+                    // stale pairs are kept until compact, so clients have time to refresh them
+                    // while still getting the stale value
+                    if !self.serve_stale {
+                        kv_store_guard.remove(key);
+                        info!("Removed expired key {:?}", key);
+                    }
+                    GetResponse {
+                        value,
+                        status: GetStatus::Expired.into(),
+                        expiration_date,
+                    }
+                } else {
+                    info!("Received GET request for key {:?}, value is: {}", key, value);
+                    GetResponse {
+                        value,
+                        status: GetStatus::Ok.into(),
+                        expiration_date,
                     }
                 }
-                None => {
-                    warn!("Received GET request for key {:?}, but not found)", key);
-                    return Ok(Response::new(GetResponse {
-                        value: String::default(),
-                        status: GetStatus::NotFound.into(),
-                    }));
+            }
+            None => {
+                warn!("Received GET request for key {:?}, but not found)", key);
+                GetResponse {
+                    value: String::default(),
+                    status: GetStatus::NotFound.into(),
+                    expiration_date: 0,
                 }
             }
-        } else {
-            error!("This node is responsible for interval ({}, {}] !", predecessor_pos, self.pos);
-            let msg = format!("Node ({}, {}) is responsible for range ({}, {}]", self.address, self.pos, predecessor_pos, self.pos);
-            return Err(Status::internal(msg));
-        };
+        }
     }
-    
-    /// PUT operation on the key value storage 
-    async fn put(&self, request: Request<PutRequest>) -> Result<Response<Empty>, Status> {
-        let key = request.get_ref().key.clone().try_into().unwrap();
-        let ttl = request.get_ref().ttl;
-        let replication = request.get_ref().replication;
-        let value = &request.get_ref().value;
-
-        // todo: handle replication
 
-        let expiration_date = now().as_secs() + ttl;
-        let _ = self.kv_store.lock().unwrap().insert(key, (value.clone(), expiration_date));
-        info!("Received PUT request ({:?}, {}) with ttl {} and replication {}", hash(&key), value, ttl, replication);
-        Ok(Response::new(Empty {}))
+    /// Whether the node is cut off from the ring, i.e. none of its successors is reachable as
+    /// learned by stabilize and the heartbeats. An unknown predecessor alone doesn't make a node
+    /// isolated, it is unknown for a while after every join and predecessor failure.
+    fn is_isolated(&self) -> bool {
+        let successors: Vec<Address> = self.successor_list.lock_or_recover().successors.iter()
+            .filter(|successor| **successor != self.address)
+            .cloned()
+            .collect();
+        let liveness_guard = self.liveness.lock_or_recover();
+        !successors.is_empty() && successors.iter()
+            .all(|successor| liveness_guard.is_dead(successor, Duration::from_millis(DEAD_MARK_EXPIRY_MILLIS)))
     }
-    
-    
-    /// updates the finger table entries one after another in a round robin fashion by calling 
-    /// find_successor for position the finger table entries point to
-    async fn fix_fingers(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
-        let index = (*self.fix_finger_index.lock().unwrap() + 1) % HashPos::finger_count();
-        debug!("Fixing finger entry {}", index);
-        let lookup_position = self.pos.overflowing_add(HashPos::one().overflowing_shl(index as u32).0).0;
 
-        let responsible_node_for_lookup_pos_response_result = self.find_successor(Request::new(HashPosMsg {
-            key: lookup_position.to_be_bytes().to_vec(),
-        })).await;
+    /// Right after a join, or after the predecessor failed, the predecessor stays unknown until the
+    /// next notify. Until then the node can't tell its range from its own state, so the ring
+    /// decides: the get is served locally if the lookup of the key ends at this node, and is
+    /// forwarded to the responsible node otherwise. A forwarded get is not forwarded again, so two
+    /// nodes without predecessor can't pass it back and forth.
+    async fn get_without_predecessor(&self, key: Key, forwarded: bool) -> Result<Response<GetResponse>, Status> {
+        let responsible_address: Address = self.find_successor_before_deadline(hash(&key), None).await?.into_inner().into();
+        if responsible_address == self.address {
+            return Ok(Response::new(self.get_local(&key)));
+        }
+        if forwarded {
+            return Err(Status::unavailable("Predecessor not set"));
+        }
+        debug!("Predecessor not set, forwarding GET to {}", responsible_address);
+        self.forward_get(key, &responsible_address).await
+    }
 
-        match responsible_node_for_lookup_pos_response_result {
-            Ok(responsible_node_for_lookup_pos_response) => {
-                let responsible_node_address: Address = responsible_node_for_lookup_pos_response.into_inner().into();
-                if index == 1 {
-                    self.successor_list.lock().unwrap().successors[0] = responsible_node_address.clone();
-                }
-                *self.fix_finger_index.lock().unwrap() = index;
-                self.finger_table.lock().unwrap().fingers[index].address = responsible_node_address;
-            }
-            Err(e) => warn!("An error occurred during fix_fingers: {}", e)
+    /// Proxy mode: looks up the node responsible for a key this node doesn't own and returns its
+    /// answer to the get.
+    async fn proxy_get(&self, key: Key) -> Result<Response<GetResponse>, Status> {
+        let responsible_address: Address = self.find_successor_before_deadline(hash(&key), None).await?.into_inner().into();
+        if responsible_address == self.address {
+            return Err(Status::unavailable("Lookup of a key outside the own range ended at this node"));
         }
-        Ok(Response::new(Empty {}))
+        debug!("Proxying GET to {}", responsible_address);
+        self.forward_get(key, &responsible_address).await
     }
 
-    /// updates the successor list and calls notify on the successor
-    async fn stabilize(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
-        let (mut current_successor_client, current_successor_address) = self.get_client_for_closest_successor().await;
-        let current_successors_predecessor_address_optional: Option<Address> = current_successor_client.get_predecessor(Request::new(Empty {}))
+    /// passes the get on to the responsible node, marked as forwarded so it isn't passed on again
+    async fn forward_get(&self, key: Key, responsible_address: &Address) -> Result<Response<GetResponse>, Status> {
+        let mut responsible_client = self.client_pool.get(responsible_address).await?;
+        with_rpc_timeout(responsible_client.get(forwarded_request(GetRequest { key: key.to_vec() })))
             .await
-            .unwrap().into_inner().address_optional.map(|address| address.into());
+            .inspect_err(|status| { self.client_pool.evict_on_connection_error(responsible_address, status); })
+    }
 
-        if let Some(current_successors_predecessor_address) = current_successors_predecessor_address_optional {
-            if !current_successors_predecessor_address.is_empty() {
-                let current_successors_predecessor_pos = hash(current_successors_predecessor_address.as_bytes());
-                let successor_pos = hash(current_successor_address.as_bytes());
-                if is_between(current_successors_predecessor_pos, self.pos + 1, successor_pos, false, true) {
-                    self.set_successor(&current_successors_predecessor_address).await;
+    /// whether the key lies in (predecessor, self], None while the predecessor is unknown
+    fn owns(&self, key: &Key) -> Option<bool> {
+        self.predecessor_option.lock_or_recover().as_ref()
+            .map(|predecessor| is_between(hash(key), hash(predecessor.address.as_bytes()).wrapping_add(1), self.pos, false, false))
+    }
+
+    /// Routes a write addressed by key: None if this node serves it, otherwise the address of the
+    /// responsible node it is forwarded to. While the predecessor is unknown the lookup decides,
+    /// like for gets (see get_without_predecessor).
+    async fn route_write(&self, key: &Key, forwarded: bool) -> Result<Option<Address>, Status> {
+        match self.owns(key) {
+            Some(true) => Ok(None),
+            Some(false) => {
+                let predecessor_pos = self.predecessor_option.lock_or_recover().as_ref()
+                    .map_or(self.pos, |predecessor| hash(predecessor.address.as_bytes()));
+                let msg = format!("Node ({}, {}) is responsible for range ({}, {}]", self.address, self.pos, predecessor_pos, self.pos);
+                Err(Status::failed_precondition(msg))
+            }
+            None => {
+                let responsible_address: Address = self.find_successor_before_deadline(hash(key), None).await?.into_inner().into();
+                if responsible_address == self.address {
+                    Ok(None)
+                } else if forwarded {
+                    Err(Status::unavailable("Predecessor not set"))
+                } else {
+                    debug!("Predecessor not set, forwarding write of {:?} to {}", hash(key), responsible_address);
+                    Ok(Some(responsible_address))
                 }
             }
         }
+    }
 
-        let mut successor_client: ChordClient<Channel> = connect_without_retry(&self.get_successor_address().await)
-            .await;
+    /// stores the result of a finger lookup, a frozen successor is never replaced
+    fn set_finger(&self, index: usize, responsible_node_address: Address) {
+        let successor_frozen = self.successor_list.lock_or_recover().frozen;
+        if index == 1 && !successor_frozen {
+            self.successor_list.lock_or_recover().set_first_successor(&responsible_node_address);
+        }
+        if index != 0 || !successor_frozen {
+            self.finger_table.lock_or_recover().fingers[index].address = responsible_node_address;
+        }
+    }
 
-        let notify_request: NotifyRequest = NotifyRequest {
-            address: Some(self.address.clone().into()),
-            pow_token: Some(PowToken::generate(self.pow_difficulty).into()),
+    /// Clamps the requested ttl to max_ttl. Returns the effective ttl in the requested unit and
+    /// in milliseconds.
+    fn clamp_ttl(&self, requested_ttl: u64, ttl_unit: i32) -> (u64, u64) {
+        // max_ttl is configured in seconds
+        let (ttl, ttl_millis) = match TtlUnit::from_i32(ttl_unit).unwrap_or(TtlUnit::Seconds) {
+            TtlUnit::Seconds => {
+                let ttl = self.max_ttl.map_or(requested_ttl, |max_ttl| requested_ttl.min(max_ttl));
+                (ttl, ttl.saturating_mul(1000))
+            }
+            TtlUnit::Milliseconds => {
+                let ttl = self.max_ttl.map_or(requested_ttl, |max_ttl| requested_ttl.min(max_ttl.saturating_mul(1000)));
+                (ttl, ttl)
+            }
         };
+        if ttl < requested_ttl {
+            warn!("Requested ttl {} exceeds max ttl, clamped to {}", requested_ttl, ttl);
+        }
+        (ttl, ttl_millis)
+    }
 
-        let mut data_handoff_stream = successor_client.notify(Request::new(notify_request))
-            .await?
+    /// Rejects values larger than max_value_bytes before anything is stored.
+    fn check_value_size(&self, value: &str) -> Result<(), Status> {
+        if value.len() > self.max_value_bytes {
+            return Err(Status::invalid_argument(format!("Value of {} bytes exceeds the limit of {} bytes", value.len(), self.max_value_bytes)));
+        }
+        Ok(())
+    }
+
+    /// Whether this node is in the successor list of the given node, as reported by that node.
+    /// Only these successors keep replicas of its pairs.
+    async fn is_successor_of(&self, address: &Address) -> Result<bool, Status> {
+        let mut client = self.client_pool.get(address).await?;
+        let successor_list = with_rpc_timeout(client.get_successor_list(request_with_timeout(Empty {}))).await
+            .inspect_err(|status| { self.client_pool.evict_on_connection_error(address, status); })?
             .into_inner();
+        Ok(successor_list.successors.iter().any(|successor| successor.address == self.address))
+    }
 
-        while let Some(pair) = data_handoff_stream.message().await.unwrap() {
-            let key: Key = pair.key.try_into().unwrap();
-            self.kv_store.lock().unwrap().insert(key, (pair.value, pair.expiration_date));
+    /// Replicas are only accepted from their primary itself, and only if this node is in its
+    /// successor list, otherwise any caller could fill the replica store.
+    async fn check_replica_sender(&self, remote_addr_option: Option<SocketAddr>, primary: &Address) -> Result<(), Status> {
+        if !sent_from(remote_addr_option, primary) || !self.is_successor_of(primary).await? {
+            return Err(Status::permission_denied(format!("Not a successor of {}", primary)));
         }
+        Ok(())
+    }
 
-        Ok(Response::new(Empty {}))
+    /// Moves the replicas within the owned range (predecessor, self] into kv_store. When a
+    /// predecessor fails, the next node in front of it becomes this node's predecessor, and the
+    /// replicas of the failed node's range are served from then on. Stored pairs are never
+    /// overwritten by replicas, they are at least as recent.
+    fn promote_replicas(&self) {
+        let predecessor_pos = match self.predecessor_option.lock_or_recover().as_ref() {
+            Some(predecessor) => hash(predecessor.address.as_bytes()),
+            None => return
+        };
+        let promoted = self.replica_store.lock_or_recover().take_in_range(predecessor_pos.wrapping_add(1), self.pos);
+        if promoted.is_empty() {
+            return;
+        }
+        info!("Promoting {} replicas of ({}, {}]", promoted.len(), predecessor_pos, self.pos);
+        let mut kv_store_guard = self.kv_store.lock_or_recover();
+        for (key, pair) in promoted {
+            kv_store_guard.entry(key).or_insert(pair);
+        }
     }
 
+    /// writes a record to the audit log if one is configured. Failing to do so fails the
+    /// operation, as it must not be acknowledged without its audit record.
+    fn audit(&self, operation: &str, key: &Key, result: &str) -> Result<(), Status> {
+        match &self.audit_log {
+            Some(audit_log) => audit_log.record(operation, hash(key), result)
+                .map_err(|err| Status::internal(format!("Unable to write audit log: {}", err))),
+            None => Ok(())
+        }
+    }
 
-    type NotifyStream = Pin<Box<dyn Stream<Item=Result<KvPairMsg, Status>> + Send>>;
+    /// the successors holding the replicas of this node's pairs
+    fn replica_successors(&self, replication: u32) -> Vec<Address> {
+        let successors: Vec<Address> = self.successor_list.lock_or_recover().successors.iter()
+            .filter(|successor| **successor != self.address)
+            .take(replication as usize)
+            .cloned()
+            .collect();
+        if successors.len() < replication as usize {
+            warn!("Only {} successors known, unable to reach {} replicas", successors.len(), replication);
+        }
+        successors
+    }
 
-    /// Notify call that is typically called on the successor to notify it about this node's presence.
-    /// If this node just joined the cluster the new successor node needs to update its predecessor
-    /// handle and send a part of its data to this very node. The latter part is implemented using
-    /// server side streaming rpc.
-    async fn notify(&self, request: Request<NotifyRequest>) -> Result<Response<Self::NotifyStream>, Status> {
+    /// Forwards the pair to the next replication successors. A replica is stored for ttl_millis
+    /// from its arrival, which is close enough to the primary's expiration date. Unreachable
+    /// successors are skipped, the primary copy is stored regardless.
+    async fn replicate_put(&self, key: &Key, value: &str, ttl_millis: u64, replication: u32) {
+        for successor in self.replica_successors(replication) {
+            if let Err(status) = self.put_replica(&successor, key, value, ttl_millis).await {
+                warn!("Unable to store replica of {:?} on {}: {}", hash(key), successor, status);
+            }
+        }
+    }
 
-        let notify_request = request.into_inner();
-        let pow_token_msg: PowTokenMsg = notify_request.pow_token.unwrap();
-        let pow_token: PowToken = pow_token_msg.into();
+    /// Passes a ttl refresh of an unchanged pair on to the next replication successors without
+    /// the value, which they already hold. A successor missing the replica gets the whole pair.
+    async fn replicate_ttl_refresh(&self, key: &Key, value: &str, ttl_millis: u64, replication: u32) {
+        for successor in self.replica_successors(replication) {
+            let replica_request = request_with_timeout(TouchRequest {
+                key: key.to_vec(),
+                ttl: ttl_millis,
+                ttl_unit: TtlUnit::Milliseconds.into(),
+                is_replica: true,
+                primary: self.address.clone(),
+            });
+            let replica_result = match connect_with_retry(&successor).await {
+                Ok(mut successor_client) => with_rpc_timeout(successor_client.touch(replica_request)).await,
+                Err(status) => Err(status),
+            };
+            let replica_result = match replica_result {
+                Ok(response) if response.get_ref().status == i32::from(TouchStatus::NotFound) => {
+                    debug!("{} holds no replica of {:?}, sending the whole pair", successor, hash(key));
+                    self.put_replica(&successor, key, value, ttl_millis).await
+                }
+                result => result.map(|_| ()),
+            };
+            if let Err(status) = replica_result {
+                warn!("Unable to refresh replica of {:?} on {}: {}", hash(key), successor, status);
+            }
+        }
+    }
+
+    async fn put_replica(&self, successor: &Address, key: &Key, value: &str, ttl_millis: u64) -> Result<(), Status> {
+        let replica_request = request_with_timeout(PutRequest {
+            key: key.to_vec(),
+            ttl: ttl_millis,
+            replication: 0,
+            value: value.to_string(),
+            refresh_ttl_only_if_unchanged: false,
+            ttl_unit: TtlUnit::Milliseconds.into(),
+            is_replica: true,
+            primary: self.address.clone(),
+        });
+        let mut successor_client = connect_with_retry(successor).await?;
+        with_rpc_timeout(successor_client.put(replica_request)).await.map(|_| ())
+    }
 
-        let (has_expired, valid) = pow_token.validate();
-        if has_expired {
-            return Err(Status::cancelled("Pow token expired"))
+    /// removes the replicas of a deleted pair from the next replication successors
+    async fn replicate_delete(&self, key: &Key, replication: u32) {
+        for successor in self.replica_successors(replication) {
+            let replica_request = request_with_timeout(DeleteRequest {
+                key: key.to_vec(),
+                replication: 0,
+                is_replica: true,
+                primary: self.address.clone(),
+            });
+            let replica_result = match connect_with_retry(&successor).await {
+                Ok(mut successor_client) => with_rpc_timeout(successor_client.delete(replica_request)).await.map(|_| ()),
+                Err(status) => Err(status),
+            };
+            if let Err(status) = replica_result {
+                warn!("Unable to delete replica of {:?} on {}: {}", hash(key), successor, status);
+            }
         }
-        if !valid {
-            return Err(Status::cancelled(format!("Invalid pow token: {}", pow_token)))
+    }
+
+    pub async fn get_successor_address(&self) -> Result<Address, Status> {
+        self.successor_list.lock_or_recover().successors.first()
+            .cloned()
+            .ok_or(Status::unavailable("Successor list is empty"))
+    }
+
+    pub async fn set_successor(&self, new_successor_address: &Address) -> () {
+        {
+            let mut successor_list_guard = self.successor_list.lock_or_recover();
+            if successor_list_guard.frozen {
+                debug!("Successor is frozen, ignoring update to {}", new_successor_address);
+                return;
+            }
+            successor_list_guard.set_first_successor(new_successor_address);
         }
+        self.finger_table.lock_or_recover().fingers[0].address = new_successor_address.clone();
+    }
 
-        let (tx, rx) = mpsc::unbounded_channel();
-        let caller_address: &Address = &notify_request.address.unwrap().into();
-        let caller_pos = hash(caller_address.as_bytes());
 
-        let mut predecessor_option_guard = self.predecessor_option.lock().unwrap();
+    pub async fn get_client_for_closest_successor(&self) -> Result<(ChordClient<Channel>, Address), Status> {
+        let successors = {
+            self.successor_list.lock_or_recover().successors.clone()
+        };
+        // the pooled client of the closest successor is reused until a call on it fails
+        if let Some(closest_successor) = successors.first() {
+            if let Some(successor_client) = self.client_pool.cached(closest_successor) {
+                return Ok((successor_client, closest_successor.clone()));
+            }
+        }
+        let (successor_client, successor) = connect_to_first_reachable_node(&successors).await
+            .ok_or(Status::unavailable("All successors in successor list are unreachable"))?;
+        self.client_pool.insert(&successor, successor_client.clone());
+        Ok((successor_client, successor))
+    }
 
-        let (update_predecessor_to_caller, lower, upper) = match *predecessor_option_guard {
-            Some(ref prev_predecessor) => {
-                let lower = hash(prev_predecessor.address.as_bytes());
-                let upper = self.pos;
-                if is_between(caller_pos, lower + 1, upper, false, true) {
-                    (true, lower, caller_pos)
+    /// find_successor implementation, forwarded calls carry the remaining time until the deadline
+    async fn find_successor_before_deadline(&self, key: HashPos, deadline_option: Option<Instant>) -> Result<Response<AddressMsg>, Status> {
+        let start = Instant::now();
+        let direct_successor_address = self.get_successor_address().await?;
+        let successor_pos: HashPos = hash(direct_successor_address.as_bytes());
+        let key_pos_msg: HashPosMsg = HashPosMsg {
+            key: key.to_be_bytes().to_vec()
+        };
+
+        let (successor_address_msg, hops): (AddressMsg, u32) = if key == self.pos {
+            // the closest preceding finger of the own position would be searched in the empty
+            // interval (pos, pos), which leads back to this node and would forward the lookup to itself
+            self.stats.local_lookup_latency.record(start.elapsed());
+            (self.address.clone().into(), 0)
+        } else if is_between(key, self.pos.wrapping_add(1), successor_pos, false, false) {
+            self.stats.local_lookup_latency.record(start.elapsed());
+            (direct_successor_address.into(), 0)
+        } else {
+            let forwarded_result: Result<(AddressMsg, u32), Status> = async {
+                let closest_preceding_node_address = self.find_closest_preceding_finger(Request::new(key_pos_msg.clone()))
+                    .await?
+                    .into_inner();
+                // if no finger precedes the key, e.g. because all of them transiently point to this
+                // node, forwarding to this node again would never terminate. The successor is
+                // closer to the key, so the lookup still makes progress along the ring
+                let next_hop_address = if closest_preceding_node_address.address == self.address {
+                    direct_successor_address.clone()
                 } else {
-                    (false, HashPos::default(), HashPos::default())
+                    closest_preceding_node_address.address
+                };
+
+                if self.lookup_strategy == LookupStrategy::Iterative {
+                    return self.look_up_iteratively(key, next_hop_address, deadline_option).await
+                        .map(|(successor_address, path)| (successor_address.into(), path.len() as u32));
+                }
+
+                let next_hop_result = match self.client_pool.get(&next_hop_address).await {
+                    Ok(mut closest_preceding_node_client) => {
+                        with_rpc_deadline(closest_preceding_node_client.find_successor(request_with_deadline(key.into(), deadline_option)), deadline_option)
+                            .await
+                            .map(into_address_and_hops)
+                    }
+                    Err(status) => Err(status)
+                };
+                match next_hop_result {
+                    // a pooled connection to a node that went down fails just like connecting to it
+                    Err(status) if status.code() == Code::Unavailable => {
+                        self.client_pool.evict(&next_hop_address);
+                        // if node returned by closest_preceding_node_address is unavailable, delegate find_successor call to predecessor
+                        let mut counter = 0;
+                        loop {
+                            if let Some(mut predecessor_client) = self.get_predecessor_client().await {
+                                return with_rpc_deadline(predecessor_client.find_successor(request_with_deadline(key.into(), deadline_option)), deadline_option)
+                                    .await
+                                    .map(into_address_and_hops);
+                            }
+                            if counter > 20 {
+                                return Err(status);
+                            }
+                            counter += 1;
+                        }
+                    }
+                    Err(status) => {
+                        self.client_pool.evict_on_connection_error(&next_hop_address, &status);
+                        Err(status)
+                    }
+                    result => result
+                }
+            }.await;
+            self.stats.forwarded_lookup_latency.record(start.elapsed());
+            forwarded_result?
+        };
+
+        debug!("Received find_successor call for {:?}, successor is {:?} after {} hops", key, successor_address_msg, hops);
+        let mut response = Response::new(successor_address_msg);
+        response.metadata_mut().insert(HOPS_METADATA_KEY, hops.into());
+        Ok(response)
+    }
+
+    /// Resolves the successor of the key by querying the nodes on the path one after another,
+    /// starting at the given node. Unlike the recursive lookup, at most one outbound call is open
+    /// at a time. Returns the successor and the queried nodes.
+    async fn look_up_iteratively(&self, key: HashPos, start_address: Address, deadline_option: Option<Instant>) -> Result<(Address, Vec<Address>), Status> {
+        let mut current_address = start_address;
+        let mut path: Vec<Address> = Vec::new();
+        loop {
+            path.push(current_address.clone());
+            if path.len() > DefaultRing::finger_count() + 1 {
+                return Err(Status::internal("Lookup did not converge"));
+            }
+
+            let mut current_client = self.client_pool.get(&current_address).await?;
+            let current_successor_list: SuccessorList = with_rpc_deadline(current_client.get_successor_list(request_with_deadline(Empty {}, deadline_option)), deadline_option)
+                .await
+                .inspect_err(|status| { self.client_pool.evict_on_connection_error(&current_address, status); })?
+                .into_inner()
+                .into();
+            let current_successor_address = current_successor_list.successors.first()
+                .cloned()
+                .ok_or(Status::unavailable(format!("{} reported an empty successor list", current_address)))?;
+
+            let current_pos = hash(current_address.as_bytes());
+            let current_successor_pos = hash(current_successor_address.as_bytes());
+            if is_between(key, current_pos.wrapping_add(1), current_successor_pos, false, false) {
+                return Ok((current_successor_address, path));
+            }
+
+            let next_address: Address = with_rpc_deadline(current_client.find_closest_preceding_finger(request_with_deadline(key.into(), deadline_option)), deadline_option)
+                .await
+                .inspect_err(|status| { self.client_pool.evict_on_connection_error(&current_address, status); })?
+                .into_inner()
+                .address;
+            // a node without a closer finger hands the lookup over to its successor
+            current_address = if next_address.eq(&current_address) {
+                current_successor_address
+            } else {
+                next_address
+            };
+        }
+    }
+
+    pub async fn get_predecessor_client(&self) -> Option<ChordClient<Channel>> {
+        let predecessor_option_clone = {
+            self.predecessor_option.lock_or_recover().clone()
+        };
+        if let Some(ref predecessor) = predecessor_option_clone {
+            // an unreachable predecessor is treated like an unknown one
+            connect_with_retry(&predecessor.address).await.ok()
+        } else {
+            None
+        }
+    }
+}
+
+
+#[tonic::async_trait]
+impl chord_proto::chord_server::Chord for ChordService {
+    
+    /// finds the next responsible node for a given position in the hash ring
+    async fn find_successor(
+        &self,
+        request: Request<chord_proto::HashPosMsg>,
+    ) -> Result<Response<chord_proto::AddressMsg>, Status> {
+        // a caller's deadline is passed on to all forwarded calls, and the lookup is abandoned
+        // once it passed as nobody waits for the result anymore
+        match remaining_time_until_deadline(&request) {
+            Some(remaining) => {
+                let key: HashPos = try_into_hash_pos(request.get_ref())?;
+                let deadline = Instant::now() + remaining;
+                let deadline_exceeded = || Status::deadline_exceeded(format!("Lookup of {} exceeded the caller's deadline", key));
+                match timeout(remaining, self.find_successor_before_deadline(key, Some(deadline))).await {
+                    // forwarded calls that ran into the propagated timeout fail as well
+                    Ok(Err(_)) if Instant::now() >= deadline => Err(deadline_exceeded()),
+                    Ok(result) => result,
+                    Err(_) => Err(deadline_exceeded())
                 }
             }
+            None => self.find_successor_before_deadline(try_into_hash_pos(request.get_ref())?, None).await
+        }
+    }
+
+
+    /// returns current node's value of the predecessor handle
+    async fn get_predecessor(&self, _request: Request<Empty>) -> Result<Response<GetPredecessorResponse>, Status> {
+        let predecessor_option: Option<AddressMsg> = match *self.predecessor_option.lock_or_recover() {
+            Some(ref predecessor) => {
+                debug!("Received get predecessor call, predecessor is {:?}", predecessor.address);
+                Some(predecessor.address.clone().into())
+            }
             None => {
-                (true, self.pos + 1, caller_pos)
+                debug!("Received get predecessor call, predecessor is Nil");
+                None
             }
         };
+        Ok(Response::new(GetPredecessorResponse { address_optional: predecessor_option }))
+    }
 
-        if update_predecessor_to_caller {
-            *predecessor_option_guard = Some(FingerEntry {
-                key: caller_pos,
-                address: caller_address.clone(),
-            });
-            debug!("Updated predecessor due to notify-call");
+    /// returns the current node's successor list
+    async fn get_successor_list(&self, _: Request<Empty>) -> Result<Response<SuccessorListMsg>, Status> {
+        Ok(Response::new(self.successor_list.lock_or_recover().clone().into()))
+    }
+
+
+    /// batched find_successor that resolves many positions in a single round trip, used by
+    /// joining nodes to initialize their whole finger table at once
+    async fn find_successors(&self, request: Request<HashPosListMsg>) -> Result<Response<AddressListMsg>, Status> {
+        let mut addresses = Vec::new();
+        for key in request.into_inner().keys {
+            addresses.push(self.find_successor(Request::new(key)).await?.into_inner());
         }
+        Ok(Response::new(AddressListMsg { addresses }))
+    }
 
-        let kv_store_arc = self.kv_store.clone();
-        if update_predecessor_to_caller {
-            tokio::spawn(async move {
-                info!("Handing over data from ({}, {}]", lower, upper);
+    async fn find_successor_traced(&self, request: Request<HashPosMsg>) -> Result<Response<FindSuccessorResponse>, Status> {
+        let (successor, hops) = into_address_and_hops(self.find_successor(request).await?);
+        // the hop added for the forwarding to this node doesn't count, the lookup started here
+        Ok(Response::new(FindSuccessorResponse { successor: Some(successor), hops: hops - 1 }))
+    }
+
+    /// find the finger in the finger table that closest precedes the hash position given in the request
+    async fn find_closest_preceding_finger(&self, request: Request<HashPosMsg>) -> Result<Response<FingerEntryMsg>, Status> {
+        let key = try_into_hash_pos(request.get_ref())?;
+        let finger_table_guard = self.finger_table.lock_or_recover();
+        let liveness_guard = self.liveness.lock_or_recover();
+        for finger in finger_table_guard.fingers.iter().rev() {
+            if finger.is_uninitialized() {
+                // ignore yet uninitialized entries
+                continue;
+            }
+            if finger.get_address() == &self.address {
+                // fix_fingers may transiently point a finger to this node during churn, it never
+                // precedes a key
+                continue;
+            }
+            if liveness_guard.is_dead(finger.get_address(), Duration::from_millis(DEAD_MARK_EXPIRY_MILLIS)) {
+                debug!("Skipping dead finger {}", finger.get_address());
+                continue;
+            }
+            let node_pos = hash(finger.get_address().as_bytes());
+            if is_between(node_pos, self.pos, key, true, true) {
+                return Ok(Response::new(FingerEntryMsg {
+                    id: node_pos.to_be_bytes().to_vec(),
+                    address: finger.clone().get_address().into(),
+                }));
+            }
+        }
+        Ok(Response::new(FingerEntryMsg {
+            id: self.pos.to_be_bytes().to_vec(),
+            address: self.address.clone().into(),
+        }))
+    }
 
-                let kv_store_lock_result = kv_store_arc.lock();
-                let mut kv_store_lock = kv_store_lock_result.unwrap();
+    /// returns a human readable node summary (requires dev_mode = true)
+    async fn get_node_summary(&self, _: Request<Empty>) -> Result<Response<NodeSummaryMsg>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        // read before the other guards are taken, link_rtts is never held together with another lock
+        let link_rtts: Vec<LinkRttMsg> = self.link_rtts.lock_or_recover().iter()
+            .map(|(address, rtt)| LinkRttMsg { address: address.clone(), rtt_micros: rtt.as_micros() as u64 })
+            .collect();
+        let finger_table_guard = self.finger_table.lock_or_recover();
+        let predecessor_option = self.predecessor_option.lock_or_recover();
+        let successor_list = self.successor_list.lock_or_recover();
 
-                let pairs_to_handoff: Vec<(Vec<u8>, String, ExpirationDate)> = kv_store_lock
-                    .iter()
-                    .filter(|(key, _)| is_between(hash(*key), lower, upper, false, false))
-                    .map(|(key, (value, ttl))| (key.to_vec(), value.clone(), ttl.clone()))
-                    .collect();
+        Ok(Response::new(NodeSummaryMsg {
+            url: self.address.clone(),
+            pos: Some(self.pos.into()),
+            predecessor: match predecessor_option.clone() {
+                Some(predecessor) => Some(predecessor.into()),
+                None => None
+            },
+            finger_entries: finger_table_guard.fingers.iter()
+                .map(|finger| finger.clone())
+                .map(|finger| finger.into())
+                .collect(),
+            successor_list: Some(successor_list.clone().into()),
+            link_rtts,
+        }))
+    }
+    
+    /// returns the number of key value pairs stored in storage (dev_mode = true)
+    async fn get_kv_store_size(&self, _: Request<Empty>) -> Result<Response<GetKvStoreSizeResponse>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        Ok(Response::new(GetKvStoreSizeResponse {
+            size: self.kv_store.lock_or_recover().len() as u32
+        }))
+    }
 
-                for (key, value, expiration_date) in pairs_to_handoff.iter() {
-                    let pair = KvPairMsg {
-                        key: key.to_vec(),
-                        value: value.clone(),
-                        expiration_date: expiration_date.clone(),
-                    };
-                    debug!("Handing over KV pair ({:?}, {})", key, value);
-                    match tx.send(Ok(pair)) {
-                        Ok(_) => {
-                            let key: Key = key.clone().try_into().unwrap();
-                            kv_store_lock.remove(&key);
-                        }
-                        Err(err) => {
-                            error!("ERROR: failed to update stream client: {:?}", err)
-                        }
-                    }
+    /// returns the full data stored in storage (dev_mode = true)
+    async fn get_kv_store_data(&self, _: Request<Empty>) -> Result<Response<GetKvStoreDataResponse>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        let kv_pairs = {
+            let one = DefaultRing::one();
+            self.kv_store.lock_or_recover()
+                .iter()
+                .filter(move |(key, _)| is_between(hash(*key), one + 1, one, false, false))
+                .map(|(key, value)| KvPairDebugMsg {
+                    key: self.key_encoding.encode(key),
+                    value: value.0.clone(),
+                }).collect()
+        };
+        Ok(Response::new(GetKvStoreDataResponse { kv_pairs }))
+    }
+
+    /// maps a position in the hash ring back to the node owning it, returning the owner's address
+    /// and exact position. Routing is the same as find_successor, but the response is meant for
+    /// diagnostic tools and does not require dev_mode as it reveals no key data
+    async fn reverse_lookup(&self, request: Request<HashPosMsg>) -> Result<Response<ReverseLookupResponse>, Status> {
+        let owner_address: Address = self.find_successor(request).await?.into_inner().into();
+        let owner_pos = hash(owner_address.as_bytes());
+        Ok(Response::new(ReverseLookupResponse {
+            owner_address: Some(owner_address.into()),
+            owner_pos: Some(owner_pos.into()),
+        }))
+    }
+
+    /// performs the lookup for the given position iteratively from this node and returns the
+    /// responsible node together with the ordered list of all nodes visited on the way
+    /// (requires dev_mode = true)
+    async fn trace_lookup(&self, request: Request<HashPosMsg>) -> Result<Response<TraceLookupResponse>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        let key: HashPos = try_into_hash_pos(request.get_ref())?;
+
+        let (successor_address, path) = self.look_up_iteratively(key, self.address.clone(), None).await?;
+        Ok(Response::new(TraceLookupResponse {
+            successor: Some(successor_address.into()),
+            path: path.into_iter().map(|address| address.into()).collect(),
+        }))
+    }
+
+    /// removes the expired pairs from the key value storage and releases the memory they occupied
+    /// (requires dev_mode = true). This is a manual way to pay the locking cost of a cleanup at a
+    /// time chosen by the operator.
+    async fn compact(&self, _: Request<Empty>) -> Result<Response<CompactResponse>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        let mut reclaimed_keys = 0;
+        let mut reclaimed_bytes = 0;
+        {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            kv_store_guard.retain(|key, (value, expiration_date)| {
+                if has_expired(expiration_date) {
+                    reclaimed_keys += 1;
+                    reclaimed_bytes += (key.len() + value.len() + size_of::<ExpirationDate>()) as u64;
+                    false
+                } else {
+                    true
                 }
-                info!("Data handoff finished, transferred {} pairs", pairs_to_handoff.len())
             });
-        };
+        }
+        info!("Compacted kv store, reclaimed {} keys and {} bytes", reclaimed_keys, reclaimed_bytes);
+        Ok(Response::new(CompactResponse { reclaimed_keys, reclaimed_bytes }))
+    }
 
-        let stream = UnboundedReceiverStream::new(rx);
-        Ok(Response::new(Box::pin(stream) as Self::NotifyStream))
+    /// checks the node's own routing state and storage for violated invariants and returns a
+    /// description of each violation (requires dev_mode = true). This is the per node counterpart
+    /// to the validate_cluster binary.
+    async fn self_check(&self, _: Request<Empty>) -> Result<Response<SelfCheckResponse>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        let fingers = self.finger_table.lock_or_recover().fingers.clone();
+        let predecessor_option = self.predecessor_option.lock_or_recover().clone();
+        let successors = self.successor_list.lock_or_recover().successors.clone();
+        // distance travelled clockwise from this node to the given position
+        let distance = |pos: HashPos| pos.wrapping_sub(self.pos);
+        let mut violations = Vec::new();
+
+        if let Some(predecessor) = &predecessor_option {
+            let predecessor_pos = hash(predecessor.address.as_bytes());
+            if predecessor_pos == self.pos && !predecessor.address.eq(&self.address) {
+                violations.push(format!("Predecessor {} has the same position as this node", predecessor.address));
+            }
+            if let Some(successor) = successors.first() {
+                let successor_pos = hash(successor.as_bytes());
+                if !predecessor.address.eq(successor) && distance(predecessor_pos) != 0 && distance(predecessor_pos) < distance(successor_pos) {
+                    violations.push(format!("Predecessor {} lies between this node and its successor {}", predecessor.address, successor));
+                }
+            }
+        }
+
+        let mut last_finger_distance = 0;
+        for (i, finger) in fingers.iter().enumerate() {
+            if finger.is_uninitialized() {
+                continue;
+            }
+            let finger_distance = distance(hash(finger.address.as_bytes()));
+            // fingers pointing to this node itself wrapped around the whole ring
+            if finger_distance == 0 {
+                continue;
+            }
+            if finger_distance < last_finger_distance {
+                violations.push(format!("Finger {} ({}) precedes the previous finger", i, finger.address));
+            }
+            last_finger_distance = finger_distance;
+        }
+
+        if successors.is_empty() {
+            violations.push("Successor list is empty".to_string());
+        }
+        let mut last_successor_distance = 0;
+        for (i, successor) in successors.iter().enumerate() {
+            if is_uninitialized(successor) {
+                violations.push(format!("Successor list has a gap at index {}", i));
+                continue;
+            }
+            if successor.eq(&self.address) {
+                // the list wrapped around the whole ring, later entries start over
+                break;
+            }
+            let successor_distance = distance(hash(successor.as_bytes()));
+            if successor_distance <= last_successor_distance {
+                violations.push(format!("Successor {} ({}) does not follow the previous successor", i, successor));
+            }
+            last_successor_distance = successor_distance;
+        }
+        if let Some(successor) = successors.first() {
+            if !fingers[0].is_uninitialized() && !fingers[0].address.eq(successor) {
+                violations.push(format!("Successor list starts with {}, but the first finger is {}", successor, fingers[0].address));
+            }
+        }
+
+        if let Some(predecessor) = &predecessor_option {
+            let predecessor_pos = hash(predecessor.address.as_bytes());
+            let foreign_keys = self.kv_store.lock_or_recover().keys()
+                .filter(|key| !is_between(hash(*key), predecessor_pos.wrapping_add(1), self.pos, false, false))
+                .count();
+            if foreign_keys > 0 {
+                violations.push(format!("{} stored keys lie outside of the owned range ({}, {}]", foreign_keys, predecessor_pos, self.pos));
+            }
+        }
+
+        Ok(Response::new(SelfCheckResponse { violations }))
     }
 
-    /// Receives a stream of key value pairs in a stream. Nodes that are about to shut down use this
-    /// call to send their data to their successor, as the successor will be responsible for this 
-    /// area in the hash ring.
-    async fn handoff(&self, request: Request<Streaming<KvPairMsg>>) -> Result<Response<Empty>, Status> {
-        let mut stream = request.into_inner();
-        let mut counter = 0;
-        info!("Receiving handoff data from predecessor!");
-        while let Some(kv_msg) = stream.message().await? {
-            let key: Key = kv_msg.key.try_into().unwrap();
-            self.kv_store.lock().unwrap().insert(key, (kv_msg.value, kv_msg.expiration_date));
-            debug!("Received kv-pair!");
-            counter += 1;
-        };
-        info!("Received {} from predecessor", counter);
+    /// overrides the direct successor, bypassing stabilize (requires dev_mode = true).
+    /// Only meant to reproduce stale routing and partitions in tests, never use it in production!
+    async fn force_successor(&self, request: Request<AddressMsg>) -> Result<Response<Empty>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        let successor_address: Address = request.into_inner().into();
+        warn!("Forcing successor to {}", successor_address);
+        self.successor_list.lock_or_recover().set_first_successor(&successor_address);
+        self.finger_table.lock_or_recover().fingers[0].address = successor_address;
         Ok(Response::new(Empty {}))
     }
 
-    /// dummy call, used to check if the receiver node is still available.
-    async fn health(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+    /// stops stabilize, fix_fingers and the successor list check from changing the direct
+    /// successor (requires dev_mode = true). Testing only, never use it in production!
+    async fn freeze_successor(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        warn!("Freezing successor");
+        self.successor_list.lock_or_recover().frozen = true;
         Ok(Response::new(Empty {}))
     }
+
+    /// lets the ring maintenance correct the successor again (requires dev_mode = true)
+    async fn unfreeze_successor(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        if !self.dev_mode {
+            return Err(Status::unimplemented(DEBUG_RPCS_UNAVAILABLE_ERROR_MESSAGE))
+        }
+        info!("Unfreezing successor");
+        self.successor_list.lock_or_recover().frozen = false;
+        Ok(Response::new(Empty {}))
+    }
+
+    /// GET operation on the key value storage 
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        self.stats.get_total.fetch_add(1, Ordering::Relaxed);
+        let forwarded = request.metadata().contains_key(FORWARDED_METADATA_KEY);
+        let key: Key = try_into_key(request.into_inner().key)?;
+        let predecessor_pos = {
+            if let Some(finger_entry) = self.predecessor_option.lock_or_recover().clone() {
+                Some(hash(finger_entry.address.as_bytes()))
+            } else if self.serve_when_isolated && self.is_isolated() {
+                None
+            } else {
+                return self.get_without_predecessor(key, forwarded).await;
+            }
+        };
+        let is_responsible = match predecessor_pos {
+            Some(predecessor_pos) => is_between(hash(&key), predecessor_pos.wrapping_add(1), self.pos, false, false),
+            None => {
+                warn!("Predecessor unknown, node may be cut off from the ring. Serving GET from local data in degraded mode");
+                true
+            }
+        };
+        if is_responsible {
+            Ok(Response::new(self.get_local(&key)))
+        } else if self.proxy_reads && !forwarded {
+            self.proxy_get(key).await
+        } else {
+            let predecessor_pos = predecessor_pos.unwrap();
+            error!("This node is responsible for interval ({}, {}] !", predecessor_pos, self.pos);
+            let msg = format!("Node ({}, {}) is responsible for range ({}, {}]", self.address, self.pos, predecessor_pos, self.pos);
+            return Err(Status::internal(msg));
+        }
+    }
+    
+    /// PUT operation on the key value storage 
+    async fn put(&self, request: Request<PutRequest>) -> Result<Response<PutResponse>, Status> {
+        let remote_addr_option = request.remote_addr();
+        let key: Key = try_into_key(request.get_ref().key.clone())?;
+        let forwarded = request.metadata().contains_key(FORWARDED_METADATA_KEY);
+        // without a predecessor the lookup decides whether the key is stored here, like for gets
+        if self.proxy_writes && !forwarded && !request.get_ref().is_replica && self.owns(&key) != Some(true) {
+            let responsible_address: Address = self.find_successor_before_deadline(hash(&key), None).await?.into_inner().into();
+            if responsible_address != self.address {
+                debug!("Proxying PUT to {}", responsible_address);
+                let mut responsible_client = self.client_pool.get(&responsible_address).await?;
+                return with_rpc_timeout(responsible_client.put(forwarded_request(request.into_inner())))
+                    .await
+                    .inspect_err(|status| { self.client_pool.evict_on_connection_error(&responsible_address, status); });
+            }
+        }
+        let _write_guard = self.admit_write().await?;
+        let requested_ttl = request.get_ref().ttl;
+        let (ttl, ttl_millis) = self.clamp_ttl(requested_ttl, request.get_ref().ttl_unit);
+        let replication = request.get_ref().replication;
+        let value = &request.get_ref().value;
+        self.check_value_size(value)?;
+        let refresh_ttl_only_if_unchanged = request.get_ref().refresh_ttl_only_if_unchanged;
+        let is_replica = request.get_ref().is_replica;
+
+        let expiration_date = now_millis().saturating_add(ttl_millis);
+        if is_replica {
+            // replicas lie outside the own range, they are kept apart from the own pairs and only
+            // served once this node takes over the range of their primary
+            let primary = &request.get_ref().primary;
+            self.check_replica_sender(remote_addr_option, primary).await?;
+            let mut replica_store_guard = self.replica_store.lock_or_recover();
+            let unchanged = refresh_ttl_only_if_unchanged && replica_store_guard.replicas_by_primary.get(primary)
+                .and_then(|replicas| replicas.get(&key))
+                .is_some_and(|(stored_value, _)| stored_value == value);
+            if unchanged {
+                replica_store_guard.touch(primary, &key, expiration_date);
+            } else {
+                replica_store_guard.insert(primary, key, (value.clone(), expiration_date));
+            }
+            drop(replica_store_guard);
+            self.stats.put_total.fetch_add(1, Ordering::Relaxed);
+            info!("Received replica of ({:?}, {}) owned by {} with ttl {}", hash(&key), value, primary, ttl);
+            self.audit("put", &key, &format!("replica ttl={}", ttl))?;
+            return Ok(Response::new(PutResponse { effective_ttl: ttl }));
+        }
+        let value_changed = {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            match kv_store_guard.get_mut(&key) {
+                Some((stored_value, stored_expiration_date)) if refresh_ttl_only_if_unchanged && stored_value == value => {
+                    // heartbeat-style put: the value is unchanged, so only the expiration is refreshed
+                    *stored_expiration_date = expiration_date;
+                    info!("Received PUT request ({:?}, {}) with unchanged value, refreshed ttl to {}", hash(&key), value, ttl);
+                    false
+                }
+                _ => {
+                    let _ = kv_store_guard.insert(key, (value.clone(), expiration_date));
+                    info!("Received PUT request ({:?}, {}) with ttl {} and replication {}", hash(&key), value, ttl, replication);
+                    true
+                }
+            }
+        };
+        // counted once stored, a proxied put only by the node storing it
+        self.stats.put_total.fetch_add(1, Ordering::Relaxed);
+        if replication > 0 && value_changed {
+            self.replicate_put(&key, value, ttl_millis, replication).await;
+        } else if replication > 0 {
+            self.replicate_ttl_refresh(&key, value, ttl_millis, replication).await;
+        }
+        self.audit("put", &key, &format!("ok ttl={}", ttl))?;
+        Ok(Response::new(PutResponse { effective_ttl: ttl }))
+    }
+
+    /// TOUCH operation, routed like GET: sets the expiration of a stored pair to now + ttl.
+    /// Expired pairs are not revived, they are removed like GET does unless stale pairs are served.
+    async fn touch(&self, request: Request<TouchRequest>) -> Result<Response<TouchResponse>, Status> {
+        let remote_addr_option = request.remote_addr();
+        let forwarded = request.metadata().contains_key(FORWARDED_METADATA_KEY);
+        let _write_guard = self.admit_write().await?;
+        let touch_request = request.into_inner();
+        let key: Key = try_into_key(touch_request.key.clone())?;
+        let (ttl, ttl_millis) = self.clamp_ttl(touch_request.ttl, touch_request.ttl_unit);
+
+        if touch_request.is_replica {
+            // ttl refresh of an unchanged replicated pair, the replica keeps its value
+            self.check_replica_sender(remote_addr_option, &touch_request.primary).await?;
+            let touched = self.replica_store.lock_or_recover()
+                .touch(&touch_request.primary, &key, now_millis().saturating_add(ttl_millis));
+            let status = if touched { TouchStatus::Ok } else { TouchStatus::NotFound };
+            debug!("Received replica TOUCH for key {:?} owned by {}: {:?}", hash(&key), touch_request.primary, status);
+            return Ok(Response::new(TouchResponse { status: status.into(), effective_ttl: ttl }));
+        }
+        if let Some(responsible_address) = self.route_write(&key, forwarded).await? {
+            let mut responsible_client = self.client_pool.get(&responsible_address).await?;
+            return with_rpc_timeout(responsible_client.touch(forwarded_request(touch_request)))
+                .await
+                .inspect_err(|status| { self.client_pool.evict_on_connection_error(&responsible_address, status); });
+        }
+
+        let status = {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            match kv_store_guard.get_mut(&key) {
+                Some((_, expiration_date)) if has_expired(expiration_date) => {
+                    if !self.serve_stale {
+                        kv_store_guard.remove(&key);
+                        info!("Removed expired key {:?}", &key);
+                    }
+                    TouchStatus::Expired
+                }
+                Some((_, expiration_date)) => {
+                    *expiration_date = now_millis().saturating_add(ttl_millis);
+                    TouchStatus::Ok
+                }
+                None => TouchStatus::NotFound
+            }
+        };
+        info!("Received TOUCH request for key {:?} with ttl {}: {:?}", hash(&key), ttl, status);
+        self.audit("touch", &key, &format!("{:?} ttl={}", status, ttl))?;
+        Ok(Response::new(TouchResponse { status: status.into(), effective_ttl: ttl }))
+    }
+
+    /// Batched PUT coordinated by this node: every key is looked up once, then each responsible
+    /// node receives all of its puts in a single stream instead of one call per key.
+    async fn put_many(&self, request: Request<PutManyRequest>) -> Result<Response<PutManyResponse>, Status> {
+        let mut puts_by_node: HashMap<Address, Vec<PutRequest>> = HashMap::new();
+        for put_request in request.into_inner().puts {
+            let key: Key = try_into_key(put_request.key.clone())?;
+            let responsible_node: Address = self.find_successor(Request::new(hash(&key).into())).await?.into_inner().into();
+            puts_by_node.entry(responsible_node).or_default().push(put_request);
+        }
+
+        let mut stored = 0;
+        for (responsible_node, puts) in puts_by_node {
+            let put_count = puts.len();
+            let mut responsible_node_client = connect_with_retry(&responsible_node).await?;
+            stored += with_rpc_timeout(responsible_node_client.put_stream(request_with_timeout(tokio_stream::iter(puts))))
+                .await?
+                .into_inner()
+                .stored;
+            debug!("Sent {} puts to {}", put_count, responsible_node);
+        }
+        Ok(Response::new(PutManyResponse { stored }))
+    }
+
+    /// stores every streamed put like a single PUT, including ttl clamping and replication.
+    /// Replicas are rejected, the streamed puts don't carry the sender that has to be checked.
+    async fn put_stream(&self, request: Request<Streaming<PutRequest>>) -> Result<Response<PutManyResponse>, Status> {
+        let mut stream = request.into_inner();
+        let mut stored = 0;
+        while let Some(put_request) = stream.message().await? {
+            if put_request.is_replica {
+                return Err(Status::invalid_argument("Replicas can't be streamed"));
+            }
+            self.put(Request::new(put_request)).await?;
+            stored += 1;
+        }
+        Ok(Response::new(PutManyResponse { stored }))
+    }
+
+    /// DELETE operation on the key value storage, routed like GET. Replicas are removed from the
+    /// replica store without an ownership check, as they lie outside the replica's own range, but
+    /// only on behalf of their primary.
+    async fn delete(&self, request: Request<DeleteRequest>) -> Result<Response<DeleteResponse>, Status> {
+        let remote_addr_option = request.remote_addr();
+        let forwarded = request.metadata().contains_key(FORWARDED_METADATA_KEY);
+        let _write_guard = self.admit_write().await?;
+        let delete_request = request.into_inner();
+        let key: Key = try_into_key(delete_request.key.clone())?;
+
+        let removed = if delete_request.is_replica {
+            self.check_replica_sender(remote_addr_option, &delete_request.primary).await?;
+            self.replica_store.lock_or_recover().remove(&delete_request.primary, &key)
+        } else {
+            if let Some(responsible_address) = self.route_write(&key, forwarded).await? {
+                let mut responsible_client = self.client_pool.get(&responsible_address).await?;
+                return with_rpc_timeout(responsible_client.delete(forwarded_request(delete_request)))
+                    .await
+                    .inspect_err(|status| { self.client_pool.evict_on_connection_error(&responsible_address, status); });
+            }
+            self.kv_store.lock_or_recover().remove(&key).is_some()
+        };
+        info!("Received DELETE request for key {:?}, removed: {}", hash(&key), removed);
+        if delete_request.replication > 0 && !delete_request.is_replica {
+            self.replicate_delete(&key, delete_request.replication).await;
+        }
+        self.audit("delete", &key, if removed { "deleted" } else { "not found" })?;
+        let status = if removed { DeleteStatus::Ok } else { DeleteStatus::NotFound };
+        Ok(Response::new(DeleteResponse { status: status.into() }))
+    }
+
+    /// Removes the pair only if its current value equals the expected value. Comparison and
+    /// removal happen under the kv_store lock, so a write that changed the value after the caller
+    /// read it prevents the delete.
+    async fn delete_if(&self, request: Request<DeleteIfRequest>) -> Result<Response<DeleteIfResponse>, Status> {
+        let forwarded = request.metadata().contains_key(FORWARDED_METADATA_KEY);
+        let _write_guard = self.admit_write().await?;
+        let delete_if_request = request.into_inner();
+        let key: Key = try_into_key(delete_if_request.key.clone())?;
+        if let Some(responsible_address) = self.route_write(&key, forwarded).await? {
+            let mut responsible_client = self.client_pool.get(&responsible_address).await?;
+            return with_rpc_timeout(responsible_client.delete_if(forwarded_request(delete_if_request)))
+                .await
+                .inspect_err(|status| { self.client_pool.evict_on_connection_error(&responsible_address, status); });
+        }
+
+        let deleted = {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            match kv_store_guard.get(&key) {
+                Some((value, expiration_date)) if *value == delete_if_request.expected_value && !has_expired(expiration_date) => {
+                    kv_store_guard.remove(&key);
+                    true
+                }
+                _ => false
+            }
+        };
+        info!("Received DELETE_IF request for key {:?}, deleted: {}", hash(&key), deleted);
+        self.audit("delete_if", &key, if deleted { "deleted" } else { "unchanged" })?;
+        Ok(Response::new(DeleteIfResponse { deleted }))
+    }
+
+    /// Compare-and-swap: stores the value only if the stored value equals the expected one, or if
+    /// the key is absent and the expected value is empty. Comparison and write happen under the
+    /// kv_store lock, so concurrent swaps with the same expected value can't both succeed.
+    async fn put_if(&self, request: Request<PutIfRequest>) -> Result<Response<PutIfResponse>, Status> {
+        let forwarded = request.metadata().contains_key(FORWARDED_METADATA_KEY);
+        let _write_guard = self.admit_write().await?;
+        let put_if_request = request.into_inner();
+        let key: Key = try_into_key(put_if_request.key.clone())?;
+        self.check_value_size(&put_if_request.value)?;
+        if let Some(responsible_address) = self.route_write(&key, forwarded).await? {
+            let mut responsible_client = self.client_pool.get(&responsible_address).await?;
+            return with_rpc_timeout(responsible_client.put_if(forwarded_request(put_if_request)))
+                .await
+                .inspect_err(|status| { self.client_pool.evict_on_connection_error(&responsible_address, status); });
+        }
+        let (ttl, ttl_millis) = self.clamp_ttl(put_if_request.ttl, put_if_request.ttl_unit);
+
+        let swapped = {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            // expired pairs count as absent
+            let current_value_option = kv_store_guard.get(&key)
+                .filter(|(_, expiration_date)| !has_expired(expiration_date))
+                .map(|(value, _)| value.as_str());
+            let matches = match current_value_option {
+                Some(current_value) => current_value == put_if_request.expected_value,
+                None => put_if_request.expected_value.is_empty()
+            };
+            if matches {
+                kv_store_guard.insert(key, (put_if_request.value, now_millis().saturating_add(ttl_millis)));
+            }
+            matches
+        };
+        if swapped {
+            self.stats.put_total.fetch_add(1, Ordering::Relaxed);
+        }
+        info!("Received PUT_IF request for key {:?}, swapped: {}", hash(&key), swapped);
+        self.audit("put_if", &key, if swapped { "swapped" } else { "unchanged" })?;
+        Ok(Response::new(PutIfResponse { swapped, effective_ttl: ttl }))
+    }
+    
+    
+    /// Returns the next page of non-expired pairs stored on this node within (lower, upper], ordered
+    /// by their position in the ring starting at lower. The continuation token is the position and
+    /// key of the last returned pair, so a page picks up right behind it without a long-lived
+    /// stream. Pages are cut from the current store content: pairs inserted or removed between
+    /// two pages may be missed if they lie before the token. Every pair is still returned at most
+    /// once, as the order is strict.
+    async fn scan_page(&self, request: Request<ScanPageRequest>) -> Result<Response<ScanPageResponse>, Status> {
+        let scan_page_request = request.into_inner();
+        let lower: HashPos = try_into_hash_pos(scan_page_request.lower.as_ref().ok_or(Status::invalid_argument("Lower bound required"))?)?;
+        let upper: HashPos = try_into_hash_pos(scan_page_request.upper.as_ref().ok_or(Status::invalid_argument("Upper bound required"))?)?;
+        if scan_page_request.page_size == 0 {
+            return Err(Status::invalid_argument("Page size must be at least 1"));
+        }
+        // pairs are ordered by their distance from lower, ties by key
+        let order = |key: &Key| (hash(key).wrapping_sub(lower), *key);
+        let after_option: Option<(HashPos, Key)> = if scan_page_request.continuation_token.is_empty() {
+            None
+        } else {
+            let (pos, key) = decode_continuation_token(&scan_page_request.continuation_token)?;
+            Some((pos.wrapping_sub(lower), key))
+        };
+
+        let mut page: Vec<(Key, Value, ExpirationDate)> = self.kv_store.lock_or_recover().iter()
+            .filter(|(key, (_, expiration_date))| {
+                is_between(hash(*key), lower.wrapping_add(1), upper, false, false)
+                    && !has_expired(expiration_date)
+                    && after_option.is_none_or(|after| order(key) > after)
+            })
+            .map(|(key, (value, expiration_date))| (*key, value.clone(), *expiration_date))
+            .collect();
+        page.sort_by_key(|(key, _, _)| order(key));
+        let has_next_page = page.len() > scan_page_request.page_size as usize;
+        page.truncate(scan_page_request.page_size as usize);
+
+        let next_continuation_token = match page.last() {
+            Some((last_key, _, _)) if has_next_page => encode_continuation_token(last_key),
+            _ => Vec::new()
+        };
+        Ok(Response::new(ScanPageResponse {
+            pairs: page.into_iter()
+                .map(|(key, value, expiration_date)| KvPairMsg { key: key.to_vec(), value, expiration_date })
+                .collect(),
+            next_continuation_token,
+        }))
+    }
+
+    type ScanStream = Pin<Box<dyn Stream<Item=Result<KvPairMsg, Status>> + Send>>;
+
+    /// streaming counterpart of scan_page without continuation tokens: clients page through the
+    /// store by passing the position after the last received pair as the next start
+    async fn scan(&self, request: Request<ScanRequest>) -> Result<Response<Self::ScanStream>, Status> {
+        let scan_request = request.into_inner();
+        let start: HashPos = scan_request.start.as_ref().map_or(Ok(HashPos::MIN), try_into_hash_pos)?;
+        let limit = match scan_request.limit {
+            0 => usize::MAX,
+            limit => limit as usize
+        };
+
+        let mut pairs: Vec<(HashPos, KvPairMsg)> = {
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            if scan_request.remove_expired {
+                kv_store_guard.retain(|key, (_, expiration_date)| hash(key) < start || !has_expired(expiration_date));
+            }
+            kv_store_guard.iter()
+                .map(|(key, pair)| (hash(key), key, pair))
+                .filter(|(pos, _, (_, expiration_date))| *pos >= start && !has_expired(expiration_date))
+                .map(|(pos, key, (value, expiration_date))| (pos, KvPairMsg {
+                    key: key.to_vec(),
+                    value: value.clone(),
+                    expiration_date: *expiration_date,
+                }))
+                .collect()
+        };
+        pairs.sort_by(|(pos, pair), (other_pos, other_pair)| (pos, &pair.key).cmp(&(other_pos, &other_pair.key)));
+        pairs.truncate(limit);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        for (_, pair) in pairs {
+            let _ = tx.send(Ok(pair));
+        }
+        Ok(Response::new(Box::pin(UnboundedReceiverStream::new(rx)) as Self::ScanStream))
+    }
+
+    /// updates the finger table entries one after another in a round robin fashion by calling 
+    /// find_successor for position the finger table entries point to
+    async fn fix_fingers(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        self.stats.fix_fingers_iterations_total.fetch_add(1, Ordering::Relaxed);
+        let index = match self.finger_fix_strategy {
+            FingerFixStrategy::RoundRobin => (*self.fix_finger_index.lock().unwrap() + 1) % DefaultRing::finger_count(),
+            FingerFixStrategy::Random => rand::thread_rng().gen_range(0..DefaultRing::finger_count()),
+            FingerFixStrategy::WeightedLow => {
+                // squaring a uniform sample in [0, 1) skews it towards 0
+                let sample: f64 = rand::thread_rng().gen();
+                ((sample * sample * DefaultRing::finger_count() as f64) as usize).min(DefaultRing::finger_count() - 1)
+            }
+        };
+        debug!("Fixing finger entry {}", index);
+        let lookup_position = finger_position::<DefaultRing>(&self.pos, index);
+
+        let responsible_node_for_lookup_pos_response_result = self.find_successor(Request::new(HashPosMsg {
+            key: lookup_position.to_be_bytes().to_vec(),
+        })).await;
+
+        match responsible_node_for_lookup_pos_response_result {
+            Ok(responsible_node_for_lookup_pos_response) => {
+                *self.fix_finger_index.lock().unwrap() = index;
+                self.set_finger(index, responsible_node_for_lookup_pos_response.into_inner().into());
+            }
+            Err(e) => warn!("An error occurred during fix_fingers: {}", e)
+        }
+        Ok(Response::new(Empty {}))
+    }
+
+    /// fixes all fingers in a single pass. Consecutive fingers often share their successor, a
+    /// finger whose position lies before the successor found for the previous finger resolves to
+    /// that successor as well and needs no lookup
+    async fn fix_all_fingers(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        let finger_keys: Vec<HashPos> = self.finger_table.lock_or_recover().fingers.iter()
+            .map(|finger| finger.key)
+            .collect();
+        let mut previous_successor_option: Option<(HashPos, Address)> = None;
+        let mut lookup_count = 0;
+        for (index, finger_key) in finger_keys.into_iter().enumerate() {
+            let responsible_node_address = match &previous_successor_option {
+                // (pos, pos] would span the whole ring, a finger resolving to this node is never reused
+                Some((successor_pos, successor_address)) if *successor_pos != self.pos
+                    && is_between(finger_key, self.pos.wrapping_add(1), *successor_pos, false, false) => {
+                    successor_address.clone()
+                }
+                _ => {
+                    lookup_count += 1;
+                    let address: Address = self.find_successor(Request::new(finger_key.into())).await?.into_inner().into();
+                    previous_successor_option = Some((hash(address.as_bytes()), address.clone()));
+                    address
+                }
+            };
+            self.set_finger(index, responsible_node_address);
+        }
+        debug!("Fixed all {} fingers with {} lookups", DefaultRing::finger_count(), lookup_count);
+        Ok(Response::new(Empty {}))
+    }
+
+    /// updates the successor list and calls notify on the successor
+    async fn stabilize(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        self.stats.stabilize_total.fetch_add(1, Ordering::Relaxed);
+        let mut stale_client_evicted = false;
+        let (current_successor_address, current_successors_predecessor_response) = loop {
+            let (mut current_successor_client, current_successor_address) = match self.get_client_for_closest_successor().await {
+                Ok(client_and_address) => {
+                    self.liveness.lock_or_recover().mark_alive(&client_and_address.1);
+                    client_and_address
+                }
+                Err(status) => {
+                    let successors = self.successor_list.lock_or_recover().successors.clone();
+                    let mut liveness_guard = self.liveness.lock_or_recover();
+                    for successor in successors.iter().filter(|successor| **successor != self.address) {
+                        liveness_guard.mark_dead(successor);
+                    }
+                    drop(liveness_guard);
+                    if self.serve_when_isolated {
+                        warn!("Node is cut off from the ring, serving local data in degraded mode until a successor is reachable again");
+                    }
+                    return Err(status);
+                }
+            };
+            match with_rpc_timeout(current_successor_client.get_predecessor(request_with_timeout(Empty {}))).await {
+                // the pooled client may belong to a successor that went down, once it is evicted
+                // the closest reachable successor is connected from scratch
+                Err(status) if !stale_client_evicted && self.client_pool.evict_on_connection_error(&current_successor_address, &status) => {
+                    stale_client_evicted = true;
+                }
+                result => break (current_successor_address, result?)
+            }
+        };
+        let current_successors_predecessor_address_optional: Option<Address> = current_successors_predecessor_response
+            .into_inner().address_optional.map(|address| address.into());
+
+        if let Some(current_successors_predecessor_address) = current_successors_predecessor_address_optional {
+            if !is_uninitialized(&current_successors_predecessor_address) {
+                let current_successors_predecessor_pos = hash(current_successors_predecessor_address.as_bytes());
+                let successor_pos = hash(current_successor_address.as_bytes());
+                let quarantined = self.successor_list.lock_or_recover()
+                    .is_quarantined(&current_successors_predecessor_address, self.successor_stable_period);
+                if quarantined {
+                    debug!("{} was demoted recently, not promoting it to successor yet", current_successors_predecessor_address);
+                } else if is_between(current_successors_predecessor_pos, self.pos.wrapping_add(1), successor_pos, false, true) {
+                    self.set_successor(&current_successors_predecessor_address).await;
+                }
+            }
+        }
+
+        let successor_address = self.get_successor_address().await?;
+        let mut successor_client: ChordClient<Channel> = self.client_pool.get(&successor_address).await?;
+
+        let mut pow_difficulty = self.base_pow_difficulty;
+        let mut data_handoff_stream = loop {
+            let notify_request: NotifyRequest = NotifyRequest {
+                address: Some(self.address.clone().into()),
+                pow_token: Some(PowToken::generate(&self.address, pow_difficulty, self.pow_threads).into()),
+            };
+            match with_rpc_timeout(successor_client.notify(request_with_timeout(notify_request))).await {
+                Ok(response) => break response.into_inner(),
+                // the successor raised its difficulty during a notify flood
+                Err(status) => match raised_pow_difficulty(&status, pow_difficulty) {
+                    Some(required_pow_difficulty) => {
+                        debug!("{} requires pow difficulty {}, regenerating the token", successor_address, required_pow_difficulty);
+                        pow_difficulty = required_pow_difficulty;
+                    }
+                    None => {
+                        self.client_pool.evict_on_connection_error(&successor_address, &status);
+                        return Err(status);
+                    }
+                }
+            }
+        };
+        // measured only once notify got through, so an unreachable successor costs a single timeout
+        self.measure_rtt(&mut successor_client, &successor_address).await;
+
+        while let Some(batch) = data_handoff_stream.message().await? {
+            let mut stored_keys = Vec::with_capacity(batch.pairs.len());
+            let mut rejected_keys = Vec::new();
+            {
+                let mut kv_store_guard = self.kv_store.lock_or_recover();
+                for pair in batch.pairs {
+                    self.stats.record_handoff_received(&pair.key, &pair.value);
+                    // the successor only hands off valid keys, a malformed one was never pending
+                    let key: Key = match try_into_key(pair.key) {
+                        Ok(key) => key,
+                        Err(status) => {
+                            warn!("Skipping handed over pair: {}", status.message());
+                            continue;
+                        }
+                    };
+                    // rejected in the ack, so the successor keeps the pair as its own
+                    if let Err(status) = self.check_value_size(&pair.value) {
+                        warn!("Rejecting handed over pair {:?}: {}", hash(&key), status.message());
+                        rejected_keys.push(key.to_vec());
+                        continue;
+                    }
+                    kv_store_guard.insert(key, (pair.value, pair.expiration_date));
+                    stored_keys.push(key.to_vec());
+                }
+            }
+            // without the ack the successor keeps its copies as tentative and doesn't send them to
+            // this node again, so nothing is lost if the ack fails
+            let handoff_ack = HandoffAckMsg { keys: stored_keys, address: Some(self.address.clone().into()), rejected_keys };
+            if let Err(status) = with_rpc_timeout(successor_client.ack_handoff(request_with_timeout(handoff_ack))).await {
+                warn!("Failed to acknowledge handoff batch to {}: {}", successor_address, status);
+            }
+        }
+
+        Ok(Response::new(Empty {}))
+    }
+
+
+    type NotifyStream = Pin<Box<dyn Stream<Item=Result<KvBatchMsg, Status>> + Send>>;
+
+    /// Notify call that is typically called on the successor to notify it about this node's presence.
+    /// If this node just joined the cluster the new successor node needs to update its predecessor
+    /// handle and send a part of its data to this very node. The latter part is implemented using
+    /// server side streaming rpc.
+    async fn notify(&self, request: Request<NotifyRequest>) -> Result<Response<Self::NotifyStream>, Status> {
+
+        let notify_request = request.into_inner();
+        let caller_address: &Address = &notify_request.address.unwrap_or_default().into();
+        if is_uninitialized(caller_address) {
+            return Err(Status::invalid_argument("Notify requires the caller's address"))
+        }
+        validate_pow_token(notify_request.pow_token, caller_address, self.required_pow_difficulty())?;
+        self.record_notify_call();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        // update_predecessor_to releases the predecessor guard before the handoff task below is
+        // spawned and locks kv_store (see the lock order documented on ChordService)
+        let kv_store_arc = self.kv_store.clone();
+        let stats = self.stats.clone();
+        let handoff_batch_size = self.handoff_batch_size;
+        let handoff_semaphore = self.handoff_semaphore.clone();
+        let pending_handoffs = self.pending_handoffs.clone();
+        let receiver_address = caller_address.clone();
+        if let Some((_, lower, upper)) = self.update_predecessor_to(caller_address) {
+            tokio::spawn(async move {
+                // during mass joins many handoffs are requested at once, only a limited number of
+                // them collect and send their pairs at the same time
+                stats.handoffs_queued.fetch_add(1, Ordering::Relaxed);
+                let _handoff_permit = handoff_semaphore.acquire_owned().await.unwrap();
+                stats.handoffs_queued.fetch_sub(1, Ordering::Relaxed);
+                info!("Handing over data from ({}, {}]", lower, upper);
+
+                // the receiver may have changed or deleted pairs it got before without acking them,
+                // sending them again would overwrite these writes
+                let already_sent: HashSet<Key> = pending_handoffs.lock_or_recover().iter()
+                    .filter(|(_, receiver)| **receiver == receiver_address)
+                    .map(|(key, _)| *key)
+                    .collect();
+                let pairs_to_handoff: Vec<(Key, KvPairMsg)> = kv_store_arc.lock_or_recover()
+                    .iter()
+                    .filter(|(key, _)| is_between(hash(*key), lower, upper, false, false) && !already_sent.contains(*key))
+                    .map(|(key, (value, expiration_date))| (*key, KvPairMsg {
+                        key: key.to_vec(),
+                        value: value.clone(),
+                        expiration_date: *expiration_date,
+                    }))
+                    .collect();
+                let pair_count = pairs_to_handoff.len();
+
+                // a sent batch may still be lost with the stream, so the pairs are only removed
+                // once the receiver acknowledges them via ack_handoff
+                for batch in pairs_to_handoff.chunks(handoff_batch_size.max(1)) {
+                    debug!("Handing over batch of {} KV pairs", batch.len());
+                    // marked before sending, the ack may arrive right after
+                    pending_handoffs.lock_or_recover()
+                        .extend(batch.iter().map(|(key, _)| (*key, receiver_address.clone())));
+                    let batch_msg = KvBatchMsg { pairs: batch.iter().map(|(_, pair)| pair.clone()).collect() };
+                    match tx.send(Ok(batch_msg)) {
+                        Ok(_) => {
+                            for (_, pair) in batch {
+                                stats.record_handoff_sent(&pair.key, &pair.value);
+                            }
+                        }
+                        Err(err) => {
+                            error!("ERROR: failed to update stream client: {:?}", err);
+                            let mut pending_handoffs_guard = pending_handoffs.lock_or_recover();
+                            for (key, _) in batch {
+                                pending_handoffs_guard.remove(key);
+                            }
+                        }
+                    }
+                }
+                info!("Data handoff finished, transferred {} pairs", pair_count)
+            });
+        };
+
+        // the caller may have replaced a failed predecessor, extending the range this node owns
+        self.promote_replicas();
+
+        let stream = UnboundedReceiverStream::new(rx);
+        Ok(Response::new(Box::pin(stream) as Self::NotifyStream))
+    }
+
+    /// Removes the pairs of a notify or join handoff that the receiver acknowledged. Acks are only accepted
+    /// from the current predecessor, which received the handoff, and only for keys that were sent
+    /// to it, so a stray ack never drops owned pairs or replicas. Pairs the receiver rejected are
+    /// no longer pending and stay here.
+    async fn ack_handoff(&self, request: Request<HandoffAckMsg>) -> Result<Response<Empty>, Status> {
+        let acking_address: Address = request.get_ref().address.clone().unwrap_or_default().into();
+        let is_predecessor = self.predecessor_option.lock_or_recover().as_ref()
+            .is_some_and(|predecessor| predecessor.address == acking_address);
+        if !is_predecessor || !sent_from(request.remote_addr(), &acking_address) {
+            return Err(Status::permission_denied("Only the predecessor receiving a handoff may acknowledge it"));
+        }
+        let handoff_ack = request.into_inner();
+        let keys: Vec<Key> = handoff_ack.keys.into_iter()
+            .map(try_into_key)
+            .collect::<Result<_, _>>()?;
+        let rejected_keys: Vec<Key> = handoff_ack.rejected_keys.into_iter()
+            .map(try_into_key)
+            .collect::<Result<_, _>>()?;
+
+        let acknowledged_keys: Vec<Key> = {
+            let mut pending_handoffs_guard = self.pending_handoffs.lock_or_recover();
+            for key in rejected_keys.iter().filter(|key| pending_handoffs_guard.get(*key) == Some(&acking_address)) {
+                pending_handoffs_guard.remove(key);
+            }
+            keys.iter()
+                .filter(|key| pending_handoffs_guard.get(*key) == Some(&acking_address))
+                .filter_map(|key| pending_handoffs_guard.remove(key).map(|_| *key))
+                .collect()
+        };
+        let mut kv_store_guard = self.kv_store.lock_or_recover();
+        let removed = acknowledged_keys.iter()
+            .filter(|key| kv_store_guard.remove(*key).is_some())
+            .count();
+        debug!("Handoff of {} pairs acknowledged by {}, removed {}, {} rejected", keys.len(), acking_address, removed, rejected_keys.len());
+        Ok(Response::new(Empty {}))
+    }
+
+    /// Explicit join called by a newcomer on its successor. Unlike notify, the predecessor update
+    /// and the data handoff happen synchronously: the response contains the previous predecessor,
+    /// which becomes the newcomer's predecessor, and all pairs of the range the newcomer now owns.
+    /// They are removed here once the newcomer acknowledges them via ack_handoff.
+    async fn join(&self, request: Request<JoinRequest>) -> Result<Response<JoinResponse>, Status> {
+        let join_request = request.into_inner();
+        let caller_address: &Address = &join_request.address.unwrap_or_default().into();
+        if is_uninitialized(caller_address) {
+            return Err(Status::invalid_argument("Join requires the caller's address"))
+        }
+        validate_pow_token(join_request.pow_token, caller_address, self.required_pow_difficulty())?;
+        let (prev_predecessor, lower, upper) = self.update_predecessor_to(caller_address)
+            .ok_or(Status::failed_precondition(format!("{} is not between this node and its predecessor", caller_address)))?;
+
+        // the pairs are kept until the newcomer acknowledges that it stored them, a newcomer that
+        // fails in between loses nothing
+        let handed_over_pairs: Vec<(Key, KvPairMsg)> = self.kv_store.lock_or_recover().iter()
+            .filter(|(key, _)| is_between(hash(*key), lower, upper, false, false))
+            .map(|(key, (value, expiration_date))| {
+                self.stats.record_handoff_sent(key, value);
+                (*key, KvPairMsg { key: key.to_vec(), value: value.clone(), expiration_date: *expiration_date })
+            })
+            .collect();
+        self.pending_handoffs.lock_or_recover()
+            .extend(handed_over_pairs.iter().map(|(key, _)| (*key, caller_address.clone())));
+        let pairs: Vec<KvPairMsg> = handed_over_pairs.into_iter()
+            .map(|(_, pair)| pair)
+            .collect();
+        info!("{} joined, handed over {} pairs from ({}, {}]", caller_address, pairs.len(), lower, upper);
+
+        Ok(Response::new(JoinResponse {
+            predecessor: prev_predecessor.map(|predecessor| predecessor.into()),
+            lower: Some(lower.into()),
+            upper: Some(upper.into()),
+            pairs,
+        }))
+    }
+
+    /// Receives a stream of key value pairs in a stream. Nodes that are about to shut down use this
+    /// call to send their data to their successor, as the successor will be responsible for this 
+    /// area in the hash ring.
+    async fn handoff(&self, request: Request<Streaming<KvBatchMsg>>) -> Result<Response<Empty>, Status> {
+        // a leaving node would lose the pairs, the predecessor hands them to the next successor
+        let _write_guard = self.admit_write().await?;
+        let mut stream = request.into_inner();
+        let mut counter = 0;
+        info!("Receiving handoff data from predecessor!");
+        while let Some(batch) = stream.message().await? {
+            debug!("Received batch of {} kv-pairs!", batch.pairs.len());
+            let mut kv_store_guard = self.kv_store.lock_or_recover();
+            for kv_msg in batch.pairs {
+                self.stats.record_handoff_received(&kv_msg.key, &kv_msg.value);
+                // failing the stream would make the leaving node hand all pairs to the next
+                // successor as well, including those already stored here, so bad pairs are skipped
+                let key: Key = match try_into_key(kv_msg.key) {
+                    Ok(key) => key,
+                    Err(status) => {
+                        warn!("Skipping handed over pair: {}", status.message());
+                        continue;
+                    }
+                };
+                if let Err(status) = self.check_value_size(&kv_msg.value) {
+                    warn!("Skipping handed over pair {:?}: {}", hash(&key), status.message());
+                    continue;
+                }
+                kv_store_guard.insert(key, (kv_msg.value, kv_msg.expiration_date));
+                counter += 1;
+            }
+        };
+        info!("Received {} from predecessor", counter);
+        Ok(Response::new(Empty {}))
+    }
+
+    /// Replaces all references to a leaving node by its neighbors: the successor of the leaving
+    /// node takes over its fingers and its place in the successor list, the predecessor of the
+    /// leaving node becomes this node's predecessor if the leaving node was. Only the leaving node
+    /// itself may send the notice, and only while it is this node's predecessor or successor.
+    async fn leave_notice(&self, request: Request<LeaveNoticeMsg>) -> Result<Response<Empty>, Status> {
+        let leaving: Address = request.get_ref().leaving.clone().ok_or(Status::invalid_argument("Leaving address required"))?.into();
+        let is_neighbor = self.predecessor_option.lock_or_recover().as_ref()
+            .is_some_and(|predecessor| predecessor.address == leaving)
+            || self.successor_list.lock_or_recover().successors.first() == Some(&leaving);
+        if !is_neighbor || !sent_from(request.remote_addr(), &leaving) {
+            return Err(Status::permission_denied("Only a leaving neighbor may send a leave notice"));
+        }
+        let leave_notice = request.into_inner();
+        let successor: Address = leave_notice.successor.ok_or(Status::invalid_argument("Successor address required"))?.into();
+        let predecessor_option: Option<Address> = leave_notice.predecessor.map(|predecessor| predecessor.into());
+        info!("{} leaves the ring", leaving);
+
+        for finger in self.finger_table.lock_or_recover().fingers.iter_mut() {
+            if finger.address == leaving {
+                finger.address = successor.clone();
+            }
+        }
+        {
+            let mut predecessor_option_guard = self.predecessor_option.lock_or_recover();
+            if predecessor_option_guard.as_ref().is_some_and(|predecessor| predecessor.address == leaving) {
+                *predecessor_option_guard = predecessor_option.map(|predecessor| FingerEntry {
+                    key: hash(predecessor.as_bytes()),
+                    address: predecessor,
+                });
+                self.stats.predecessor_changes.lock_or_recover().record();
+            }
+        }
+        {
+            let mut successor_list_guard = self.successor_list.lock_or_recover();
+            if successor_list_guard.successors.first() == Some(&leaving) {
+                successor_list_guard.set_first_successor(&successor);
+            }
+            successor_list_guard.successors.retain(|address| *address != leaving);
+            successor_list_guard.successors.dedup();
+        }
+        self.liveness.lock_or_recover().mark_dead(&leaving);
+        self.client_pool.evict(&leaving);
+        Ok(Response::new(Empty {}))
+    }
+
+    /// Pushes a copy of all pairs within (predecessor, self] to every node in the successor list.
+    /// Each push replaces the previous copy on the successor. Without a known predecessor the
+    /// owned range is unknown and nothing is pushed.
+    async fn push_replicas(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        let predecessor_pos = match self.predecessor_option.lock_or_recover().as_ref() {
+            Some(predecessor) => hash(predecessor.address.as_bytes()),
+            None => return Ok(Response::new(Empty {}))
+        };
+        let successors: Vec<Address> = {
+            let mut successors = self.successor_list.lock_or_recover().successors.clone();
+            successors.retain(|successor| *successor != self.address);
+            successors.dedup();
+            successors
+        };
+        let pairs: Vec<KvPairMsg> = self.kv_store.lock_or_recover().iter()
+            .filter(|(key, (_, expiration_date))| {
+                is_between(hash(*key), predecessor_pos.wrapping_add(1), self.pos, false, false) && !has_expired(expiration_date)
+            })
+            .map(|(key, (value, expiration_date))| KvPairMsg {
+                key: key.to_vec(),
+                value: value.clone(),
+                expiration_date: *expiration_date,
+            })
+            .collect();
+
+        let mut batches: Vec<ReplicaBatchMsg> = into_batches(pairs, self.handoff_batch_size).into_iter()
+            .map(|batch| ReplicaBatchMsg { primary: self.address.clone(), pairs: batch.pairs })
+            .collect();
+        if batches.is_empty() {
+            // an empty copy still replaces the successor's previous one
+            batches.push(ReplicaBatchMsg { primary: self.address.clone(), pairs: Vec::new() });
+        }
+        for successor in successors {
+            let replicate_result = match connect_with_retry(&successor).await {
+                Ok(mut successor_client) => with_rpc_timeout(successor_client.replicate(request_with_timeout(tokio_stream::iter(batches.clone())))).await,
+                Err(status) => Err(status),
+            };
+            if let Err(status) = replicate_result {
+                warn!("Unable to push replicas to {}: {}", successor, status);
+            }
+        }
+        Ok(Response::new(Empty {}))
+    }
+
+    /// receives the copy of a preceding node's pairs, they are kept apart from kv_store and not
+    /// served until this node takes over their range. Only the primary itself may push them, and
+    /// only to the nodes of its successor list.
+    async fn replicate(&self, request: Request<Streaming<ReplicaBatchMsg>>) -> Result<Response<Empty>, Status> {
+        let remote_addr_option = request.remote_addr();
+        let mut stream = request.into_inner();
+        let first_batch = stream.message().await?
+            .ok_or(Status::invalid_argument("Replicate requires at least one batch"))?;
+        let primary = first_batch.primary.clone();
+        if primary == self.address {
+            return Ok(Response::new(Empty {}));
+        }
+        self.check_replica_sender(remote_addr_option, &primary).await?;
+
+        let mut replicas = KvStore::new();
+        let mut batch_option = Some(first_batch);
+        while let Some(batch) = batch_option {
+            if batch.primary != primary {
+                return Err(Status::invalid_argument("All batches of a replicate stream need the same primary"));
+            }
+            for pair in batch.pairs {
+                let key: Key = try_into_key(pair.key)?;
+                self.check_value_size(&pair.value)?;
+                replicas.insert(key, (pair.value, pair.expiration_date));
+            }
+            batch_option = stream.message().await?;
+        }
+        debug!("Received {} replicas of {}", replicas.len(), primary);
+        self.replica_store.lock_or_recover().replace(&primary, replicas);
+        Ok(Response::new(Empty {}))
+    }
+
+    /// dummy call, used to check if the receiver node is still available.
+    async fn health(&self, _: Request<Empty>) -> Result<Response<Empty>, Status> {
+        Ok(Response::new(Empty {}))
+    }
+
+    /// echoes the caller's timestamp, the caller computes the round-trip time from it
+    async fn ping(&self, request: Request<PingMsg>) -> Result<Response<PingMsg>, Status> {
+        Ok(Response::new(request.into_inner()))
+    }
+
+    /// receives the heartbeat of a neighbor
+    async fn heartbeat(&self, request: Request<HeartbeatMsg>) -> Result<Response<Empty>, Status> {
+        let heartbeat = request.into_inner();
+        if !self.liveness.lock_or_recover().record_heartbeat(&heartbeat.address, heartbeat.sequence) {
+            debug!("Ignoring outdated heartbeat {} of {}", heartbeat.sequence, heartbeat.address);
+        }
+        Ok(Response::new(Empty {}))
+    }
+
+    /// returns cluster wide settings that a joining node has to agree with
+    async fn get_cluster_info(&self, _: Request<Empty>) -> Result<Response<ClusterInfoMsg>, Status> {
+        Ok(Response::new(ClusterInfoMsg { salt_fingerprint: hash_salt_fingerprint() }))
+    }
+
+    /// returns the node's operational counters
+    async fn get_stats(&self, _: Request<Empty>) -> Result<Response<StatsMsg>, Status> {
+        let owned_arc = match self.predecessor_option.lock_or_recover().as_ref() {
+            Some(predecessor) if predecessor.address != self.address => self.pos.wrapping_sub(hash(predecessor.address.as_bytes())),
+            _ => HashPos::MAX,
+        };
+        let initialized_fingers = self.finger_table.lock_or_recover().fingers.iter()
+            .filter(|finger| !finger.is_uninitialized())
+            .count();
+        let has_predecessor = self.predecessor_option.lock_or_recover().is_some();
+        let pow_difficulty = *self.pow_difficulty.lock_or_recover();
+        let key_count = self.kv_store.lock_or_recover().len();
+        let successor_list_guard = self.successor_list.lock_or_recover();
+        Ok(Response::new(StatsMsg {
+            handoff_bytes_sent: self.stats.handoff_bytes_sent.load(Ordering::Relaxed),
+            handoff_bytes_received: self.stats.handoff_bytes_received.load(Ordering::Relaxed),
+            suspected_successors: successor_list_guard.suspected_since.iter()
+                .map(|(address, since)| SuccessorTimerMsg {
+                    address: address.clone(),
+                    elapsed_millis: since.elapsed().as_millis() as u64,
+                })
+                .collect(),
+            demoted_successors: successor_list_guard.demoted_at.iter()
+                .map(|(address, since)| SuccessorTimerMsg {
+                    address: address.clone(),
+                    elapsed_millis: since.elapsed().as_millis() as u64,
+                })
+                .collect(),
+            local_lookup_latency: Some((&self.stats.local_lookup_latency).into()),
+            forwarded_lookup_latency: Some((&self.stats.forwarded_lookup_latency).into()),
+            handoffs_in_flight: (self.max_concurrent_handoffs - self.handoff_semaphore.available_permits()) as u32,
+            handoffs_queued: self.stats.handoffs_queued.load(Ordering::Relaxed) as u32,
+            predecessor_changes: self.stats.predecessor_changes.lock_or_recover().count() as u32,
+            successor_changes: successor_list_guard.successor_changes.count() as u32,
+            owned_arc,
+            key_density: key_count as f64 / owned_arc as f64,
+            get_total: self.stats.get_total.load(Ordering::Relaxed),
+            get_expired_total: self.stats.get_expired_total.load(Ordering::Relaxed),
+            put_total: self.stats.put_total.load(Ordering::Relaxed),
+            fix_fingers_iterations_total: self.stats.fix_fingers_iterations_total.load(Ordering::Relaxed),
+            stabilize_total: self.stats.stabilize_total.load(Ordering::Relaxed),
+            kv_store_size: key_count as u64,
+            successor_list_len: successor_list_guard.successors.len() as u32,
+            initialized_fingers: initialized_fingers as u32,
+            has_predecessor,
+            pow_difficulty: pow_difficulty as u32,
+            expired_kv_store_size: self.stats.expired_kv_store_size.load(Ordering::Relaxed),
+        }))
+    }
+
+    /// readiness check: unlike health (liveness) this only reports ready once the node is fully
+    /// integrated into the ring, i.e. it knows its predecessor and its first finger is set
+    async fn readiness(&self, _: Request<Empty>) -> Result<Response<ReadinessResponse>, Status> {
+        let has_successor = !self.finger_table.lock_or_recover().fingers[0].is_uninitialized();
+        let has_predecessor = self.predecessor_option.lock_or_recover().is_some();
+        Ok(Response::new(ReadinessResponse { ready: has_successor && has_predecessor }))
+    }
 }
 
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::Request;
+    use tonic::transport::Server;
+
+    use crate::threads::chord::chord_proto::chord_server::{Chord, ChordServer};
+    use crate::threads::expiration::sweep_expired;
+    use crate::utils::constants::{MAX_VALUE_BYTES_DEFAULT, SUCCESSOR_LIST_LENGTH_DEFAULT};
+
+    use super::*;
+
+    /// handles to the data structures of a node under test
+    pub(crate) struct TestNode {
+        pub(crate) address: Address,
+        pub(crate) finger_table: Arc<Mutex<FingerTable>>,
+        pub(crate) predecessor_option: Arc<Mutex<Option<FingerEntry>>>,
+        pub(crate) kv_store: Arc<Mutex<KvStore>>,
+        pub(crate) successor_list: Arc<Mutex<SuccessorList>>,
+        pub(crate) replica_store: Arc<Mutex<ReplicaStore>>,
+        pub(crate) stats: Arc<NodeStats>,
+        pub(crate) liveness: Arc<Mutex<Liveness>>,
+        pub(crate) handoff_semaphore: Arc<Semaphore>,
+        pub(crate) client_pool: Arc<ClientPool>,
+        /// task serving the node, only set for nodes spawned with spawn_test_node
+        pub(crate) server_handle: Option<tokio::task::JoinHandle<()>>,
+        /// the served service, for calls that are not exposed as RPCs. Only set for nodes spawned
+        /// with spawn_test_node
+        pub(crate) service: Option<Arc<ChordService>>,
+    }
+
+    /// builds a service around fresh data structures, the returned handles can be used to
+    /// inspect or manipulate the node state from within a test
+    pub(crate) fn test_config(address: &str) -> Config {
+        Config {
+            p2p_address: address.to_string(),
+            pow_difficulty: 0,
+            dev_mode: true,
+            ..Config::default()
+        }
+    }
+
+    pub(crate) async fn new_test_service(address: &str, successor: &str) -> (ChordService, TestNode) {
+        new_test_service_with_config(test_config(address), successor).await
+    }
+
+    pub(crate) async fn new_test_service_with_config(config: Config, successor: &str) -> (ChordService, TestNode) {
+        let address: Address = config.p2p_address.clone();
+        let finger_table = Arc::new(Mutex::new(FingerTable::new(&hash(address.as_bytes()))));
+        let predecessor_option = Arc::new(Mutex::new(None));
+        let kv_store = Arc::new(Mutex::new(KvStore::new()));
+        let successor_list = Arc::new(Mutex::new(SuccessorList::new(&address, &successor.to_string(), config.successor_list_length)));
+        finger_table.lock().unwrap().fingers[0].address = successor.to_string();
+
+        let (tx, rx) = oneshot::channel();
+        tx.send((finger_table.clone(), predecessor_option.clone(), kv_store.clone(), successor_list.clone())).unwrap();
+        let service = ChordService::new(rx, &config).await.unwrap();
+        let node = TestNode { address, finger_table, predecessor_option, kv_store, successor_list,
+            replica_store: service.replica_store.clone(), stats: service.stats.clone(), liveness: service.liveness.clone(), handoff_semaphore: service.handoff_semaphore.clone(),
+            client_pool: service.client_pool.clone(), server_handle: None, service: None };
+        (service, node)
+    }
+
+    /// a plain put without replication and with a ttl in seconds, other fields can be overridden
+    /// with struct update syntax
+    pub(crate) fn put_request(key: &Key, value: &str, ttl: u64) -> PutRequest {
+        PutRequest {
+            key: key.to_vec(),
+            ttl,
+            replication: 0,
+            value: value.to_string(),
+            refresh_ttl_only_if_unchanged: false,
+            ttl_unit: TtlUnit::Seconds.into(),
+            is_replica: false,
+            primary: String::default(),
+        }
+    }
+
+    /// serves a test service on an ephemeral local port, the node is its own successor unless
+    /// another successor is given
+    pub(crate) async fn spawn_test_node(successor_option: Option<&str>) -> TestNode {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        serve_test_node(listener, test_config(&address), successor_option.unwrap_or(&address)).await
+    }
+
+    /// serves a test service with the given config, its p2p_address has to match the listener
+    pub(crate) async fn serve_test_node(listener: TcpListener, config: Config, successor: &str) -> TestNode {
+        let (service, mut node) = new_test_service_with_config(config, successor).await;
+        let service = Arc::new(service);
+        node.service = Some(service.clone());
+        node.server_handle = Some(tokio::spawn(async move {
+            Server::builder()
+                .add_service(ChordServer::from_arc(service))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+                .unwrap();
+        }));
+        node
+    }
+
+    /// spawns n nodes and wires them into a consistent ring with correct predecessors, successor
+    /// lists and finger tables, the nodes are returned in ring order
+    pub(crate) async fn spawn_test_ring(n: usize) -> Vec<TestNode> {
+        spawn_test_ring_with_config(n, test_config).await
+    }
+
+    /// like spawn_test_ring, with the config of each node derived from its address
+    pub(crate) async fn spawn_test_ring_with_config(n: usize, config_of: fn(&str) -> Config) -> Vec<TestNode> {
+        let mut nodes = Vec::new();
+        for _ in 0..n {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            nodes.push(serve_test_node(listener, config_of(&address), &address).await);
+        }
+        nodes.sort_by_key(|node| hash(node.address.as_bytes()));
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let responsible_node_index = |key: HashPos| positions.iter().position(|pos| key <= *pos).unwrap_or(0);
+
+        for (i, node) in nodes.iter().enumerate() {
+            node.successor_list.lock().unwrap().successors = (1..=SUCCESSOR_LIST_LENGTH_DEFAULT.min(n))
+                .map(|j| nodes[(i + j) % n].address.clone())
+                .collect();
+            *node.predecessor_option.lock().unwrap() = Some(nodes[(i + n - 1) % n].address.clone().into());
+            for finger in node.finger_table.lock().unwrap().fingers.iter_mut() {
+                finger.address = nodes[responsible_node_index(finger.key)].address.clone();
+            }
+        }
+        nodes
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_get_and_notify_do_not_deadlock() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:6001".to_string().into());
+        for i in 0..100u8 {
+            node.kv_store.lock().unwrap().insert([i; 32], (i.to_string(), u64::MAX));
+        }
+        let service = Arc::new(service);
+
+        let mut handles = Vec::new();
+        for i in 0..50u8 {
+            let get_service = service.clone();
+            handles.push(tokio::spawn(async move {
+                let _ = get_service.get(Request::new(GetRequest { key: [i; 32].to_vec() })).await;
+            }));
+            let notify_service = service.clone();
+            handles.push(tokio::spawn(async move {
+                let caller_address = format!("127.0.0.1:{}", 7000 + i as u16);
+                let request = NotifyRequest {
+                    pow_token: Some(PowToken::generate(&caller_address, 0, 1).into()),
+                    address: Some(caller_address.into()),
+                };
+                if let Ok(response) = notify_service.notify(Request::new(request)).await {
+                    let mut stream = response.into_inner();
+                    while tokio_stream::StreamExt::next(&mut stream).await.is_some() {}
+                }
+            }));
+        }
+
+        let all_finished = tokio::time::timeout(Duration::from_secs(20), async {
+            for handle in handles {
+                handle.await.unwrap();
+            }
+        }).await;
+        assert!(all_finished.is_ok(), "get and notify deadlocked");
+    }
+
+    #[tokio::test]
+    async fn joining_node_is_not_ready_until_stabilized() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6002").await;
+        let ready = service.readiness(Request::new(Empty {})).await.unwrap().into_inner().ready;
+        assert!(!ready);
+
+        // a notify from the predecessor during stabilization completes the integration
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:6001".to_string().into());
+        let ready = service.readiness(Request::new(Empty {})).await.unwrap().into_inner().ready;
+        assert!(ready);
+    }
+
+    #[tokio::test]
+    async fn keys_in_the_wrap_region_route_to_the_lowest_node() {
+        let nodes = spawn_test_ring(4).await;
+        let min_pos = hash(nodes[0].address.as_bytes());
+        let max_pos = hash(nodes[3].address.as_bytes());
+        // keys above the highest node and up to the lowest node wrap around the end of the ring,
+        // the lowest node's own position is also looked up on the lowest node itself
+        let wrap_keys = [max_pos + 1, (max_pos / 2) + (HashPos::MAX / 2), HashPos::MAX, 0, min_pos / 2, min_pos];
+
+        for node in &nodes {
+            let mut client = connect_with_retry(&node.address).await.unwrap();
+            for key in wrap_keys {
+                let successor: Address = client.find_successor(Request::new(key.into())).await.unwrap().into_inner().into();
+                assert_eq!(successor, nodes[0].address, "lookup of {} from {}", key, node.address);
+            }
+            // the boundaries themselves belong to the highest and the second lowest node
+            let successor: Address = client.find_successor(Request::new(max_pos.into())).await.unwrap().into_inner().into();
+            assert_eq!(successor, nodes[3].address);
+            let successor: Address = client.find_successor(Request::new((min_pos + 1).into())).await.unwrap().into_inner().into();
+            assert_eq!(successor, nodes[1].address);
+        }
+    }
+
+    #[tokio::test]
+    async fn closest_preceding_finger_of_a_wrap_key_is_the_highest_node() {
+        let nodes = spawn_test_ring(4).await;
+        let max_pos = hash(nodes[3].address.as_bytes());
+
+        for key in [max_pos + 1, HashPos::MAX, 0] {
+            // the first finger of the second highest node points to the highest node
+            let mut client = connect_with_retry(&nodes[2].address).await.unwrap();
+            let closest = client.find_closest_preceding_finger(Request::new(key.into())).await.unwrap().into_inner();
+            assert_eq!(closest.address, nodes[3].address, "closest preceding finger of {}", key);
+            // no node lies between the highest node and the key, so it is its own closest preceding node
+            let mut client = connect_with_retry(&nodes[3].address).await.unwrap();
+            let closest = client.find_closest_preceding_finger(Request::new(key.into())).await.unwrap().into_inner();
+            assert_eq!(closest.address, nodes[3].address, "closest preceding finger of {}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn lookup_terminates_with_transiently_self_pointing_fingers() {
+        let nodes = spawn_test_ring(4).await;
+        let key = hash(nodes[2].address.as_bytes());
+        // during churn fix_fingers may point fingers to the node itself for positions it doesn't own
+        for finger in nodes[0].finger_table.lock().unwrap().fingers.iter_mut() {
+            finger.address = nodes[0].address.clone();
+        }
+
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        let closest = client.find_closest_preceding_finger(Request::new(key.into())).await.unwrap().into_inner();
+        assert_eq!(closest.address, nodes[0].address);
+        let successor = timeout(Duration::from_secs(5), client.find_successor(Request::new(key.into())))
+            .await
+            .expect("lookup did not terminate")
+            .unwrap();
+        assert_eq!(Into::<Address>::into(successor.into_inner()), nodes[2].address);
+    }
+
+    #[tokio::test]
+    async fn reverse_lookup_returns_owner_and_position() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let response = service.reverse_lookup(Request::new(12345.into())).await.unwrap().into_inner();
+
+        let owner_address: Address = response.owner_address.unwrap().into();
+        let owner_pos: HashPos = try_into_hash_pos(&response.owner_pos.unwrap()).unwrap();
+        assert_eq!(owner_address, node.address);
+        assert_eq!(owner_pos, hash(node.address.as_bytes()));
+    }
+
+    #[tokio::test]
+    async fn unchanged_put_only_refreshes_ttl() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let put = |value: &str, ttl: u64| Request::new(PutRequest { refresh_ttl_only_if_unchanged: true, ..put_request(&[1; 32], value, ttl) });
+
+        service.put(put("alive", 10)).await.unwrap();
+        let (_, first_expiration_date) = node.kv_store.lock().unwrap().get(&[1; 32]).cloned().unwrap();
+
+        service.put(put("alive", 1000)).await.unwrap();
+        let (value, refreshed_expiration_date) = node.kv_store.lock().unwrap().get(&[1; 32]).cloned().unwrap();
+        assert_eq!(value, "alive");
+        assert!(refreshed_expiration_date >= first_expiration_date + 990);
+
+        service.put(put("changed", 10)).await.unwrap();
+        let (value, _) = node.kv_store.lock().unwrap().get(&[1; 32]).cloned().unwrap();
+        assert_eq!(value, "changed");
+
+        // the replicas of an unchanged pair only get the new expiration, not the value again
+        let nodes = spawn_test_ring(3).await;
+        let replicated_put = |value: &str, ttl: u64| Request::new(PutRequest { replication: 1, ..put(value, ttl).into_inner() });
+        let replica = || nodes[1].replica_store.lock().unwrap().replicas_by_primary[&nodes[0].address][&[1; 32]].clone();
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        client.put(replicated_put("alive", 10)).await.unwrap();
+        let (_, first_replica_expiration_date) = replica();
+        // a value write to the replica would overwrite the mark
+        nodes[1].replica_store.lock().unwrap().insert(&nodes[0].address, [1; 32], ("marked".to_string(), first_replica_expiration_date));
+
+        client.put(replicated_put("alive", 1000)).await.unwrap();
+        let (value, refreshed_replica_expiration_date) = replica();
+        assert_eq!(value, "marked");
+        assert!(refreshed_replica_expiration_date >= first_replica_expiration_date + 990);
+
+        client.put(replicated_put("changed", 10)).await.unwrap();
+        assert_eq!(replica().0, "changed");
+    }
+
+    #[tokio::test]
+    async fn trace_lookup_records_path_to_responsible_node() {
+        let nodes = spawn_test_ring(4).await;
+        let target_pos = hash(nodes[3].address.as_bytes());
+
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        let response = client.trace_lookup(Request::new(target_pos.into())).await.unwrap().into_inner();
+        let path: Vec<Address> = response.path.into_iter().map(|address| address.into()).collect();
+
+        assert_eq!(Into::<Address>::into(response.successor.unwrap()), nodes[3].address);
+        assert_eq!(path[0], nodes[0].address);
+        assert!(path.len() <= DefaultRing::finger_count());
+    }
+
+    #[tokio::test]
+    async fn iterative_and_recursive_lookups_find_the_same_successor() {
+        let nodes = spawn_test_ring(8).await;
+        // an unserved copy of the first node that resolves lookups iteratively
+        let config = Config { lookup_strategy: LookupStrategy::Iterative, ..test_config(&nodes[0].address) };
+        let (iterative_service, iterative_node) = new_test_service_with_config(config, &nodes[1].address).await;
+        *iterative_node.finger_table.lock().unwrap() = nodes[0].finger_table.lock().unwrap().clone();
+        *iterative_node.successor_list.lock().unwrap() = nodes[0].successor_list.lock().unwrap().clone();
+        *iterative_node.predecessor_option.lock().unwrap() = nodes[0].predecessor_option.lock().unwrap().clone();
+
+        let mut recursive_client = connect_with_retry(&nodes[0].address).await.unwrap();
+        let keys = nodes.iter()
+            .map(|node| hash(node.address.as_bytes()))
+            .chain((0..32u8).map(|i| hash(&[i; 32])));
+        for key in keys {
+            let recursive_successor = recursive_client.find_successor(Request::new(key.into())).await.unwrap().into_inner();
+            let iterative_successor = iterative_service.find_successor(Request::new(key.into())).await.unwrap().into_inner();
+            assert_eq!(iterative_successor, recursive_successor, "successor of {}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn first_reachable_node_is_found_without_retrying_dead_ones() {
+        let mut dead_addresses = Vec::new();
+        for _ in 0..2 {
+            // nothing listens on the port once the listener is dropped
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            dead_addresses.push(listener.local_addr().unwrap().to_string());
+        }
+        let live_node = spawn_test_node(None).await;
+        let address_list = vec![dead_addresses[0].clone(), dead_addresses[1].clone(), live_node.address.clone()];
+
+        let start = Instant::now();
+        let (_, address) = connect_to_first_reachable_node(&address_list).await.unwrap();
+        assert_eq!(address, live_node.address);
+        assert!(start.elapsed() < Duration::from_millis(MAX_RETRIES * CONNECTION_RETRY_SLEEP));
+    }
+
+    #[tokio::test]
+    async fn malformed_positions_and_keys_are_rejected_without_panicking() {
+        let node = spawn_test_node(None).await;
+        let mut client = connect_with_retry(&node.address).await.unwrap();
+
+        for key in [vec![], vec![1; 3], vec![1; 32]] {
+            let status = client.find_successor(Request::new(HashPosMsg { key })).await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+        let status = client.get(Request::new(GetRequest { key: vec![1; 31] })).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        // the node keeps serving well-formed requests
+        let successor: Address = client.find_successor(Request::new(HashPos::MAX.into())).await.unwrap().into_inner().into();
+        assert_eq!(successor, node.address);
+    }
+
+    #[tokio::test]
+    async fn forwarded_lookups_reuse_the_pooled_connection() {
+        let nodes = spawn_test_ring(4).await;
+        let key = hash(nodes[2].address.as_bytes());
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+
+        let response = client.find_successor_traced(Request::new(key.into())).await.unwrap().into_inner();
+        assert!(response.hops > 0);
+        let connections_opened = nodes[0].client_pool.connections_opened();
+        assert!(connections_opened > 0);
+
+        for _ in 0..10 {
+            let successor: Address = client.find_successor(Request::new(key.into())).await.unwrap().into_inner().into();
+            assert_eq!(successor, nodes[2].address);
+        }
+        assert_eq!(nodes[0].client_pool.connections_opened(), connections_opened);
+    }
+
+    #[tokio::test]
+    async fn put_clamps_ttl_to_max_ttl() {
+        let config = Config { max_ttl: Some(100), ..test_config("127.0.0.1:6000") };
+        let (service, node) = new_test_service_with_config(config, "127.0.0.1:6000").await;
+
+        assert_eq!(service.put(Request::new(put_request(&[1; 32], "value", 100))).await.unwrap().into_inner().effective_ttl, 100);
+        assert_eq!(service.put(Request::new(put_request(&[1; 32], "value", 101))).await.unwrap().into_inner().effective_ttl, 100);
+        let (_, expiration_date) = node.kv_store.lock().unwrap().get(&[1; 32]).cloned().unwrap();
+        assert!(expiration_date <= now_millis() + 100_000);
+    }
+
+    #[tokio::test]
+    async fn put_rejects_values_larger_than_max_value_bytes() {
+        let config = Config { max_value_bytes: 8, ..test_config("127.0.0.1:6000") };
+        let (service, node) = new_test_service_with_config(config, "127.0.0.1:6000").await;
+
+        assert!(service.put(Request::new(put_request(&[1; 32], "12345678", 100))).await.is_ok());
+        let status = service.put(Request::new(put_request(&[2; 32], "123456789", 100))).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+
+        let kv_store_guard = node.kv_store.lock().unwrap();
+        assert_eq!(kv_store_guard.get(&[1; 32]).map(|(value, _)| value.as_str()), Some("12345678"));
+        assert!(kv_store_guard.get(&[2; 32]).is_none());
+    }
+
+    #[tokio::test]
+    async fn put_with_huge_ttl_does_not_overflow() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let response = service.put(Request::new(put_request(&[1; 32], "value", u64::MAX))).await.unwrap().into_inner();
+
+        assert_eq!(response.effective_ttl, u64::MAX);
+        assert_eq!(node.kv_store.lock().unwrap().get(&[1; 32]).unwrap().1, u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn millisecond_ttl_expires_at_the_right_time() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some(node.address.clone().into());
+        let get_status = || async {
+            service.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await.unwrap().into_inner().status
+        };
+
+        let put_time = now_millis();
+        let response = service.put(Request::new(PutRequest { ttl_unit: TtlUnit::Milliseconds.into(), ..put_request(&[1; 32], "value", 300) })).await.unwrap().into_inner();
+        assert_eq!(response.effective_ttl, 300);
+        let (_, expiration_date) = node.kv_store.lock().unwrap().get(&[1; 32]).cloned().unwrap();
+        assert!((put_time + 300..=now_millis() + 300).contains(&expiration_date));
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(get_status().await, i32::from(GetStatus::Ok));
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(get_status().await, i32::from(GetStatus::Expired));
+    }
+
+    #[tokio::test]
+    async fn get_before_the_first_notify_is_forwarded_to_the_responsible_node() {
+        let ring = spawn_test_ring(3).await;
+        let address = "127.0.0.1:6100".to_string();
+        let pos = hash(address.as_bytes());
+        // the node just joined in front of its successor, but no predecessor notified it yet
+        let successor_index = ring.iter().position(|node| hash(node.address.as_bytes()) >= pos).unwrap_or(0);
+        let (service, node) = new_test_service(&address, &ring[successor_index].address).await;
+        assert!(node.predecessor_option.lock().unwrap().is_none());
+
+        let owner = &ring[(successor_index + 1) % ring.len()];
+        let owner_predecessor_pos = hash(ring[successor_index].address.as_bytes());
+        let key: Key = (0u64..)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[..8].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), owner_predecessor_pos.wrapping_add(1), hash(owner.address.as_bytes()), false, false))
+            .unwrap();
+        owner.kv_store.lock().unwrap().insert(key, ("value".to_string(), u64::MAX));
+
+        let response = service.get(Request::new(GetRequest { key: key.to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::Ok));
+        assert_eq!(response.value, "value");
+    }
+
+    #[tokio::test]
+    async fn writes_before_the_first_notify_are_forwarded_like_gets() {
+        let ring = spawn_test_ring(3).await;
+        let address = "127.0.0.1:6100".to_string();
+        let pos = hash(address.as_bytes());
+        let successor_index = ring.iter().position(|node| hash(node.address.as_bytes()) >= pos).unwrap_or(0);
+        let (service, node) = new_test_service(&address, &ring[successor_index].address).await;
+        assert!(node.predecessor_option.lock().unwrap().is_none());
+
+        let owner = &ring[(successor_index + 1) % ring.len()];
+        let owner_predecessor_pos = hash(ring[successor_index].address.as_bytes());
+        let key: Key = (0u64..)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[..8].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), owner_predecessor_pos.wrapping_add(1), hash(owner.address.as_bytes()), false, false))
+            .unwrap();
+
+        let put_if_request = PutIfRequest { key: key.to_vec(), value: "value".to_string(), ttl: 100, ttl_unit: TtlUnit::Seconds.into(), expected_value: String::new() };
+        assert!(service.put_if(Request::new(put_if_request)).await.unwrap().into_inner().swapped);
+        assert!(owner.kv_store.lock().unwrap().contains_key(&key));
+
+        let touch_request = TouchRequest { key: key.to_vec(), ttl: 10, ttl_unit: TtlUnit::Seconds.into(), is_replica: false, primary: String::default() };
+        assert_eq!(service.touch(Request::new(touch_request)).await.unwrap().into_inner().status, i32::from(TouchStatus::Ok));
+
+        let delete_if_request = DeleteIfRequest { key: key.to_vec(), expected_value: "value".to_string() };
+        assert!(service.delete_if(Request::new(delete_if_request)).await.unwrap().into_inner().deleted);
+        assert!(!owner.kv_store.lock().unwrap().contains_key(&key));
+
+        let delete_request = DeleteRequest { key: key.to_vec(), replication: 0, is_replica: false, primary: String::default() };
+        assert_eq!(service.delete(Request::new(delete_request)).await.unwrap().into_inner().status, i32::from(DeleteStatus::NotFound));
+        assert!(node.kv_store.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn node_with_reachable_successor_forwards_gets_despite_serve_when_isolated() {
+        let successor = spawn_test_node(None).await;
+        let config = Config { serve_when_isolated: true, ..test_config("127.0.0.1:6000") };
+        let (service, node) = new_test_service_with_config(config, &successor.address).await;
+        let key: Key = (0u32..)
+            .map(|i| {
+                let mut key = [0; 32];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), service.pos.wrapping_add(1), hash(successor.address.as_bytes()), false, false))
+            .unwrap();
+        node.kv_store.lock().unwrap().insert(key, ("outdated".to_string(), u64::MAX));
+        successor.kv_store.lock().unwrap().insert(key, ("current".to_string(), u64::MAX));
+
+        // the predecessor is unknown, but the successor is alive, so the node is not isolated
+        service.stabilize(Request::new(Empty {})).await.unwrap();
+        *node.predecessor_option.lock().unwrap() = None;
+        let response = service.get(Request::new(GetRequest { key: key.to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.value, "current");
+    }
+
+    #[tokio::test]
+    async fn isolated_node_serves_local_data_and_hands_it_over_after_recovery() {
+        // nothing listens on this address anymore, so the node has no reachable neighbor
+        let unreachable_address = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().to_string();
+        let config = Config { serve_when_isolated: true, ..test_config("127.0.0.1:6000") };
+        let (service, _) = new_test_service_with_config(config, &unreachable_address).await;
+        let key: Key = [7; 32];
+
+        let status = service.stabilize(Request::new(Empty {})).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unavailable);
+        service.put(Request::new(put_request(&key, "written while isolated", 100))).await.unwrap();
+        let response = service.get(Request::new(GetRequest { key: key.to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.value, "written while isolated");
+
+        // once the partition heals, the owner of the key notifies the node and receives the pair
+        let owner_address = (0..).map(|i| format!("127.0.0.1:{}", 7000 + i))
+            .find(|address| is_between(hash(&key), service.pos.wrapping_add(1), hash(address.as_bytes()), false, false))
+            .unwrap();
+        let request = NotifyRequest {
+            pow_token: Some(PowToken::generate(&owner_address, 0, 1).into()),
+            address: Some(owner_address.into()),
+        };
+        let mut stream = service.notify(Request::new(request)).await.unwrap().into_inner();
+        let batch = tokio_stream::StreamExt::next(&mut stream).await.unwrap().unwrap();
+        assert_eq!(batch.pairs[0].key, key.to_vec());
+        assert_eq!(batch.pairs[0].value, "written while isolated");
+    }
+
+    #[tokio::test]
+    async fn interleaved_write_prevents_conditional_delete() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some(node.address.clone().into());
+        let delete_if_request = |expected_value: &str| Request::new(DeleteIfRequest {
+            key: [1; 32].to_vec(),
+            expected_value: expected_value.to_string(),
+        });
+
+        service.put(Request::new(put_request(&[1; 32], "read by the cleaner", 100))).await.unwrap();
+        let read_value = service.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await.unwrap().into_inner().value;
+        // another client overwrites the pair between the cleaner's read and delete
+        service.put(Request::new(put_request(&[1; 32], "written concurrently", 100))).await.unwrap();
+
+        assert!(!service.delete_if(delete_if_request(&read_value)).await.unwrap().into_inner().deleted);
+        assert_eq!(node.kv_store.lock().unwrap().get(&[1; 32]).unwrap().0, "written concurrently");
+
+        assert!(service.delete_if(delete_if_request("written concurrently")).await.unwrap().into_inner().deleted);
+        assert!(node.kv_store.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn only_one_of_two_concurrent_swaps_succeeds() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some(node.address.clone().into());
+        let service = Arc::new(service);
+        let put_if_request = |value: &str, expected_value: &str| Request::new(PutIfRequest {
+            key: [1; 32].to_vec(),
+            value: value.to_string(),
+            ttl: 100,
+            ttl_unit: TtlUnit::Seconds.into(),
+            expected_value: expected_value.to_string(),
+        });
+
+        // an empty expected value only matches an absent key
+        assert!(service.put_if(put_if_request("unlocked", "")).await.unwrap().into_inner().swapped);
+        assert!(!service.put_if(put_if_request("unlocked", "")).await.unwrap().into_inner().swapped);
+
+        let swaps: Vec<_> = ["owner a", "owner b"].into_iter()
+            .map(|owner| {
+                let service = service.clone();
+                let request = put_if_request(owner, "unlocked");
+                tokio::spawn(async move { service.put_if(request).await.unwrap().into_inner().swapped })
+            })
+            .collect();
+        let mut swapped_count = 0;
+        for swap in swaps {
+            if swap.await.unwrap() {
+                swapped_count += 1;
+            }
+        }
+        assert_eq!(swapped_count, 1);
+        let stored_value = node.kv_store.lock().unwrap().get(&[1; 32]).unwrap().0.clone();
+        assert!(stored_value == "owner a" || stored_value == "owner b");
+    }
+
+    #[tokio::test]
+    async fn node_keeps_serving_after_a_panic_poisoned_the_kv_store() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some(node.address.clone().into());
+        let kv_store = node.kv_store.clone();
+        let _ = std::thread::spawn(move || {
+            let _kv_store_guard = kv_store.lock().unwrap();
+            panic!("handler panics while holding the kv_store lock");
+        }).join();
+        assert!(node.kv_store.is_poisoned());
+
+        service.put(Request::new(put_request(&[1; 32], "value", 100))).await.unwrap();
+        let response = service.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.value, "value");
+    }
+
+    #[tokio::test]
+    async fn kv_store_data_renders_keys_in_the_configured_encoding() {
+        let mut key: Key = [0; 32];
+        key[..5].copy_from_slice(b"hello");
+        let expected_renderings = [
+            (KeyEncoding::Hex, format!("68656c6c6f{}", "00".repeat(27))),
+            (KeyEncoding::Base64, "aGVsbG8AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".to_string()),
+            (KeyEncoding::DecimalBytes, format!("104 101 108 108 111{}", " 0".repeat(27))),
+        ];
+
+        for (key_encoding, expected_rendering) in expected_renderings {
+            let config = Config { key_encoding, ..test_config("127.0.0.1:6000") };
+            let (service, node) = new_test_service_with_config(config, "127.0.0.1:6000").await;
+            node.kv_store.lock().unwrap().insert(key, ("world".to_string(), u64::MAX));
+
+            let kv_pairs = service.get_kv_store_data(Request::new(Empty {})).await.unwrap().into_inner().kv_pairs;
+            assert_eq!(kv_pairs[0].key, expected_rendering);
+            assert_eq!(key_encoding.encode(&key), expected_rendering);
+        }
+    }
+
+    #[tokio::test]
+    async fn expired_pair_is_only_kept_with_serve_stale() {
+        for serve_stale in [false, true] {
+            let config = Config { serve_stale, ..test_config("127.0.0.1:6000") };
+            let (service, node) = new_test_service_with_config(config, "127.0.0.1:6000").await;
+            *node.predecessor_option.lock().unwrap() = Some(node.address.clone().into());
+            node.kv_store.lock().unwrap().insert([1; 32], ("stale".to_string(), 1));
+
+            let response = service.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await.unwrap().into_inner();
+            assert_eq!(response.status, i32::from(GetStatus::Expired));
+            assert_eq!(response.value, "stale");
+            assert_eq!(node.kv_store.lock().unwrap().contains_key(&[1; 32]), serve_stale);
+
+            let response = service.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await.unwrap().into_inner();
+            let expected_status = if serve_stale { GetStatus::Expired } else { GetStatus::NotFound };
+            assert_eq!(response.status, i32::from(expected_status));
+        }
+    }
+
+    #[tokio::test]
+    async fn compact_drops_expired_pairs() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        {
+            let mut kv_store_guard = node.kv_store.lock().unwrap();
+            kv_store_guard.insert([1; 32], ("expired".to_string(), 0));
+            kv_store_guard.insert([2; 32], ("alive".to_string(), u64::MAX));
+        }
+
+        let response = service.compact(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(response.reclaimed_keys, 1);
+        assert_eq!(response.reclaimed_bytes, (32 + "expired".len() + 8) as u64);
+        assert_eq!(node.kv_store.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stats_report_the_expired_pairs_of_the_last_sweep() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        {
+            let mut kv_store_guard = node.kv_store.lock().unwrap();
+            kv_store_guard.insert([1; 32], ("expired".to_string(), 0));
+            kv_store_guard.insert([2; 32], ("alive".to_string(), u64::MAX));
+        }
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.kv_store_size, 2);
+        assert_eq!(stats.expired_kv_store_size, 0);
+
+        // as with serve_stale, the sweep only counts the expired pair
+        sweep_expired(&node.kv_store, 1, false, &node.stats).await;
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.kv_store_size, 2);
+        assert_eq!(stats.expired_kv_store_size, 1);
+    }
+
+    #[tokio::test]
+    async fn frozen_forced_successor_is_not_corrected() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        service.force_successor(Request::new("127.0.0.1:6009".to_string().into())).await.unwrap();
+        service.freeze_successor(Request::new(Empty {})).await.unwrap();
+
+        service.set_successor(&"127.0.0.1:6002".to_string()).await;
+        assert_eq!(service.get_successor_address().await.unwrap(), "127.0.0.1:6009");
+        assert_eq!(node.finger_table.lock().unwrap().fingers[0].address, "127.0.0.1:6009");
+
+        service.unfreeze_successor(Request::new(Empty {})).await.unwrap();
+        service.set_successor(&"127.0.0.1:6002".to_string()).await;
+        assert_eq!(service.get_successor_address().await.unwrap(), "127.0.0.1:6002");
+    }
+
+    #[tokio::test]
+    async fn lookup_without_successors_is_unavailable() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        node.successor_list.lock().unwrap().successors.clear();
+
+        let status = service.find_successor(Request::new(HashPos::MAX.into())).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+    }
+
+    #[tokio::test]
+    async fn closest_preceding_finger_skips_uninitialized_fingers() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let own_pos = hash(node.address.as_bytes());
+        let finger_address = "127.0.0.1:6005".to_string();
+        let key = hash(finger_address.as_bytes()).wrapping_add(1);
+
+        // no finger is initialized, so the node itself is the closest preceding node
+        node.finger_table.lock().unwrap().fingers[0].address = Address::default();
+        let closest = service.find_closest_preceding_finger(Request::new(key.into())).await.unwrap().into_inner();
+        assert_eq!(closest.address, node.address);
+        assert_eq!(HashPos::from_be_bytes(closest.id.try_into().unwrap()), own_pos);
+
+        node.finger_table.lock().unwrap().fingers[3].address = finger_address.clone();
+        let closest = service.find_closest_preceding_finger(Request::new(key.into())).await.unwrap().into_inner();
+        assert_eq!(closest.address, finger_address);
+    }
+
+    #[tokio::test]
+    async fn unset_predecessor_is_returned_as_none() {
+        let (service, _) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let response = service.get_predecessor(Request::new(Empty {})).await.unwrap().into_inner();
+        assert!(response.address_optional.is_none());
+    }
+
+    #[tokio::test]
+    async fn notify_handoff_is_accounted_in_sent_bytes() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let key: Key = [7; 32];
+        node.kv_store.lock().unwrap().insert(key, ("value".to_string(), u64::MAX));
+        let caller_address = (0..).map(|i| format!("127.0.0.1:{}", 7000 + i))
+            // without predecessor, the node hands off everything between itself and the caller
+            .find(|address| is_between(hash(&key), service.pos.wrapping_add(1), hash(address.as_bytes()), false, false))
+            .unwrap();
+
+        let request = NotifyRequest {
+            pow_token: Some(PowToken::generate(&caller_address, 0, 1).into()),
+            address: Some(caller_address.into()),
+        };
+        let mut stream = service.notify(Request::new(request)).await.unwrap().into_inner();
+        while tokio_stream::StreamExt::next(&mut stream).await.is_some() {}
+
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.handoff_bytes_sent, 32 + "value".len() as u64);
+        assert_eq!(stats.handoff_bytes_received, 0);
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_handoff_pairs_are_retained_when_the_stream_breaks() {
+        let config = Config { handoff_batch_size: 1, ..test_config("127.0.0.1:6000") };
+        let (service, node) = new_test_service_with_config(config, "127.0.0.1:6001").await;
+        let caller_address = "127.0.0.1:7000".to_string();
+        let caller_pos = hash(caller_address.as_bytes());
+        // without predecessor, the node hands off everything between itself and the caller
+        let keys: Vec<Key> = (0u32..)
+            .map(|i| {
+                let mut key = [0; 32];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .filter(|key| is_between(hash(key), service.pos.wrapping_add(1), caller_pos, false, false))
+            .take(3)
+            .collect();
+        for key in &keys {
+            node.kv_store.lock().unwrap().insert(*key, ("value".to_string(), u64::MAX));
+        }
+
+        let request = NotifyRequest {
+            pow_token: Some(PowToken::generate(&caller_address, 0, 1).into()),
+            address: Some(caller_address.clone().into()),
+        };
+        let mut stream = service.notify(Request::new(request)).await.unwrap().into_inner();
+        let batch = tokio_stream::StreamExt::next(&mut stream).await.unwrap().unwrap();
+        assert_eq!(node.kv_store.lock().unwrap().len(), 3);
+        let acknowledged_key: Key = batch.pairs[0].key.clone().try_into().unwrap();
+        service.ack_handoff(Request::new(HandoffAckMsg {
+            keys: vec![acknowledged_key.to_vec()],
+            address: Some(caller_address.into()),
+            rejected_keys: vec![],
+        })).await.unwrap();
+        // the receiver fails before it got the remaining batches
+        drop(stream);
+        sleep(Duration::from_millis(50)).await;
+
+        let kv_store_guard = node.kv_store.lock().unwrap();
+        assert!(!kv_store_guard.contains_key(&acknowledged_key));
+        assert_eq!(kv_store_guard.len(), 2);
+        assert!(keys.iter().filter(|key| **key != acknowledged_key).all(|key| kv_store_guard.contains_key(key)));
+    }
+
+    #[tokio::test]
+    async fn ack_handoff_only_removes_pairs_sent_to_the_predecessor() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let caller_address = "127.0.0.1:7000".to_string();
+        *node.predecessor_option.lock().unwrap() = Some(caller_address.clone().into());
+        node.kv_store.lock().unwrap().insert([1; 32], ("value".to_string(), u64::MAX));
+        node.kv_store.lock().unwrap().insert([2; 32], ("value".to_string(), u64::MAX));
+        service.pending_handoffs.lock().unwrap().insert([2; 32], caller_address.clone());
+        let ack = |address: &str| Request::new(HandoffAckMsg {
+            keys: vec![[1; 32].to_vec(), [2; 32].to_vec()],
+            address: Some(address.to_string().into()),
+            rejected_keys: vec![],
+        });
+
+        let status = service.ack_handoff(ack("127.0.0.1:7001")).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(node.kv_store.lock().unwrap().len(), 2);
+
+        // [1; 32] was never handed off, e.g. it is owned or a replica
+        service.ack_handoff(ack(&caller_address)).await.unwrap();
+        assert!(node.kv_store.lock().unwrap().contains_key(&[1; 32]));
+        assert!(!node.kv_store.lock().unwrap().contains_key(&[2; 32]));
+        assert!(service.pending_handoffs.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejected_handoff_pairs_are_kept_and_no_longer_pending() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let caller_address = "127.0.0.1:7000".to_string();
+        *node.predecessor_option.lock().unwrap() = Some(caller_address.clone().into());
+        node.kv_store.lock().unwrap().insert([1; 32], ("x".repeat(MAX_VALUE_BYTES_DEFAULT + 1), u64::MAX));
+        service.pending_handoffs.lock().unwrap().insert([1; 32], caller_address.clone());
+
+        service.ack_handoff(Request::new(HandoffAckMsg {
+            keys: vec![],
+            address: Some(caller_address.into()),
+            rejected_keys: vec![[1; 32].to_vec()],
+        })).await.unwrap();
+        assert!(node.kv_store.lock().unwrap().contains_key(&[1; 32]));
+        assert!(service.pending_handoffs.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unacknowledged_pairs_are_not_handed_off_to_the_same_receiver_again() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let caller_address = "127.0.0.1:7000".to_string();
+        let caller_pos = hash(caller_address.as_bytes());
+        let key: Key = (0u32..)
+            .map(|i| {
+                let mut key = [0; 32];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), service.pos.wrapping_add(1), caller_pos, false, false))
+            .unwrap();
+        node.kv_store.lock().unwrap().insert(key, ("value".to_string(), u64::MAX));
+        let notify_and_collect = || async {
+            let request = NotifyRequest {
+                pow_token: Some(PowToken::generate(&caller_address, 0, 1).into()),
+                address: Some(caller_address.clone().into()),
+            };
+            let mut stream = service.notify(Request::new(request)).await.unwrap().into_inner();
+            let mut handed_off_keys = Vec::new();
+            while let Some(batch) = tokio_stream::StreamExt::next(&mut stream).await {
+                handed_off_keys.extend(batch.unwrap().pairs.into_iter().map(|pair| pair.key));
+            }
+            handed_off_keys
+        };
+
+        assert_eq!(notify_and_collect().await, vec![key.to_vec()]);
+        // the ack got lost and the predecessor was reset, e.g. by a failed health check
+        *node.predecessor_option.lock().unwrap() = None;
+        assert!(notify_and_collect().await.is_empty());
+        assert!(node.kv_store.lock().unwrap().contains_key(&key));
+    }
+
+    #[tokio::test]
+    async fn joined_pairs_are_kept_until_the_newcomer_acknowledges_them() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let caller_address = "127.0.0.1:7000".to_string();
+        let caller_pos = hash(caller_address.as_bytes());
+        let key: Key = (0u32..)
+            .map(|i| {
+                let mut key = [0; 32];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), service.pos.wrapping_add(1), caller_pos, false, false))
+            .unwrap();
+        node.kv_store.lock().unwrap().insert(key, ("value".to_string(), u64::MAX));
+
+        let join_response = service.join(Request::new(JoinRequest {
+            address: Some(caller_address.clone().into()),
+            pow_token: Some(PowToken::generate(&caller_address, 0, 1).into()),
+        })).await.unwrap().into_inner();
+        assert_eq!(join_response.pairs.len(), 1);
+        // the newcomer may fail before storing the pairs
+        assert!(node.kv_store.lock().unwrap().contains_key(&key));
+
+        service.ack_handoff(Request::new(HandoffAckMsg {
+            keys: vec![key.to_vec()],
+            address: Some(caller_address.into()),
+            rejected_keys: vec![],
+        })).await.unwrap();
+        assert!(!node.kv_store.lock().unwrap().contains_key(&key));
+    }
+
+    #[tokio::test]
+    async fn stats_report_routing_health_of_a_fresh_node() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        node.kv_store.lock().unwrap().insert([1; 32], ("value".to_string(), u64::MAX));
+
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.kv_store_size, 1);
+        assert_eq!(stats.successor_list_len, 1);
+        // only the successor is known before fix_fingers ran
+        assert_eq!(stats.initialized_fingers, 1);
+        assert!(!stats.has_predecessor);
+        assert_eq!(stats.pow_difficulty, 0);
+
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:6002".to_string().into());
+        node.finger_table.lock().unwrap().fingers[1].address = "127.0.0.1:6001".to_string();
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.initialized_fingers, 2);
+        assert!(stats.has_predecessor);
+    }
+
+    #[tokio::test]
+    async fn ping_measures_a_round_trip_time() {
+        let node = spawn_test_node(None).await;
+        let mut client = connect_with_retry(&node.address).await.unwrap();
+        assert!(ping(&mut client).await.unwrap() > Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn stabilize_reports_the_rtt_to_the_successor_in_the_summary() {
+        let successor = spawn_test_node(None).await;
+        let (service, _) = new_test_service("127.0.0.1:6000", &successor.address).await;
+
+        service.stabilize(Request::new(Empty {})).await.unwrap();
+
+        let summary = service.get_node_summary(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(summary.link_rtts.len(), 1);
+        assert_eq!(summary.link_rtts[0].address, successor.address);
+        assert!(summary.link_rtts[0].rtt_micros > 0);
+    }
+
+    #[tokio::test]
+    async fn separated_rings_merge_once_a_seed_node_is_reachable() {
+        // the seed's port is bound, but nobody answers on it until the partition is over
+        let seed_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let seed_address = seed_listener.local_addr().unwrap().to_string();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let config = Config { seed_nodes: vec![seed_address.clone()], ..test_config(&address) };
+        let node = serve_test_node(listener, config, &address).await;
+        let service = node.service.clone().unwrap();
+        let mut client = connect_with_retry(&node.address).await.unwrap();
+
+        assert!(!service.detect_foreign_ring().await.unwrap());
+
+        // the seed formed a ring of its own in the meantime and stores a pair the node owns once
+        // it joins the seed's ring
+        let seed = serve_test_node(seed_listener, test_config(&seed_address), &seed_address).await;
+        let mut seed_client = connect_with_retry(&seed.address).await.unwrap();
+        let key = (0..=u8::MAX).map(|i| [i; 32])
+            .find(|key| is_between(hash(key), hash(seed.address.as_bytes()).wrapping_add(1), hash(node.address.as_bytes()), false, false))
+            .unwrap();
+        seed.kv_store.lock().unwrap().insert(key, ("value".to_string(), u64::MAX));
+
+        // the rejoin runs the join: the pair of the node's range is handed over
+        assert!(service.detect_foreign_ring().await.unwrap());
+        assert!(node.kv_store.lock().unwrap().contains_key(&key));
+        assert!(!seed.kv_store.lock().unwrap().contains_key(&key));
+        for _ in 0..2 {
+            client.stabilize(Request::new(Empty {})).await.unwrap();
+            seed_client.stabilize(Request::new(Empty {})).await.unwrap();
+        }
+
+        assert_eq!(node.successor_list.lock().unwrap().successors[0], seed.address);
+        assert_eq!(seed.successor_list.lock().unwrap().successors[0], node.address);
+        assert_eq!(node.predecessor_option.lock().unwrap().clone().unwrap().address, seed.address);
+        assert_eq!(seed.predecessor_option.lock().unwrap().clone().unwrap().address, node.address);
+        // once merged, the seed's ring is the node's own ring
+        assert!(!service.detect_foreign_ring().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn notify_flood_raises_the_pow_difficulty() {
+        let (service, _) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let notify_request = |pow_difficulty| NotifyRequest {
+            address: Some("127.0.0.1:7000".to_string().into()),
+            pow_token: Some(PowToken::generate("127.0.0.1:7000", pow_difficulty, 1).into()),
+        };
+
+        for _ in 1..NOTIFY_RATE_THRESHOLD {
+            assert!(service.notify(Request::new(notify_request(0))).await.is_ok());
+        }
+        assert_eq!(*service.pow_difficulty.lock().unwrap(), 0);
+
+        // the call reaching the threshold raises the difficulty for the following calls
+        assert!(service.notify(Request::new(notify_request(0))).await.is_ok());
+        assert_eq!(*service.pow_difficulty.lock().unwrap(), 1);
+        let status = service.notify(Request::new(notify_request(0))).await.err().unwrap();
+        assert_eq!(raised_pow_difficulty(&status, 0), Some(1));
+        assert!(service.notify(Request::new(notify_request(1))).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejected_notify_calls_do_not_raise_the_pow_difficulty() {
+        let config = Config { pow_difficulty: 1, ..test_config("127.0.0.1:6000") };
+        let (service, _) = new_test_service_with_config(config, "127.0.0.1:6001").await;
+        let forged_token = (0..)
+            .map(|nonce| PowToken { timestamp: now_millis() / 1000, nonce, pow_difficulty: 1 })
+            .find(|token| !token.validate("127.0.0.1:7000", 1).1)
+            .unwrap();
+
+        for _ in 0..2 * NOTIFY_RATE_THRESHOLD {
+            let _ = service.notify(Request::new(NotifyRequest {
+                address: Some("127.0.0.1:7000".to_string().into()),
+                pow_token: Some(forged_token.clone().into()),
+            })).await;
+        }
+        assert_eq!(*service.pow_difficulty.lock().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn stabilize_regenerates_its_token_at_the_successors_difficulty() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let successor_address = listener.local_addr().unwrap().to_string();
+        // the successor requires a higher difficulty than the node's configured one
+        let successor = serve_test_node(listener, Config { pow_difficulty: 1, ..test_config(&successor_address) }, &successor_address).await;
+        let (service, _) = new_test_service("127.0.0.1:6000", &successor.address).await;
+
+        service.stabilize(Request::new(Empty {})).await.unwrap();
+        assert_eq!(successor.predecessor_option.lock().unwrap().clone().unwrap().address, "127.0.0.1:6000");
+    }
+
+    #[tokio::test]
+    async fn notify_with_the_token_of_another_node_is_rejected() {
+        let config = Config { pow_difficulty: 2, ..test_config("127.0.0.1:6000") };
+        let (service, node) = new_test_service_with_config(config, "127.0.0.1:6001").await;
+        let sniffed_token = PowToken::generate("127.0.0.1:7000", 2, 1);
+
+        let status = service.notify(Request::new(NotifyRequest {
+            address: Some("127.0.0.1:7001".to_string().into()),
+            pow_token: Some(sniffed_token.clone().into()),
+        })).await.err().unwrap();
+        assert_eq!(status.code(), Code::Cancelled);
+        assert!(node.predecessor_option.lock().unwrap().is_none());
+
+        assert!(service.notify(Request::new(NotifyRequest {
+            address: Some("127.0.0.1:7000".to_string().into()),
+            pow_token: Some(sniffed_token.into()),
+        })).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn each_stabilize_induced_successor_change_is_counted() {
+        let (service, _) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let mut nodes = Vec::new();
+        for _ in 0..4 {
+            nodes.push(spawn_test_node(None).await);
+        }
+        // ordered from the farthest to the closest node in front of the service's node
+        nodes.sort_by_key(|node| std::cmp::Reverse(hash(node.address.as_bytes()).wrapping_sub(service.pos)));
+        for i in 0..3 {
+            *nodes[i].predecessor_option.lock().unwrap() = Some(nodes[i + 1].address.clone().into());
+        }
+        service.set_successor(&nodes[0].address).await;
+        let initial_changes = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner().successor_changes;
+
+        // every stabilize learns about a closer node from the successor's predecessor
+        for node in &nodes[1..] {
+            service.stabilize(Request::new(Empty {})).await.unwrap();
+            assert_eq!(service.get_successor_address().await.unwrap(), node.address);
+        }
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.successor_changes, initial_changes + 3);
+
+        // nothing changes anymore once the ring is stable
+        service.stabilize(Request::new(Empty {})).await.unwrap();
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.successor_changes, initial_changes + 3);
+    }
+
+    #[tokio::test]
+    async fn concurrent_notify_handoffs_respect_the_limit() {
+        let config = Config { max_concurrent_handoffs: 2, ..test_config("127.0.0.1:6000") };
+        let (service, node) = new_test_service_with_config(config, "127.0.0.1:6001").await;
+        // two long running handoffs occupy all slots
+        let running_handoffs = node.handoff_semaphore.clone().acquire_many_owned(2).await.unwrap();
+
+        // each caller lies closer in front of the node than the previous one, so every notify
+        // makes the caller the new predecessor and requests a handoff
+        let mut caller_addresses: Vec<Address> = (0..3).map(|i| format!("127.0.0.1:{}", 7000 + i)).collect();
+        caller_addresses.sort_by_key(|address| hash(address.as_bytes()).wrapping_sub(service.pos));
+        let mut streams = Vec::new();
+        for caller_address in caller_addresses {
+            let request = NotifyRequest {
+                pow_token: Some(PowToken::generate(&caller_address, 0, 1).into()),
+                address: Some(caller_address.into()),
+            };
+            streams.push(service.notify(Request::new(request)).await.unwrap().into_inner());
+        }
+        sleep(Duration::from_millis(100)).await;
+
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.handoffs_in_flight, 2);
+        assert_eq!(stats.handoffs_queued, 3);
+        for stream in streams.iter_mut() {
+            assert!(timeout(Duration::from_millis(50), tokio_stream::StreamExt::next(stream)).await.is_err());
+        }
+
+        drop(running_handoffs);
+        for stream in streams.iter_mut() {
+            while tokio_stream::StreamExt::next(stream).await.is_some() {}
+        }
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.handoffs_in_flight, 0);
+        assert_eq!(stats.handoffs_queued, 0);
+    }
+
+    #[tokio::test]
+    async fn crashed_neighbor_is_detected_within_one_announce_interval() {
+        let successor = spawn_test_node(None).await;
+        // nothing listens on this address anymore, like on a crashed node
+        let crashed_address = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().to_string();
+
+        let (service, node) = new_test_service("127.0.0.1:6000", &successor.address).await;
+        node.successor_list.lock().unwrap().successors.push(crashed_address.clone());
+        *node.predecessor_option.lock().unwrap() = Some(crashed_address.clone().into());
+        let expiry = Duration::from_millis(DEAD_MARK_EXPIRY_MILLIS);
+
+        timeout(service.announce_interval, service.announce()).await
+            .expect("announce took longer than one interval");
+
+        assert!(node.liveness.lock().unwrap().is_dead(&crashed_address, expiry));
+        assert!(!node.liveness.lock().unwrap().is_dead(&successor.address, expiry));
+        assert!(successor.liveness.lock().unwrap().last_heartbeats.contains_key(&node.address));
+    }
+
+    #[tokio::test]
+    async fn closest_preceding_finger_skips_dead_fingers() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        let dead_finger = "127.0.0.1:6005".to_string();
+        let key = hash(dead_finger.as_bytes()).wrapping_add(1);
+        node.finger_table.lock().unwrap().fingers[0].address = Address::default();
+        node.finger_table.lock().unwrap().fingers[3].address = dead_finger.clone();
+        node.liveness.lock().unwrap().mark_dead(&dead_finger);
+
+        let closest = service.find_closest_preceding_finger(Request::new(key.into())).await.unwrap().into_inner();
+        assert_eq!(closest.address, node.address);
+    }
+
+    /// node 6000 with node 6001 being both its predecessor and successor
+    async fn new_consistent_test_service() -> (ChordService, TestNode) {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:6001".to_string().into());
+        (service, node)
+    }
+
+    async fn self_check_violations(service: &ChordService) -> Vec<String> {
+        service.self_check(Request::new(Empty {})).await.unwrap().into_inner().violations
+    }
+
+    #[tokio::test]
+    async fn self_check_passes_on_consistent_node() {
+        let (service, _) = new_consistent_test_service().await;
+        assert_eq!(self_check_violations(&service).await, Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn self_check_detects_each_violated_invariant() {
+        // predecessor between the node and its successor
+        let (service, node) = new_consistent_test_service().await;
+        let own_pos = hash(node.address.as_bytes());
+        let successor_distance = hash("127.0.0.1:6001".as_bytes()).wrapping_sub(own_pos);
+        let predecessor = (0..).map(|i| format!("127.0.0.1:{}", 7000 + i))
+            .find(|address| hash(address.as_bytes()).wrapping_sub(own_pos) < successor_distance)
+            .unwrap();
+        *node.predecessor_option.lock().unwrap() = Some(predecessor.into());
+        assert_eq!(self_check_violations(&service).await.len(), 1);
+
+        // fingers going backwards
+        let (service, node) = new_consistent_test_service().await;
+        let mut finger_addresses: Vec<Address> = (0..2).map(|i| format!("127.0.0.1:{}", 7000 + i)).collect();
+        finger_addresses.sort_by_key(|address| std::cmp::Reverse(hash(address.as_bytes()).wrapping_sub(own_pos)));
+        node.finger_table.lock().unwrap().fingers[10].address = finger_addresses[0].clone();
+        node.finger_table.lock().unwrap().fingers[11].address = finger_addresses[1].clone();
+        let violations = self_check_violations(&service).await;
+        assert!(violations.iter().any(|violation| violation.starts_with("Finger 11")), "{:?}", violations);
+
+        // gap in the successor list
+        let (service, node) = new_consistent_test_service().await;
+        node.successor_list.lock().unwrap().successors.push(Address::default());
+        assert_eq!(self_check_violations(&service).await, vec!["Successor list has a gap at index 1".to_string()]);
+
+        // key outside of the owned range
+        let (service, node) = new_consistent_test_service().await;
+        let predecessor_pos = hash("127.0.0.1:6001".as_bytes());
+        let foreign_key: Key = (0u8..).map(|i| [i; 32])
+            .find(|key| !is_between(hash(key), predecessor_pos.wrapping_add(1), own_pos, false, false))
+            .unwrap();
+        node.kv_store.lock().unwrap().insert(foreign_key, ("value".to_string(), u64::MAX));
+        let violations = self_check_violations(&service).await;
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("1 stored keys lie outside"));
+    }
+
+    #[tokio::test]
+    async fn each_finger_fix_strategy_converges_on_static_ring() {
+        let ring = spawn_test_ring(3).await;
+        let positions: Vec<HashPos> = ring.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let responsible_address = |key: HashPos| ring[positions.iter().position(|pos| key <= *pos).unwrap_or(0)].address.clone();
+
+        for strategy in [FingerFixStrategy::RoundRobin, FingerFixStrategy::Random, FingerFixStrategy::WeightedLow] {
+            let address = "127.0.0.1:6200";
+            let config = Config { finger_fix_strategy: strategy, ..test_config(address) };
+            let (service, node) = new_test_service_with_config(config, &responsible_address(hash(address.as_bytes()))).await;
+            let expected: Vec<Address> = node.finger_table.lock().unwrap().fingers.iter()
+                .map(|finger| responsible_address(finger.key))
+                .collect();
+
+            let converged = |node: &TestNode| node.finger_table.lock().unwrap().fingers.iter()
+                .map(|finger| finger.address.clone())
+                .eq(expected.iter().cloned());
+            let mut rounds = 0;
+            while !converged(&node) {
+                assert!(rounds < 10_000, "{:?} did not converge", strategy);
+                service.fix_fingers(Request::new(Empty {})).await.unwrap();
+                rounds += 1;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn short_deadline_cancels_forwarded_lookup() {
+        // the next hop accepts connections but never answers
+        let slow_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let slow_address = slow_listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            loop {
+                sockets.push(slow_listener.accept().await.unwrap());
+            }
+        });
+        let (service, _) = new_test_service("127.0.0.1:6000", &slow_address).await;
+        // beyond the successor, so the lookup is forwarded to it
+        let key = hash(slow_address.as_bytes()).wrapping_add(1);
+
+        let mut request = Request::new(key.into());
+        request.set_timeout(Duration::from_millis(200));
+        let start = Instant::now();
+        let status = service.find_successor(request).await.unwrap_err();
+
+        assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+        assert!(start.elapsed() < Duration::from_secs(1), "lookup took {:?}", start.elapsed());
+    }
+
+    #[tokio::test]
+    async fn local_lookups_are_recorded_separately_from_forwarded_ones() {
+        let (service, _) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        // a node that is its own successor answers every lookup itself
+        service.find_successor(Request::new(42.into())).await.unwrap();
+
+        let stats = service.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+        assert_eq!(stats.local_lookup_latency.unwrap().count, 1);
+        assert_eq!(stats.forwarded_lookup_latency.unwrap().count, 0);
+    }
+
+    async fn scan_all_pages(service: &ChordService, lower: HashPos, upper: HashPos, page_size: u32,
+                            between_pages: impl Fn()) -> Vec<Key> {
+        let mut keys = Vec::new();
+        let mut continuation_token = Vec::new();
+        loop {
+            let page = service.scan_page(Request::new(ScanPageRequest {
+                lower: Some(lower.into()),
+                upper: Some(upper.into()),
+                page_size,
+                continuation_token,
+            })).await.unwrap().into_inner();
+            assert!(page.pairs.len() <= page_size as usize);
+            keys.extend(page.pairs.into_iter().map(|pair| -> Key { pair.key.try_into().unwrap() }));
+            if page.next_continuation_token.is_empty() {
+                return keys;
+            }
+            continuation_token = page.next_continuation_token;
+            between_pages();
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_page_rejects_malformed_continuation_tokens() {
+        let (service, _) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let token = encode_continuation_token(&[7; 32]);
+        assert_eq!(decode_continuation_token(&token).unwrap(), (hash(&[7; 32]), [7; 32]));
+
+        for continuation_token in [token[1..].to_vec(), [token.as_slice(), &[0]].concat()] {
+            let status = service.scan_page(Request::new(ScanPageRequest {
+                lower: Some(0.into()),
+                upper: Some(HashPos::MAX.into()),
+                page_size: 1,
+                continuation_token,
+            })).await.unwrap_err();
+            assert_eq!(status.code(), Code::InvalidArgument);
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_page_returns_each_pair_once_across_page_boundaries() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let keys: Vec<Key> = (0..10u8).map(|i| [i; 32]).collect();
+        for key in &keys {
+            node.kv_store.lock().unwrap().insert(*key, ("value".to_string(), u64::MAX));
+        }
+        node.kv_store.lock().unwrap().insert([100; 32], ("expired".to_string(), 0));
+        let lower = service.pos;
+
+        for page_size in [1, 3, 10, 20] {
+            let scanned = scan_all_pages(&service, lower, lower, page_size, || {}).await;
+            let mut expected = keys.clone();
+            expected.sort_by_key(|key| hash(key).wrapping_sub(lower));
+            assert_eq!(scanned, expected, "page size {}", page_size);
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_streams_all_unexpired_pairs_once_in_pages() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        let keys: Vec<Key> = (0..100u8).map(|i| [i; 32]).collect();
+        for key in &keys {
+            node.kv_store.lock().unwrap().insert(*key, ("value".to_string(), u64::MAX));
+        }
+        node.kv_store.lock().unwrap().insert([200; 32], ("expired".to_string(), 0));
+
+        let mut scanned: Vec<Key> = Vec::new();
+        let mut start = HashPos::MIN;
+        loop {
+            let mut stream = service.scan(Request::new(ScanRequest {
+                start: Some(start.into()),
+                limit: 10,
+                remove_expired: true,
+            })).await.unwrap().into_inner();
+            let mut page: Vec<KvPairMsg> = Vec::new();
+            while let Some(pair) = tokio_stream::StreamExt::next(&mut stream).await {
+                page.push(pair.unwrap());
+            }
+            assert!(page.len() <= 10);
+            scanned.extend(page.iter().map(|pair| -> Key { pair.key.clone().try_into().unwrap() }));
+            match page.last() {
+                Some(last_pair) if page.len() == 10 => start = hash(&last_pair.key) + 1,
+                _ => break
+            }
+        }
+
+        let mut expected = keys.clone();
+        expected.sort_by_key(|key| hash(key));
+        assert_eq!(scanned, expected);
+        assert!(!node.kv_store.lock().unwrap().contains_key(&[200; 32]));
+    }
+
+    #[tokio::test]
+    async fn scan_page_tolerates_concurrent_mutations() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        for i in 0..20u8 {
+            node.kv_store.lock().unwrap().insert([i; 32], ("value".to_string(), u64::MAX));
+        }
+        let mutated_keys = std::cell::Cell::new(20u8);
+        let scanned = scan_all_pages(&service, service.pos, service.pos, 4, || {
+            let i = mutated_keys.get();
+            let mut kv_store_guard = node.kv_store.lock().unwrap();
+            kv_store_guard.remove(&[i - 20; 32]);
+            kv_store_guard.insert([i; 32], ("value".to_string(), u64::MAX));
+            mutated_keys.set(i + 1);
+        }).await;
+
+        let mut deduplicated = scanned.clone();
+        deduplicated.sort();
+        deduplicated.dedup();
+        assert_eq!(deduplicated.len(), scanned.len());
+    }
+
+    #[tokio::test]
+    async fn each_put_appends_one_audit_line_in_order() {
+        let audit_log_path = std::env::temp_dir().join(format!("chord-audit-{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&audit_log_path);
+        let config = Config { audit_log: Some(audit_log_path.to_str().unwrap().to_string()), ..test_config("127.0.0.1:6000") };
+        let (service, _) = new_test_service_with_config(config, "127.0.0.1:6000").await;
+
+        let keys: Vec<Key> = (0..5u8).map(|i| [i; 32]).collect();
+        for key in &keys {
+            service.put(Request::new(put_request(key, "value", 10))).await.unwrap();
+        }
+
+        let audit_log = std::fs::read_to_string(&audit_log_path).unwrap();
+        std::fs::remove_file(&audit_log_path).unwrap();
+        let lines: Vec<Vec<&str>> = audit_log.lines().map(|line| line.split('\t').collect()).collect();
+        assert_eq!(lines.len(), keys.len());
+        for (line, key) in lines.iter().zip(keys.iter()) {
+            assert_eq!(line[1], "put");
+            assert_eq!(line[2], format!("{:016x}", hash(key)));
+            assert_eq!(line[3], "ok ttl=10");
+        }
+        assert!(lines.windows(2).all(|pair| pair[0][0].parse::<u64>().unwrap() <= pair[1][0].parse::<u64>().unwrap()));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn in_flight_get_completes_during_shutdown() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let (service, node) = new_test_service(&address, &address).await;
+        *node.predecessor_option.lock().unwrap() = Some(address.clone().into());
+        node.kv_store.lock().unwrap().insert([1; 32], ("value".to_string(), u64::MAX));
+
+        let (tx_shutdown, rx_shutdown) = oneshot::channel::<()>();
+        let server_handle = tokio::spawn(async move {
+            Server::builder()
+                .add_service(ChordServer::new(service))
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    let _ = rx_shutdown.await;
+                })
+                .await
+                .unwrap();
+        });
+        let mut client = connect_with_retry(&address).await.unwrap();
+
+        // holding the store lock keeps the get in flight until it is released
+        let (tx_locked, rx_locked) = std::sync::mpsc::channel();
+        let (tx_release, rx_release) = std::sync::mpsc::channel::<()>();
+        let kv_store = node.kv_store.clone();
+        let lock_holder = std::thread::spawn(move || {
+            let _kv_store_guard = kv_store.lock().unwrap();
+            tx_locked.send(()).unwrap();
+            rx_release.recv().unwrap();
+        });
+        rx_locked.recv().unwrap();
+        let get_handle = tokio::spawn(async move {
+            client.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await
+        });
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        tx_shutdown.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(!server_handle.is_finished(), "server must wait for the in-flight get");
+
+        tx_release.send(()).unwrap();
+        lock_holder.join().unwrap();
+        let response = get_handle.await.unwrap().unwrap().into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::Ok));
+        assert_eq!(response.value, "value");
+        tokio::time::timeout(Duration::from_secs(5), server_handle).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn key_density_identifies_the_overloaded_node() {
+        let nodes = spawn_test_ring(3).await;
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        // the keys of nodes 0 and 2 are proportional to their arcs, node 1 holds five times as many
+        let mut next_key = 0u32;
+        for (i, node) in nodes.iter().enumerate() {
+            let arc = positions[i].wrapping_sub(positions[(i + 2) % 3]);
+            let load = if i == 1 { 5.0 } else { 1.0 };
+            let key_count = ((arc as f64 / HashPos::MAX as f64 * 10_000.0 * load) as u32).max(1);
+            let mut kv_store_guard = node.kv_store.lock().unwrap();
+            for _ in 0..key_count {
+                let mut key = [0; 32];
+                key[..4].copy_from_slice(&next_key.to_be_bytes());
+                kv_store_guard.insert(key, ("value".to_string(), u64::MAX));
+                next_key += 1;
+            }
+        }
+
+        let mut stats = Vec::new();
+        for (i, node) in nodes.iter().enumerate() {
+            let mut client = connect_with_retry(&node.address).await.unwrap();
+            let node_stats = client.get_stats(Request::new(Empty {})).await.unwrap().into_inner();
+            assert_eq!(node_stats.owned_arc, positions[i].wrapping_sub(positions[(i + 2) % 3]));
+            stats.push(node_stats);
+        }
+        assert!(stats[1].key_density > 3.0 * stats[0].key_density);
+        assert!(stats[1].key_density > 3.0 * stats[2].key_density);
+    }
+
+    #[tokio::test]
+    async fn put_stores_replicas_on_the_following_successors() {
+        let nodes = spawn_test_ring(4).await;
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        client.put(Request::new(PutRequest { replication: 2, ..put_request(&[1; 32], "replicated", 100) })).await.unwrap();
+
+        let encoded_key = KeyEncoding::Hex.encode(&[1; 32]);
+        for (i, node) in nodes.iter().enumerate() {
+            let mut client = connect_with_retry(&node.address).await.unwrap();
+            let kv_pairs = client.get_kv_store_data(Request::new(Empty {})).await.unwrap().into_inner().kv_pairs;
+            let holds_pair = kv_pairs.iter().any(|pair| pair.key == encoded_key && pair.value == "replicated");
+            assert_eq!(holds_pair, i == 0, "node {} of the ring", i);
+            // replicas are kept apart from the successors' own pairs
+            let holds_replica = node.replica_store.lock().unwrap().replicas_by_primary.get(&nodes[0].address)
+                .is_some_and(|replicas| replicas.contains_key(&[1; 32]));
+            assert_eq!(holds_replica, i == 1 || i == 2, "node {} of the ring", i);
+        }
+        // replicas expire together with the primary copy
+        let (_, primary_expiration_date) = nodes[0].kv_store.lock().unwrap().get(&[1; 32]).cloned().unwrap();
+        let (_, replica_expiration_date) = nodes[2].replica_store.lock().unwrap().replicas_by_primary[&nodes[0].address][&[1; 32]].clone();
+        assert!(replica_expiration_date.abs_diff(primary_expiration_date) < 1_000);
+
+        // a replicated delete removes the replica from the replica store
+        let mut successor_client = connect_with_retry(&nodes[1].address).await.unwrap();
+        successor_client.delete(Request::new(DeleteRequest { key: [1; 32].to_vec(), replication: 0, is_replica: true, primary: nodes[0].address.clone() })).await.unwrap();
+        assert_eq!(nodes[1].replica_store.lock().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn deleted_key_is_not_found() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some(node.address.clone().into());
+        service.put(Request::new(put_request(&[1; 32], "value", 100))).await.unwrap();
+        let delete_request = || Request::new(DeleteRequest { key: [1; 32].to_vec(), replication: 0, is_replica: false, primary: String::default() });
+
+        let response = service.delete(delete_request()).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(DeleteStatus::Ok));
+        let response = service.get(Request::new(GetRequest { key: [1; 32].to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::NotFound));
+        let response = service.delete(delete_request()).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(DeleteStatus::NotFound));
+    }
+
+    #[tokio::test]
+    async fn unresponsive_peer_fails_with_deadline_exceeded() {
+        // accepts connections, but never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            loop {
+                sockets.push(listener.accept().await.unwrap().0);
+            }
+        });
+
+        let start = Instant::now();
+        let result = match connect_with_timeout(&address, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
+            Ok(mut client) => with_rpc_timeout(client.health(request_with_timeout(Empty {}))).await.map(|_| ()),
+            Err(status) => Err(status),
+        };
+        assert_eq!(result.unwrap_err().code(), Code::DeadlineExceeded);
+        assert!(start.elapsed() < Duration::from_millis(2 * RPC_TIMEOUT_MILLIS));
+    }
+
+    #[tokio::test]
+    async fn successor_serves_the_keys_of_a_failed_primary() {
+        let nodes = spawn_test_ring(3).await;
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let key: Key = (0u32..)
+            .map(|i| {
+                let mut key = [0; 32];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), positions[0].wrapping_add(1), positions[1], false, false))
+            .unwrap();
+        nodes[1].kv_store.lock().unwrap().insert(key, ("replicated".to_string(), u64::MAX));
+
+        let mut primary_client = connect_with_retry(&nodes[1].address).await.unwrap();
+        primary_client.push_replicas(Request::new(Empty {})).await.unwrap();
+        // replicas are not served as long as the primary is alive
+        assert!(!nodes[2].kv_store.lock().unwrap().contains_key(&key));
+
+        nodes[1].server_handle.as_ref().unwrap().abort();
+        // the health check of the successor notices the failure, the node in front of the failed
+        // primary becomes the new predecessor with its next stabilize
+        *nodes[2].predecessor_option.lock().unwrap() = None;
+        let mut successor_client = connect_with_retry(&nodes[2].address).await.unwrap();
+        let mut handoff_stream = successor_client.notify(Request::new(NotifyRequest {
+            address: Some(nodes[0].address.clone().into()),
+            pow_token: Some(PowToken::generate(&nodes[0].address, 0, 1).into()),
+        })).await.unwrap().into_inner();
+        while handoff_stream.message().await.unwrap().is_some() {}
+
+        let response = successor_client.get(Request::new(GetRequest { key: key.to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::Ok));
+        assert_eq!(response.value, "replicated");
+    }
+
+    #[tokio::test]
+    async fn leaving_node_hands_over_its_keys_and_links_its_neighbors() {
+        let nodes = spawn_test_ring(3).await;
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let keys: Vec<Key> = (0..30u8).map(|i| [i; 32]).collect();
+        for key in &keys {
+            let responsible_node_index = positions.iter().position(|pos| hash(key) <= *pos).unwrap_or(0);
+            nodes[responsible_node_index].kv_store.lock().unwrap().insert(*key, ("value".to_string(), u64::MAX));
+        }
+        let leaving_keys: Vec<Key> = nodes[1].kv_store.lock().unwrap().keys().cloned().collect();
+
+        nodes[1].service.as_ref().unwrap().leave().await.unwrap();
+        nodes[1].server_handle.as_ref().unwrap().abort();
+
+        let remaining_key_count = nodes[0].kv_store.lock().unwrap().len() + nodes[2].kv_store.lock().unwrap().len();
+        assert_eq!(remaining_key_count, keys.len());
+        assert!(leaving_keys.iter().all(|key| nodes[2].kv_store.lock().unwrap().contains_key(key)));
+
+        assert_eq!(nodes[2].predecessor_option.lock().unwrap().as_ref().unwrap().address, nodes[0].address);
+        assert_eq!(nodes[0].successor_list.lock().unwrap().successors[0], nodes[2].address);
+        for node in [&nodes[0], &nodes[2]] {
+            assert!(!node.successor_list.lock().unwrap().successors.contains(&nodes[1].address));
+            assert!(node.finger_table.lock().unwrap().fingers.iter().all(|finger| finger.address != nodes[1].address));
+        }
+    }
+
+    #[tokio::test]
+    async fn replicas_are_only_accepted_from_a_preceding_primary() {
+        let primary = spawn_test_node(None).await;
+        let receiver = spawn_test_node(None).await;
+        let replicate = |value: &str| tokio_stream::iter(vec![ReplicaBatchMsg {
+            primary: primary.address.clone(),
+            pairs: vec![KvPairMsg { key: vec![1; 32], value: value.to_string(), expiration_date: u64::MAX }],
+        }]);
+        let mut receiver_client = connect_with_retry(&receiver.address).await.unwrap();
+
+        // the receiver is not in the successor list of the primary
+        let status = receiver_client.replicate(Request::new(replicate("value"))).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 0);
+
+        primary.successor_list.lock().unwrap().successors = vec![receiver.address.clone()];
+        let status = receiver_client.replicate(Request::new(replicate(&"x".repeat(MAX_VALUE_BYTES_DEFAULT + 1)))).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 0);
+        receiver_client.replicate(Request::new(replicate("value"))).await.unwrap();
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn replica_puts_and_deletes_are_only_accepted_from_a_preceding_primary() {
+        let primary = spawn_test_node(None).await;
+        let receiver = spawn_test_node(None).await;
+        let replica_put = || Request::new(PutRequest { is_replica: true, primary: primary.address.clone(), ..put_request(&[1; 32], "value", 100) });
+        let replica_delete = || Request::new(DeleteRequest { key: vec![1; 32], replication: 0, is_replica: true, primary: primary.address.clone() });
+        let mut receiver_client = connect_with_retry(&receiver.address).await.unwrap();
+
+        // the receiver is not in the successor list of the primary
+        let status = receiver_client.put(replica_put()).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 0);
+        let status = receiver_client.put_stream(Request::new(tokio_stream::iter(vec![replica_put().into_inner()]))).await.unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 0);
+
+        primary.successor_list.lock().unwrap().successors = vec![receiver.address.clone()];
+        receiver_client.put(replica_put()).await.unwrap();
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 1);
+
+        primary.successor_list.lock().unwrap().successors = vec![];
+        let status = receiver_client.delete(replica_delete()).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 1);
+
+        primary.successor_list.lock().unwrap().successors = vec![receiver.address.clone()];
+        receiver_client.delete(replica_delete()).await.unwrap();
+        assert_eq!(receiver.replica_store.lock().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn handoff_skips_invalid_pairs_without_failing_the_stream() {
+        let node = spawn_test_node(None).await;
+        let pairs = vec![
+            KvPairMsg { key: vec![1; 32], value: "value".to_string(), expiration_date: u64::MAX },
+            KvPairMsg { key: vec![2; 32], value: "x".repeat(MAX_VALUE_BYTES_DEFAULT + 1), expiration_date: u64::MAX },
+            KvPairMsg { key: vec![3; 5], value: "value".to_string(), expiration_date: u64::MAX },
+            KvPairMsg { key: vec![4; 32], value: "value".to_string(), expiration_date: u64::MAX },
+        ];
+        let mut client = connect_with_retry(&node.address).await.unwrap();
+
+        // a failed stream would make the leaving node send the stored pairs to another node as well
+        client.handoff(Request::new(tokio_stream::iter(into_batches(pairs, 2)))).await.unwrap();
+        let kv_store_guard = node.kv_store.lock().unwrap();
+        assert!(!kv_store_guard.contains_key(&[2; 32]));
+        assert!(kv_store_guard.contains_key(&[1; 32]) && kv_store_guard.contains_key(&[4; 32]));
+        assert_eq!(kv_store_guard.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn writes_are_rejected_once_the_node_leaves() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:6000".to_string().into());
+        service.put(Request::new(put_request(&[1; 32], "value", 100))).await.unwrap();
+
+        service.leave().await.unwrap();
+        let status = service.put(Request::new(put_request(&[2; 32], "value", 100))).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        let status = service.delete(Request::new(DeleteRequest { key: vec![1; 32], replication: 0, is_replica: false, primary: String::default() })).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(node.kv_store.lock().unwrap().len(), 1);
+        assert_eq!(node.stats.put_total.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn leave_notice_is_only_accepted_from_a_neighbor() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6001").await;
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:7000".to_string().into());
+        let leave_notice = LeaveNoticeMsg {
+            leaving: Some("127.0.0.1:7001".to_string().into()),
+            predecessor: None,
+            successor: Some("127.0.0.1:7002".to_string().into()),
+        };
+
+        let status = service.leave_notice(Request::new(leave_notice)).await.unwrap_err();
+        assert_eq!(status.code(), Code::PermissionDenied);
+        assert_eq!(node.successor_list.lock().unwrap().successors[0], "127.0.0.1:6001");
+        assert_eq!(node.predecessor_option.lock().unwrap().as_ref().unwrap().address, "127.0.0.1:7000");
+    }
+
+    #[tokio::test]
+    async fn touched_pair_survives_its_original_expiration() {
+        let (service, node) = new_test_service("127.0.0.1:6000", "127.0.0.1:6000").await;
+        *node.predecessor_option.lock().unwrap() = Some("127.0.0.1:6000".to_string().into());
+        let put = |key: Key| PutRequest { ttl_unit: TtlUnit::Milliseconds.into(), ..put_request(&key, "large value", 200) };
+        service.put(Request::new(put([1; 32]))).await.unwrap();
+        service.put(Request::new(put([2; 32]))).await.unwrap();
+
+        let touch = |key: Key| TouchRequest { key: key.to_vec(), ttl: 10, ttl_unit: TtlUnit::Seconds.into(), is_replica: false, primary: String::default() };
+        let response = service.touch(Request::new(touch([1; 32]))).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(TouchStatus::Ok));
+        let response = service.touch(Request::new(touch([3; 32]))).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(TouchStatus::NotFound));
+
+        sleep(Duration::from_millis(300)).await;
+        let response = service.get(Request::new(GetRequest { key: vec![1; 32] })).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::Ok));
+        assert_eq!(response.value, "large value");
+        let response = service.touch(Request::new(touch([2; 32]))).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(TouchStatus::Expired));
+        assert!(!node.kv_store.lock().unwrap().contains_key(&[2; 32]));
+    }
+
+    #[tokio::test]
+    async fn fix_all_fingers_converges_the_whole_finger_table() {
+        let nodes = spawn_test_ring(4).await;
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let responsible_node_index = |key: HashPos| positions.iter().position(|pos| key <= *pos).unwrap_or(0);
+        for node in &nodes {
+            for finger in node.finger_table.lock().unwrap().fingers.iter_mut().skip(1) {
+                finger.address = Address::default();
+            }
+        }
+
+        for node in &nodes {
+            let mut client = connect_with_retry(&node.address).await.unwrap();
+            client.fix_all_fingers(Request::new(Empty {})).await.unwrap();
+        }
+
+        for node in &nodes {
+            for (index, finger) in node.finger_table.lock().unwrap().fingers.iter().enumerate() {
+                assert_eq!(finger.address, nodes[responsible_node_index(finger.key)].address, "finger {} of {}", index, node.address);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn lookup_hops_are_bounded_by_the_finger_count() {
+        let nodes = spawn_test_ring(4).await;
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+
+        // the successor of nodes[0] answers directly, every other node is at least one hop away
+        let own_successor_pos = hash(nodes[1].address.as_bytes());
+        let response = client.find_successor_traced(Request::new(own_successor_pos.into())).await.unwrap().into_inner();
+        assert_eq!(response.hops, 0);
+
+        for i in 0..64u64 {
+            let key = i * (HashPos::MAX / 64);
+            let response = client.find_successor_traced(Request::new(key.into())).await.unwrap().into_inner();
+            let successor: Address = response.successor.unwrap().into();
+            let trace = client.trace_lookup(Request::new(key.into())).await.unwrap().into_inner();
+            assert_eq!(successor, Into::<Address>::into(trace.successor.unwrap()));
+            assert!((response.hops as usize) < DefaultRing::finger_count(), "{} hops for {}", response.hops, key);
+            assert!((response.hops as usize) < nodes.len());
+        }
+    }
+
+    #[tokio::test]
+    async fn put_many_stores_each_pair_on_its_responsible_node() {
+        let nodes = spawn_test_ring(3).await;
+        let keys: Vec<Key> = (0..50u8).map(|i| [i; 32]).collect();
+        let puts: Vec<PutRequest> = keys.iter()
+            .map(|key| put_request(key, &format!("value {}", key[0]), 100))
+            .collect();
+
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        let response = client.put_many(Request::new(PutManyRequest { puts })).await.unwrap().into_inner();
+        assert_eq!(response.stored, keys.len() as u32);
+
+        for key in &keys {
+            let responsible_node: Address = client.find_successor(Request::new(hash(key).into())).await.unwrap().into_inner().into();
+            let mut responsible_node_client = connect_with_retry(&responsible_node).await.unwrap();
+            let response = responsible_node_client.get(Request::new(GetRequest { key: key.to_vec() })).await.unwrap().into_inner();
+            assert_eq!(response.status, i32::from(GetStatus::Ok));
+            assert_eq!(response.value, format!("value {}", key[0]));
+        }
+        let stored_count: usize = nodes.iter().map(|node| node.kv_store.lock().unwrap().len()).sum();
+        assert_eq!(stored_count, keys.len());
+    }
+
+    #[tokio::test]
+    async fn proxy_mode_serves_keys_of_other_nodes() {
+        let nodes = spawn_test_ring_with_config(3, |address| Config { proxy_reads: true, proxy_writes: true, ..test_config(address) }).await;
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let responsible_node_index = |key: &Key| positions.iter().position(|pos| hash(key) <= *pos).unwrap_or(0);
+        let key: Key = (0..=u8::MAX).map(|i| [i; 32]).find(|key| responsible_node_index(key) != 0).unwrap();
+        let responsible_node = &nodes[responsible_node_index(&key)];
+
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        client.put(Request::new(put_request(&key, "proxied", 100))).await.unwrap();
+        assert!(!nodes[0].kv_store.lock().unwrap().contains_key(&key));
+        assert!(responsible_node.kv_store.lock().unwrap().contains_key(&key));
+        assert_eq!(nodes[0].stats.put_total.load(Ordering::Relaxed), 0);
+        assert_eq!(responsible_node.stats.put_total.load(Ordering::Relaxed), 1);
+
+        let response = client.get(Request::new(GetRequest { key: key.to_vec() })).await.unwrap().into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::Ok));
+        assert_eq!(response.value, "proxied");
+    }
+
+    #[tokio::test]
+    async fn proxy_mode_forwards_puts_while_the_predecessor_is_unknown() {
+        let nodes = spawn_test_ring_with_config(3, |address| Config { proxy_writes: true, ..test_config(address) }).await;
+        let positions: Vec<HashPos> = nodes.iter().map(|node| hash(node.address.as_bytes())).collect();
+        let responsible_node_index = |key: &Key| positions.iter().position(|pos| hash(key) <= *pos).unwrap_or(0);
+        let key: Key = (0..=u8::MAX).map(|i| [i; 32]).find(|key| responsible_node_index(key) != 0).unwrap();
+        *nodes[0].predecessor_option.lock().unwrap() = None;
+
+        let mut client = connect_with_retry(&nodes[0].address).await.unwrap();
+        client.put(Request::new(put_request(&key, "proxied", 100))).await.unwrap();
+        assert!(!nodes[0].kv_store.lock().unwrap().contains_key(&key));
+        assert!(nodes[responsible_node_index(&key)].kv_store.lock().unwrap().contains_key(&key));
+    }
+}
@@ -1,17 +1,20 @@
 use std::error::Error;
 use std::io::ErrorKind;
 use std::mem;
+use std::time::Duration;
 
-use log::info;
+use log::{info, warn};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tonic::Request;
+use tokio::time::sleep;
+use tonic::{Request, Status};
 use tonic::transport::Channel;
 
-use crate::threads::chord::chord_proto::{GetRequest, GetStatus, HashPosMsg, PutRequest};
+use crate::threads::chord::chord_proto::{DeleteRequest, DeleteStatus, GetRequest, GetStatus, HashPosMsg, PutRequest, TtlUnit};
 use crate::threads::chord::chord_proto::chord_client::ChordClient;
 use crate::threads::chord::connect_with_retry;
-use crate::utils::constants::{DHT_FAILURE, DHT_GET, DHT_PUT, DHT_SUCCESS};
+use crate::utils::config::ClientCommand;
+use crate::utils::constants::{DHT_FAILURE, DHT_GET, DHT_PUT, DHT_SUCCESS, LOOK_UP_MAX_RETRIES, LOOK_UP_RETRY_SLEEP_MILLIS};
 use crate::utils::crypto;
 use crate::utils::types::HashPos;
 
@@ -24,14 +27,14 @@ pub async fn handle_client_connection(mut socket: TcpStream, grpc_address: &Stri
                 info!("Client disconnected");
                 return Ok(());
             }
-            _ => panic!("Unexpected Error")
+            Err(err) => return Err(err.into())
         };
-        let code = socket.read_u16().await.unwrap();
+        let code = socket.read_u16().await?;
         match code {
-            code if code == DHT_PUT => handle_put(&grpc_address, &mut socket, size).await,
-            code if code == DHT_GET => handle_get(&grpc_address, &mut socket).await,
-            _ => panic!("invalid code {}", code)
-        }.unwrap();
+            code if code == DHT_PUT => handle_put(grpc_address, &mut socket, size).await?,
+            code if code == DHT_GET => handle_get(grpc_address, &mut socket).await?,
+            _ => return Err(format!("Invalid message code {}", code).into())
+        }
     }
     Ok(())
 }
@@ -44,81 +47,157 @@ async fn handle_get(grpc_address: &String, socket: &mut TcpStream) -> Result<(),
     let mut responsible_node_client = perform_chord_look_up(
         &crypto::hash(key_array.as_slice()),
         grpc_address.as_str(),
-    ).await;
+    ).await?;
 
-    let response = responsible_node_client.get(Request::new(GetRequest {
+    let get_result = responsible_node_client.get(Request::new(GetRequest {
         key: key_array.to_vec(),
-    })).await.unwrap();
+    })).await;
 
-    match GetStatus::from_i32(response.get_ref().status) {
-        Some(GetStatus::Ok) => {
-            send_dht_success(socket, key_array, response.get_ref().value.as_bytes().to_vec()).await?;
+    match get_result.map(|response| (GetStatus::from_i32(response.get_ref().status), response.into_inner().value)) {
+        Ok((Some(GetStatus::Ok), value)) => {
+            send_dht_success(socket, key_array, value.into_bytes()).await?;
+        }
+        Ok((Some(GetStatus::NotFound), _)) | Ok((Some(GetStatus::Expired), _)) => {
+            send_dht_failure(socket, key_array).await?;
         }
-        Some(GetStatus::NotFound) => {
+        Ok((Some(GetStatus::None), _)) | Ok((None, _)) => {
+            warn!("GET for key {:?} received an invalid response status", key_array);
             send_dht_failure(socket, key_array).await?;
         }
-        Some(GetStatus::Expired) => {
+        Err(status) => {
+            warn!("GET for key {:?} failed: {}", key_array, status.message());
             send_dht_failure(socket, key_array).await?;
         }
-        _ => panic!("Received invalid get response status")
     }
 
     Ok(())
 }
 
 async fn handle_put(grpc_address: &String, socket: &mut TcpStream, size: u16) -> Result<(), Box<dyn Error>> {
-    let mut ttl = socket.read_u16().await.unwrap();
+    let mut ttl = socket.read_u16().await?;
     if ttl == 0 {
         ttl = u16::MAX;
     }
-    let replication = socket.read_u8().await.unwrap();
-    let _reserved = socket.read_u8().await.unwrap();
+    let replication = socket.read_u8().await?;
+    let _reserved = socket.read_u8().await?;
 
     let mut key_array: [u8; 32] = [0; 32];
     socket.read_exact(&mut key_array).await?;
     let hash_ring_pos: HashPos = crypto::hash(key_array.as_slice());
 
-    let remaining_msg_len: usize = size as usize
-        - mem::size_of_val(&size)
-        - mem::size_of_val(&DHT_PUT)
-        - mem::size_of_val(&ttl)
-        - mem::size_of_val(&replication)
-        - mem::size_of_val(&_reserved)
-        - mem::size_of_val(&key_array);
+    let header_len = mem::size_of_val(&size)
+        + mem::size_of_val(&DHT_PUT)
+        + mem::size_of_val(&ttl)
+        + mem::size_of_val(&replication)
+        + mem::size_of_val(&_reserved)
+        + mem::size_of_val(&key_array);
+    let remaining_msg_len: usize = (size as usize).checked_sub(header_len)
+        .ok_or(format!("PUT message size {} is smaller than its header", size))?;
 
-    let mut value_string = String::new();
+    let mut value_bytes = vec![0; remaining_msg_len];
+    socket.read_exact(&mut value_bytes).await?;
+    let value_string = String::from_utf8(value_bytes)?;
+    info!("Processing PUT for key {}...", hash_ring_pos);
 
-    if socket.read_to_string(&mut value_string).await.unwrap() == remaining_msg_len {
-        info!("Processing PUT for key {}...", hash_ring_pos);
+    let mut responsible_node_client = perform_chord_look_up(&hash_ring_pos, grpc_address.as_str())
+        .await?;
 
-        let mut responsible_node_client = perform_chord_look_up(&hash_ring_pos, grpc_address.as_str())
-            .await;
+    let put_result = responsible_node_client.put(Request::new(PutRequest {
+        key: key_array.to_vec(),
+        ttl: ttl as u64,
+        replication: replication as u32,
+        value: value_string,
+        refresh_ttl_only_if_unchanged: false,
+        ttl_unit: TtlUnit::Seconds.into(),
+        is_replica: false,
+        primary: String::default(),
+    })).await;
 
-        let _ = responsible_node_client.put(Request::new(PutRequest {
-            key: key_array.to_vec(),
-            ttl: ttl as u64,
-            replication: replication as u32,
-            value: value_string,
-        })).await.unwrap();
+    // a rejected put, e.g. of an oversized value, is reported to the client instead of
+    // taking down the connection
+    if let Err(status) = put_result {
+        warn!("PUT for key {} failed: {}", hash_ring_pos, status.message());
+        send_dht_failure(socket, key_array).await?;
+    }
+    Ok(())
+}
 
-        Ok(())
-    } else {
-        panic!("Error reading string value")
+/// Runs a get, put or delete of the client mode against the given node and returns the line to
+/// print, starting with the status of the operation.
+pub async fn run_client_command(command: ClientCommand) -> Result<String, Status> {
+    match command {
+        ClientCommand::Get { node, key } => {
+            let key_array = crypto::hash_to_key(key.as_bytes());
+            let mut responsible_node_client = perform_chord_look_up(&crypto::hash(key_array.as_slice()), &node).await?;
+            let response = responsible_node_client.get(Request::new(GetRequest {
+                key: key_array.to_vec(),
+            })).await?.into_inner();
+            match GetStatus::from_i32(response.status) {
+                Some(GetStatus::Ok) => Ok(format!("OK {}", response.value)),
+                Some(GetStatus::NotFound) => Ok("NOT_FOUND".to_string()),
+                Some(GetStatus::Expired) => Ok("EXPIRED".to_string()),
+                _ => Err(Status::internal("Received invalid get response status"))
+            }
+        }
+        ClientCommand::Put { node, key, value, ttl, replication } => {
+            let key_array = crypto::hash_to_key(key.as_bytes());
+            let mut responsible_node_client = perform_chord_look_up(&crypto::hash(key_array.as_slice()), &node).await?;
+            let response = responsible_node_client.put(Request::new(PutRequest {
+                key: key_array.to_vec(),
+                ttl,
+                replication,
+                value,
+                refresh_ttl_only_if_unchanged: false,
+                ttl_unit: TtlUnit::Seconds.into(),
+                is_replica: false,
+                primary: String::default(),
+            })).await?.into_inner();
+            Ok(format!("OK ttl {}", response.effective_ttl))
+        }
+        ClientCommand::Delete { node, key, replication } => {
+            let key_array = crypto::hash_to_key(key.as_bytes());
+            let mut responsible_node_client = perform_chord_look_up(&crypto::hash(key_array.as_slice()), &node).await?;
+            let response = responsible_node_client.delete(Request::new(DeleteRequest {
+                key: key_array.to_vec(),
+                replication,
+                is_replica: false,
+                primary: String::default(),
+            })).await?.into_inner();
+            match DeleteStatus::from_i32(response.status) {
+                Some(DeleteStatus::Ok) => Ok("OK".to_string()),
+                Some(DeleteStatus::NotFound) => Ok("NOT_FOUND".to_string()),
+                _ => Err(Status::internal("Received invalid delete response status"))
+            }
+        }
     }
 }
 
-pub async fn perform_chord_look_up(key: &HashPos, grpc_address: &str) -> ChordClient<Channel> {
-    let mut local_node_client: ChordClient<Channel> = connect_with_retry(&grpc_address.to_string())
-        .await
-        .unwrap();
+/// Looks up the node responsible for the key via the local node and connects to it. Lookups can
+/// fail transiently while the ring stabilizes, so failed attempts are retried a few times.
+pub async fn perform_chord_look_up(key: &HashPos, grpc_address: &str) -> Result<ChordClient<Channel>, Status> {
+    let mut retries = 0;
+    loop {
+        match try_chord_look_up(key, grpc_address).await {
+            Ok(responsible_node_client) => return Ok(responsible_node_client),
+            Err(status) if retries < LOOK_UP_MAX_RETRIES => {
+                retries += 1;
+                warn!("Look up for key {} failed: {}. Retrying...", key, status);
+                sleep(Duration::from_millis(LOOK_UP_RETRY_SLEEP_MILLIS)).await;
+            }
+            Err(status) => return Err(status)
+        }
+    }
+}
+
+async fn try_chord_look_up(key: &HashPos, grpc_address: &str) -> Result<ChordClient<Channel>, Status> {
+    let mut local_node_client: ChordClient<Channel> = connect_with_retry(&grpc_address.to_string()).await?;
 
-    // todo: retry find_sucessor if error
     let response = local_node_client.find_successor(Request::new(HashPosMsg {
         key: key.to_be_bytes().to_vec()
-    })).await.unwrap();
+    })).await?;
 
     let responsible_node_address = &response.get_ref().address;
-    connect_with_retry(responsible_node_address).await.unwrap()
+    connect_with_retry(responsible_node_address).await
 }
 
 async fn send_dht_success(socket: &mut TcpStream, key: [u8; 32], value: Vec<u8>) -> Result<(), Box<dyn Error>> {
@@ -145,3 +224,33 @@ async fn send_dht_failure(socket: &mut TcpStream, key: [u8; 32]) -> Result<(), B
     socket.write_all(&buffer).await?;
     Ok(())
 }
+
+
+#[cfg(test)]
+mod tests {
+    use crate::threads::chord::tests::spawn_test_ring;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn client_round_trips_a_value_through_a_node() {
+        let ring = spawn_test_ring(1).await;
+        let node = &ring[0];
+        let get = || ClientCommand::Get { node: node.address.clone(), key: "hello".to_string() };
+
+        assert_eq!(run_client_command(get()).await.unwrap(), "NOT_FOUND");
+        let put = ClientCommand::Put {
+            node: node.address.clone(),
+            key: "hello".to_string(),
+            value: "world".to_string(),
+            ttl: 60,
+            replication: 0,
+        };
+        assert_eq!(run_client_command(put).await.unwrap(), "OK ttl 60");
+        assert_eq!(run_client_command(get()).await.unwrap(), "OK world");
+
+        let delete = ClientCommand::Delete { node: node.address.clone(), key: "hello".to_string(), replication: 0 };
+        assert_eq!(run_client_command(delete).await.unwrap(), "OK");
+        assert_eq!(run_client_command(get()).await.unwrap(), "NOT_FOUND");
+    }
+}
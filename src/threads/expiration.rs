@@ -0,0 +1,95 @@
+use std::ops::Bound::{Excluded, Unbounded};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use log::{debug, info};
+use tokio::task::yield_now;
+use tokio::time::sleep;
+
+use chord::utils::constants::EXPIRATION_SWEEP_BATCH_SIZE;
+use chord::utils::sync::LockOrRecover;
+use chord::utils::time::has_expired;
+use chord::utils::types::{Key, KvStore};
+
+use crate::node::stats::NodeStats;
+
+/// Expired pairs are otherwise only removed when a get touches them, pairs nobody reads again
+/// would stay in the store forever. With serve_stale the expired pairs are only counted.
+pub async fn sweep_expired_periodically(kv_store_arc: Arc<Mutex<KvStore>>, sweep_interval: Duration, remove_expired: bool, stats: Arc<NodeStats>) -> ! {
+    info!("Starting up periodic expiration sweep thread");
+    loop {
+        sleep(sweep_interval).await;
+        let expired = sweep_expired(&kv_store_arc, EXPIRATION_SWEEP_BATCH_SIZE, remove_expired, &stats).await;
+        if expired > 0 {
+            info!("Expiration sweep found {} expired pairs, removed: {}", expired, remove_expired);
+        } else {
+            debug!("Expiration sweep found no expired pairs");
+        }
+    }
+}
+
+/// Counts the expired pairs, removes them if remove_expired is set, and records their number in
+/// stats, so get_stats doesn't scan the store itself. The store is walked in key order, batch_size
+/// pairs at a time with the lock released in between, so rpcs waiting for the store wait for one
+/// batch at most. Pairs inserted behind the cursor during the sweep are left to the next sweep.
+pub(crate) async fn sweep_expired(kv_store_arc: &Arc<Mutex<KvStore>>, batch_size: usize, remove_expired: bool, stats: &NodeStats) -> usize {
+    let mut cursor_option: Option<Key> = None;
+    let mut expired = 0;
+    loop {
+        {
+            let mut kv_store_guard = kv_store_arc.lock_or_recover();
+            let lower = cursor_option.map_or(Unbounded, Excluded);
+            let batch: Vec<(Key, bool)> = kv_store_guard.range((lower, Unbounded))
+                .take(batch_size.max(1))
+                .map(|(key, (_, expiration_date))| (*key, has_expired(expiration_date)))
+                .collect();
+            match batch.last() {
+                Some((last_key, _)) => cursor_option = Some(*last_key),
+                None => break
+            }
+            for (key, _) in batch.into_iter().filter(|(_, is_expired)| *is_expired) {
+                if remove_expired {
+                    kv_store_guard.remove(&key);
+                }
+                expired += 1;
+            }
+        }
+        yield_now().await;
+    }
+    stats.expired_kv_store_size.store(expired as u64, Ordering::Relaxed);
+    expired
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chord::utils::time::now_millis;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn sweep_removes_pairs_once_they_expired() {
+        let kv_store_arc = Arc::new(Mutex::new(KvStore::new()));
+        for i in 0..5u8 {
+            kv_store_arc.lock().unwrap().insert([i; 32], ("short lived".to_string(), now_millis() + 50));
+        }
+
+        assert_eq!(sweep_expired(&kv_store_arc, 2, true, &NodeStats::default()).await, 0);
+        assert_eq!(kv_store_arc.lock().unwrap().len(), 5);
+
+        sleep(Duration::from_millis(100)).await;
+        assert_eq!(sweep_expired(&kv_store_arc, 2, true, &NodeStats::default()).await, 5);
+        assert_eq!(kv_store_arc.lock().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn sweep_keeps_pairs_that_did_not_expire() {
+        let kv_store_arc = Arc::new(Mutex::new(KvStore::new()));
+        kv_store_arc.lock().unwrap().insert([1; 32], ("expired".to_string(), 0));
+        kv_store_arc.lock().unwrap().insert([2; 32], ("alive".to_string(), u64::MAX));
+
+        assert_eq!(sweep_expired(&kv_store_arc, 1, true, &NodeStats::default()).await, 1);
+        assert!(kv_store_arc.lock().unwrap().contains_key(&[2; 32]));
+    }
+}
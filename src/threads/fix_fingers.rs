@@ -6,9 +6,8 @@ use tonic::Request;
 
 use crate::threads::chord::chord_proto::Empty;
 use crate::threads::chord::connect_with_retry;
-use crate::utils::constants::FIX_FINGERS_SLEEP_MILLIS;
 
-pub async fn fix_fingers_periodically(local_grpc_service_address: String) -> ! {
+pub async fn fix_fingers_periodically(local_grpc_service_address: String, fix_fingers_interval: Duration) -> ! {
     info!("Starting up periodic fix_fingers thread");
     let mut client = connect_with_retry(&local_grpc_service_address).await.unwrap();
     debug!("Successfully connected to local grpc service");
@@ -16,6 +15,6 @@ pub async fn fix_fingers_periodically(local_grpc_service_address: String) -> ! {
         client.fix_fingers(Request::new(Empty {}))
             .await
             .unwrap();
-        sleep(Duration::from_millis(FIX_FINGERS_SLEEP_MILLIS)).await;
+        sleep(fix_fingers_interval).await;
     }
 }
@@ -1,44 +1,96 @@
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use tokio::sync::oneshot::Receiver;
 use tokio::time::sleep;
 use tonic::Request;
 
-use chord::utils::constants::HEALTH_SLEEP_MILLIS;
+use chord::utils::constants::RPC_TIMEOUT_MILLIS;
+use chord::utils::sync::LockOrRecover;
+use chord::utils::types::Address;
 
 use crate::node::finger_entry::FingerEntry;
 use crate::threads::chord::chord_proto::Empty;
-use crate::threads::chord::{connect, connect_with_retry};
+use crate::threads::chord::{connect_with_retry, connect_with_timeout, request_with_timeout, with_rpc_timeout};
 
-pub async fn check_predecessor_health_periodically(local_grpc_service_address: String, rx: Receiver<Arc<Mutex<Option<FingerEntry>>>>) -> ! {
+pub async fn check_predecessor_health_periodically(local_grpc_service_address: String, rx: Receiver<Arc<Mutex<Option<FingerEntry>>>>,
+                                                   failure_threshold: u32, health_interval: Duration) -> ! {
     let predecessor_arc = rx.await.unwrap();
     info!("Starting up periodic predecessor health check thread");
     let mut local_grpc_client = connect_with_retry(&local_grpc_service_address.clone()).await.unwrap();
     debug!("Connected to local grpc service");
+    let mut consecutive_failures = 0;
+    let mut checked_address_option: Option<Address> = None;
     loop {
         let predecessor_address_msg_optional = local_grpc_client.get_predecessor(Request::new(Empty {}))
             .await
             .unwrap().into_inner().address_optional;
 
         if let Some(predecessor_address_msg) = predecessor_address_msg_optional {
-            match connect(&predecessor_address_msg.address).await {
-                Ok(mut predecessor_client) => {
-                    match predecessor_client.health(Request::new(Empty {})).await {
-                        Ok(_) => debug!("predecessor node healthy"),
-                        Err(_) => unset_predecessor(predecessor_arc.clone()).await
-                    }
-                }
-                Err(_) => unset_predecessor(predecessor_arc.clone()).await
+            // failures of a previous predecessor don't count against a new one
+            if checked_address_option.as_ref() != Some(&predecessor_address_msg.address) {
+                consecutive_failures = 0;
+                checked_address_option = Some(predecessor_address_msg.address.clone());
             }
+            consecutive_failures = check_predecessor_health(&predecessor_arc, &predecessor_address_msg.address,
+                                                            consecutive_failures, failure_threshold).await;
         }
 
-        sleep(Duration::from_millis(HEALTH_SLEEP_MILLIS)).await;
+        sleep(health_interval).await;
     }
 }
 
-async fn unset_predecessor(predecessor_arc: Arc<Mutex<Option<FingerEntry>>>) -> () {
-    debug!("Predecessor unavailable, setting predecessor to Nil");
-    *predecessor_arc.lock().unwrap() = None;
+/// Checks the predecessor once and returns the updated number of consecutive failures. After
+/// failure_threshold consecutive failures the predecessor is unset, so that stabilize and notify
+/// can repair the ring.
+async fn check_predecessor_health(predecessor_arc: &Arc<Mutex<Option<FingerEntry>>>, predecessor_address: &Address,
+                                  consecutive_failures: u32, failure_threshold: u32) -> u32 {
+    let healthy = match connect_with_timeout(predecessor_address, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
+        Ok(mut predecessor_client) => with_rpc_timeout(predecessor_client.health(request_with_timeout(Empty {}))).await.is_ok(),
+        Err(_) => false
+    };
+    if healthy {
+        debug!("predecessor node healthy");
+        return 0;
+    }
+    let consecutive_failures = consecutive_failures + 1;
+    if consecutive_failures < failure_threshold {
+        warn!("Predecessor {} failed {} of {} health checks", predecessor_address, consecutive_failures, failure_threshold);
+        return consecutive_failures;
+    }
+    unset_predecessor(predecessor_arc, predecessor_address);
+    0
+}
+
+/// unsets the predecessor unless it was replaced in the meantime
+fn unset_predecessor(predecessor_arc: &Arc<Mutex<Option<FingerEntry>>>, predecessor_address: &Address) {
+    let mut predecessor_guard = predecessor_arc.lock_or_recover();
+    if predecessor_guard.as_ref().map(|predecessor| &predecessor.address) == Some(predecessor_address) {
+        debug!("Predecessor unavailable, setting predecessor to Nil");
+        *predecessor_guard = None;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn unreachable_predecessor_is_cleared_after_the_failure_threshold() {
+        // nothing listens on this address anymore
+        let unreachable_address = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().to_string();
+        let predecessor_arc: Arc<Mutex<Option<FingerEntry>>> = Arc::new(Mutex::new(Some(unreachable_address.clone().into())));
+
+        let consecutive_failures = check_predecessor_health(&predecessor_arc, &unreachable_address, 0, 2).await;
+        assert_eq!(consecutive_failures, 1);
+        assert!(predecessor_arc.lock().unwrap().is_some());
+
+        let consecutive_failures = check_predecessor_health(&predecessor_arc, &unreachable_address, consecutive_failures, 2).await;
+        assert_eq!(consecutive_failures, 0);
+        assert!(predecessor_arc.lock().unwrap().is_none());
+    }
 }
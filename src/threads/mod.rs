@@ -4,6 +4,10 @@ pub mod client_api;
 pub mod shutdown_handoff;
 pub mod fix_fingers;
 pub mod stabilize;
+pub mod announce;
+pub mod expiration;
 pub mod health;
+pub mod partition;
+pub mod replication;
 pub mod successor_list;
 pub mod web;
@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info};
+use tokio::time::sleep;
+
+use chord::utils::constants::RPC_TIMEOUT_MILLIS;
+use chord::utils::types::Address;
+
+use crate::threads::chord::{ChordService, connect_with_timeout};
+
+/// returns the first seed node other than this node that accepts a connection, it serves as join
+/// peer if no join address is configured. If none is reachable, the node starts a ring of its own
+/// and rejoins once the partition detection reaches a seed node
+pub async fn first_reachable_seed_node(seed_nodes: &[Address], own_address: &Address) -> Option<Address> {
+    for seed_address in seed_nodes.iter().filter(|seed_address| *seed_address != own_address) {
+        match connect_with_timeout(seed_address, Duration::from_millis(RPC_TIMEOUT_MILLIS)).await {
+            Ok(_) => return Some(seed_address.clone()),
+            Err(status) => info!("Seed node {} unreachable: {}", seed_address, status)
+        }
+    }
+    None
+}
+
+/// makes the local node check every interval whether it got separated from the ring of its seed
+/// nodes, in which case it rejoins that ring
+pub async fn detect_foreign_ring_periodically(chord_service: Arc<ChordService>, partition_check_interval: Duration) -> ! {
+    info!("Starting up periodic partition detection thread");
+    loop {
+        sleep(partition_check_interval).await;
+        match chord_service.detect_foreign_ring().await {
+            Ok(true) => info!("Rejoined the ring of the seed nodes"),
+            Ok(false) => {}
+            Err(error) => error!("An error occured during partition detection: {}", error)
+        }
+    }
+}
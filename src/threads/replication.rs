@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+use log::{debug, error, info};
+use tokio::time::sleep;
+use tonic::Request;
+
+use crate::threads::chord::chord_proto::Empty;
+use crate::threads::chord::connect_with_retry;
+
+/// makes the local node push a copy of the pairs it owns to its successors every interval, so
+/// they survive if the node fails before handing them over
+pub async fn replicate_periodically(local_grpc_service_address: String, replication_interval: Duration) -> ! {
+    info!("Starting up periodic replication thread");
+    let mut client = connect_with_retry(&local_grpc_service_address).await.unwrap();
+    debug!("Successfully connected to local grpc service");
+    loop {
+        if let Err(error) = client.push_replicas(Request::new(Empty {})).await {
+            error!("An error occured during replication: {}", error)
+        }
+        sleep(replication_interval).await;
+    }
+}
@@ -1,27 +1,40 @@
 use std::error::Error;
+use std::io::ErrorKind;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
-use log::info;
+use log::{info, warn};
 use tokio::sync::oneshot::Sender;
 use tonic::Request;
 
+use crate::node::conversions::{try_into_hash_pos, try_into_key};
 use crate::node::finger_entry::FingerEntry;
 use crate::node::finger_table::FingerTable;
+use crate::node::kv_dump::{dump_kv_store_on_panic, read_kv_dump, snapshot_periodically, SNAPSHOT_FILE_NAME};
+use crate::node::routing_snapshot::{read_routing_snapshot, ROUTING_SNAPSHOT_FILE_NAME, RoutingSnapshot, snapshot_routing_periodically};
 use crate::node::successor_list::SuccessorList;
-use crate::threads::chord::chord_proto::{Empty, HashPosMsg};
-use crate::threads::chord::connect_with_retry;
-use crate::utils::crypto::hash;
-use crate::utils::types::{Address, KvStore};
+use crate::threads::chord::chord_proto::{Empty, HandoffAckMsg, HashPosListMsg, HashPosMsg};
+use crate::threads::chord::{connect_with_retry, join_with_pow};
+use crate::utils::crypto::{hash, hash_salt_fingerprint, is_between};
+use crate::utils::sync::LockOrRecover;
+use crate::utils::types::{Address, Key, KvStore};
 
 /// Setup function that distinguishes betwenn two scenarios:
 /// 1. The node joins an existing cluster (join_address_option is None)
 /// 2. The node starts up a new cluster (join_address_option is given)
 pub async fn setup(join_address_option: Option<Address>, own_grpc_address_str: &String,
                    tx_grpc_thread: Sender<(Arc<Mutex<FingerTable>>, Arc<Mutex<Option<FingerEntry>>>, Arc<Mutex<KvStore>>, Arc<Mutex<SuccessorList>>)>,
-                   tx_handoff_thread: Sender<Arc<Mutex<KvStore>>>,
                    tx_check_predecessor: Sender<Arc<Mutex<Option<FingerEntry>>>>,
                    tx_successor_list: Sender<Arc<Mutex<SuccessorList>>>,
                    tx_web_interface: Sender<Arc<Mutex<FingerTable>>>,
+                   pow_difficulty: usize,
+                   pow_threads: usize,
+                   seed_all_fingers: bool,
+                   dump_on_panic_path_option: Option<String>,
+                   data_dir_option: Option<String>,
+                   snapshot_interval: Duration,
+                   successor_list_length: usize,
 ) -> Result<(), Box<dyn Error>> {
     info!("Starting up setup thread");
     let own_id = hash(own_grpc_address_str.as_bytes());
@@ -30,38 +43,345 @@ pub async fn setup(join_address_option: Option<Address>, own_grpc_address_str: &
     let kv_store_arc = Arc::new(Mutex::new(KvStore::new()));
     let predecessor_option_arc = Arc::new(Mutex::new(None));
     let mut successor_list_arc = Arc::new(Mutex::new(SuccessorList::default()));
+    if let Some(dump_on_panic_path) = dump_on_panic_path_option {
+        dump_kv_store_on_panic(kv_store_arc.clone(), dump_on_panic_path);
+    }
+    // the snapshot of the last run is loaded first, pairs handed over on join are more recent
+    let snapshot_path_option = data_dir_option.as_ref()
+        .map(|data_dir| Path::new(data_dir).join(SNAPSHOT_FILE_NAME).to_string_lossy().to_string());
+    if let Some(snapshot_path) = &snapshot_path_option {
+        match read_kv_dump(snapshot_path) {
+            Ok(kv_store) => {
+                info!("Loaded {} pairs from snapshot {}", kv_store.len(), snapshot_path);
+                *kv_store_arc.lock_or_recover() = kv_store;
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => info!("No snapshot found at {}", snapshot_path),
+            Err(err) => return Err(format!("Unable to load snapshot {}: {}", snapshot_path, err).into())
+        }
+    }
+
+    let routing_snapshot_path_option = data_dir_option
+        .map(|data_dir| Path::new(&data_dir).join(ROUTING_SNAPSHOT_FILE_NAME).to_string_lossy().to_string());
+    let routing_snapshot_option = routing_snapshot_path_option.as_ref()
+        .and_then(|routing_snapshot_path| load_routing_snapshot(routing_snapshot_path, own_grpc_address_str, &finger_table_arc.lock_or_recover()));
 
     match join_address_option {
         Some(peer_address_str) => {
             info!("Joining existing cluster");
-            let mut join_peer_client = connect_with_retry(&peer_address_str)
-                .await
-                .unwrap();
+            let mut join_peer_client = connect_with_retry(&peer_address_str).await?;
+            let cluster_info = join_peer_client.get_cluster_info(Request::new(Empty {}))
+                .await?
+                .into_inner();
+            if cluster_info.salt_fingerprint != hash_salt_fingerprint() {
+                return Err("Cluster salt differs from the salt of the join peer".into());
+            }
             let successor_address: Address = join_peer_client.find_successor(Request::new(HashPosMsg {
                 key: own_id.to_be_bytes().to_vec(),
-            })).await.unwrap().into_inner().into();
+            })).await?.into_inner().into();
 
-            let mut successor_client = connect_with_retry(&successor_address)
-                .await
-                .unwrap();
+            let mut successor_client = connect_with_retry(&successor_address).await?;
             let _: SuccessorList = successor_client.get_successor_list(Request::new(Empty {}))
-                .await
-                .unwrap().into_inner().into();
+                .await?
+                .into_inner()
+                .into();
 
-            successor_list_arc = Arc::new(Mutex::new(SuccessorList::new(own_grpc_address_str, &successor_address)));
-            finger_table_arc.lock().unwrap().fingers[0].address = successor_address;
+            // joining explicitly makes us the successor's predecessor right away and hands over
+            // our range synchronously, so reads work before the first stabilize cycle
+            let join_response = join_with_pow(&mut successor_client, own_grpc_address_str, pow_difficulty, pow_threads).await?;
+            if let Some(predecessor_address_msg) = join_response.predecessor {
+                let predecessor_address: Address = predecessor_address_msg.into();
+                info!("Taking over predecessor {} from successor {}", predecessor_address, successor_address);
+                *predecessor_option_arc.lock_or_recover() = Some(predecessor_address.into());
+            }
+            let mut received_keys = Vec::new();
+            {
+                let mut kv_store_guard = kv_store_arc.lock_or_recover();
+                // pairs of the snapshot outside of the range we own now belong to other nodes,
+                // which hold more recent versions of them
+                if let (Some(lower_msg), Some(upper_msg)) = (&join_response.lower, &join_response.upper) {
+                    let (lower, upper) = (try_into_hash_pos(lower_msg)?, try_into_hash_pos(upper_msg)?);
+                    let loaded_pair_count = kv_store_guard.len();
+                    kv_store_guard.retain(|key, _| is_between(hash(key), lower, upper, false, false));
+                    info!("Dropped {} snapshot pairs outside of the owned range", loaded_pair_count - kv_store_guard.len());
+                }
+                for pair in join_response.pairs {
+                    let key: Key = try_into_key(pair.key)?;
+                    kv_store_guard.insert(key, (pair.value, pair.expiration_date));
+                    received_keys.push(key.to_vec());
+                }
+                info!("Received {} pairs from successor {}", received_keys.len(), successor_address);
+            }
+            // the successor keeps the handed over pairs until they are stored here
+            let handoff_ack = HandoffAckMsg { keys: received_keys, address: Some(own_grpc_address_str.clone().into()), rejected_keys: vec![] };
+            if let Err(status) = successor_client.ack_handoff(Request::new(handoff_ack)).await {
+                warn!("Unable to acknowledge the join handoff to {}: {}", successor_address, status);
+            }
+
+            // resolving all fingers with one batched call instead of one find_successor round trip
+            // per finger, positions between our predecessor and us belong to us. Without seeding,
+            // only the first finger is set and fix_fingers initializes the others over time
+            if seed_all_fingers {
+                let finger_keys: Vec<HashPosMsg> = finger_table_arc.lock_or_recover().fingers.iter()
+                    .map(|finger| finger.key.into())
+                    .collect();
+                let finger_addresses = join_peer_client.find_successors(Request::new(HashPosListMsg { keys: finger_keys }))
+                    .await?
+                    .into_inner()
+                    .addresses;
+                let predecessor_pos_option = predecessor_option_arc.lock_or_recover().clone()
+                    .map(|predecessor: FingerEntry| hash(predecessor.address.as_bytes()));
+                for (finger, address_msg) in finger_table_arc.lock_or_recover().fingers.iter_mut().zip(finger_addresses) {
+                    finger.address = match predecessor_pos_option {
+                        Some(predecessor_pos) if is_between(finger.key, predecessor_pos.wrapping_add(1), own_id, false, false) => own_grpc_address_str.clone(),
+                        _ => address_msg.into()
+                    };
+                }
+            }
+
+            let mut successor_list = SuccessorList::new(own_grpc_address_str, &successor_address, successor_list_length);
+            // warm start: fingers the join left uninitialized point to where they pointed in the
+            // last run, and the successors of the last run follow the new successor
+            if let Some(routing_snapshot) = routing_snapshot_option {
+                for (finger, snapshot_finger) in finger_table_arc.lock_or_recover().fingers.iter_mut().zip(routing_snapshot.finger_table.fingers) {
+                    if finger.is_uninitialized() {
+                        finger.address = snapshot_finger.address;
+                    }
+                }
+                successor_list.update_with_other_succ_list(SuccessorList {
+                    own_address: successor_address.clone(),
+                    ..routing_snapshot.successor_list
+                });
+            }
+
+            successor_list_arc = Arc::new(Mutex::new(successor_list));
+            finger_table_arc.lock_or_recover().fingers[0].address = successor_address;
         }
         None => {
+            // the routing of a previous ring is not reused, the node would rejoin that ring
             info!("Starting up a new cluster");
-            successor_list_arc = Arc::new(Mutex::new(SuccessorList::new(own_grpc_address_str, own_grpc_address_str)));
-            finger_table_arc.lock().unwrap().fingers[0].address = own_grpc_address_str.clone();
+            successor_list_arc = Arc::new(Mutex::new(SuccessorList::new(own_grpc_address_str, own_grpc_address_str, successor_list_length)));
+            finger_table_arc.lock_or_recover().fingers[0].address = own_grpc_address_str.clone();
         }
     };
 
-    tx_grpc_thread.send((finger_table_arc.clone(), predecessor_option_arc.clone(), kv_store_arc.clone(), successor_list_arc.clone())).unwrap();
+    if let Some(snapshot_path) = snapshot_path_option {
+        tokio::spawn(snapshot_periodically(kv_store_arc.clone(), snapshot_path, snapshot_interval));
+    }
+    if let Some(routing_snapshot_path) = routing_snapshot_path_option {
+        tokio::spawn(snapshot_routing_periodically(finger_table_arc.clone(), successor_list_arc.clone(), routing_snapshot_path, snapshot_interval));
+    }
+
+    tx_grpc_thread.send((finger_table_arc.clone(), predecessor_option_arc.clone(), kv_store_arc, successor_list_arc.clone())).unwrap();
     tx_web_interface.send(finger_table_arc.clone()).unwrap();
-    tx_handoff_thread.send(kv_store_arc).unwrap();
     tx_check_predecessor.send(predecessor_option_arc).unwrap();
     tx_successor_list.send(successor_list_arc).unwrap();
     Ok(())
 }
+
+/// Reads the routing snapshot of the last run. It is only a hint, so a missing, unreadable or
+/// foreign snapshot is ignored.
+fn load_routing_snapshot(path: &str, own_address: &Address, finger_table: &FingerTable) -> Option<RoutingSnapshot> {
+    match read_routing_snapshot(path) {
+        Ok(routing_snapshot) if routing_snapshot.belongs_to(own_address, finger_table) => {
+            info!("Loaded routing snapshot {}", path);
+            Some(routing_snapshot)
+        }
+        Ok(_) => {
+            warn!("Ignoring routing snapshot {} of another node", path);
+            None
+        }
+        Err(err) if err.kind() == ErrorKind::NotFound => None,
+        Err(err) => {
+            warn!("Unable to load routing snapshot {}: {}", path, err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+    use tokio::sync::oneshot;
+    use tokio_stream::wrappers::TcpListenerStream;
+    use tonic::transport::Server;
+
+    use crate::threads::chord::chord_proto::chord_server::{Chord, ChordServer};
+    use crate::threads::chord::chord_proto::{GetRequest, GetStatus};
+    use crate::threads::chord::ChordService;
+    use crate::node::kv_dump::write_kv_dump;
+    use crate::threads::chord::tests::{spawn_test_ring, test_config, TestNode};
+    use crate::utils::constants::SUCCESSOR_LIST_LENGTH_DEFAULT;
+    use crate::utils::types::HashPos;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn joined_node_serves_reads_before_stabilize() {
+        let ring = spawn_test_ring(3).await;
+        let own_address = "127.0.0.1:6100".to_string();
+
+        let own_pos = hash(own_address.as_bytes());
+        let successor = ring.iter()
+            .find(|node| hash(node.address.as_bytes()) >= own_pos)
+            .unwrap_or(&ring[0]);
+        let predecessor_pos = ring.iter()
+            .map(|node| hash(node.address.as_bytes()))
+            .filter(|pos| *pos < own_pos)
+            .max()
+            .unwrap_or_else(|| hash(ring.last().unwrap().address.as_bytes()));
+        let key: Key = (0u64..)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[..8].copy_from_slice(&i.to_be_bytes());
+                key
+            })
+            .find(|key| is_between(hash(key), predecessor_pos.wrapping_add(1), own_pos, false, false))
+            .unwrap();
+        successor.kv_store.lock().unwrap().insert(key, ("value".to_string(), u64::MAX));
+
+        let (tx_grpc_thread, rx_grpc_thread) = oneshot::channel();
+        let (tx_check_predecessor, _rx_check_predecessor) = oneshot::channel();
+        let (tx_successor_list, _rx_successor_list) = oneshot::channel();
+        let (tx_web_interface, _rx_web_interface) = oneshot::channel();
+        setup(Some(ring[0].address.clone()), &own_address, tx_grpc_thread,
+              tx_check_predecessor, tx_successor_list, tx_web_interface, 0, 1, true, None, None, Duration::ZERO, SUCCESSOR_LIST_LENGTH_DEFAULT).await.unwrap();
+        let service = ChordService::new(rx_grpc_thread, &test_config(&own_address)).await.unwrap();
+
+        // the successor handed the key over during join and already points to us as predecessor
+        assert!(successor.kv_store.lock().unwrap().is_empty());
+        assert_eq!(successor.predecessor_option.lock().unwrap().clone().unwrap().address, own_address);
+
+        let response = service.get(Request::new(GetRequest { key: key.to_vec() }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.status, i32::from(GetStatus::Ok));
+        assert_eq!(response.value, "value");
+    }
+
+    #[tokio::test]
+    async fn snapshot_pairs_outside_of_the_owned_range_are_dropped_after_join() {
+        let ring = spawn_test_ring(3).await;
+        let own_address = "127.0.0.1:6101".to_string();
+        let own_pos = hash(own_address.as_bytes());
+        let predecessor_pos = ring.iter()
+            .map(|node| hash(node.address.as_bytes()))
+            .filter(|pos| *pos < own_pos)
+            .max()
+            .unwrap_or_else(|| hash(ring.last().unwrap().address.as_bytes()));
+        let keys = (0u64..).map(|i| {
+            let mut key = [0u8; 32];
+            key[..8].copy_from_slice(&i.to_be_bytes());
+            key
+        });
+        let owned_key: Key = keys.clone().find(|key| is_between(hash(key), predecessor_pos.wrapping_add(1), own_pos, false, false)).unwrap();
+        let foreign_key: Key = keys.clone().find(|key| !is_between(hash(key), predecessor_pos.wrapping_add(1), own_pos, false, false)).unwrap();
+
+        let data_dir = std::env::temp_dir().join(format!("chord-setup-{}", std::process::id()));
+        std::fs::create_dir_all(&data_dir).unwrap();
+        let mut snapshot = KvStore::new();
+        snapshot.insert(owned_key, ("owned".to_string(), u64::MAX));
+        snapshot.insert(foreign_key, ("foreign".to_string(), u64::MAX));
+        write_kv_dump(&snapshot, data_dir.join(SNAPSHOT_FILE_NAME).to_str().unwrap()).unwrap();
+
+        let (tx_grpc_thread, rx_grpc_thread) = oneshot::channel();
+        let (tx_check_predecessor, _rx_check_predecessor) = oneshot::channel();
+        let (tx_successor_list, _rx_successor_list) = oneshot::channel();
+        let (tx_web_interface, _rx_web_interface) = oneshot::channel();
+        setup(Some(ring[0].address.clone()), &own_address, tx_grpc_thread, tx_check_predecessor, tx_successor_list, tx_web_interface, 0, 1, false,
+              None, Some(data_dir.to_str().unwrap().to_string()), Duration::from_secs(3600), SUCCESSOR_LIST_LENGTH_DEFAULT).await.unwrap();
+        let (_, _, kv_store_arc, _) = rx_grpc_thread.await.unwrap();
+        std::fs::remove_dir_all(&data_dir).unwrap();
+
+        let kv_store_guard = kv_store_arc.lock().unwrap();
+        assert!(kv_store_guard.contains_key(&owned_key));
+        assert!(!kv_store_guard.contains_key(&foreign_key));
+    }
+
+    #[tokio::test]
+    async fn join_initializes_all_fingers_with_one_batched_lookup() {
+        let ring = spawn_test_ring(3).await;
+        let own_address = "127.0.0.1:6100".to_string();
+        let own_pos = hash(own_address.as_bytes());
+        // positions of the ring after the join
+        let mut members: Vec<(HashPos, Address)> = ring.iter()
+            .map(|node| (hash(node.address.as_bytes()), node.address.clone()))
+            .chain([(own_pos, own_address.clone())])
+            .collect();
+        members.sort();
+        let responsible_address = |key: HashPos| members.iter()
+            .find(|(pos, _)| key <= *pos)
+            .unwrap_or(&members[0])
+            .1.clone();
+
+        let (tx_grpc_thread, rx_grpc_thread) = oneshot::channel();
+        let (tx_check_predecessor, _rx_check_predecessor) = oneshot::channel();
+        let (tx_successor_list, _rx_successor_list) = oneshot::channel();
+        let (tx_web_interface, _rx_web_interface) = oneshot::channel();
+        setup(Some(ring[0].address.clone()), &own_address, tx_grpc_thread,
+              tx_check_predecessor, tx_successor_list, tx_web_interface, 0, 1, true, None, None, Duration::ZERO, SUCCESSOR_LIST_LENGTH_DEFAULT).await.unwrap();
+        let (finger_table_arc, _, _, _) = rx_grpc_thread.await.unwrap();
+
+        // without the batched lookup, all fingers but the first stayed uninitialized until fix_fingers
+        for finger in finger_table_arc.lock().unwrap().fingers.iter() {
+            assert_eq!(finger.address, responsible_address(finger.key), "finger for {}", finger.key);
+        }
+    }
+
+    /// joins a new node to the ring via setup and serves it on the given listener
+    async fn join_and_serve(ring: &[TestNode], listener: TcpListener, seed_all_fingers: bool) -> (Address, Arc<Mutex<FingerTable>>) {
+        let own_address = listener.local_addr().unwrap().to_string();
+        let (tx_grpc_thread, rx_grpc_thread) = oneshot::channel();
+        let (tx_check_predecessor, _rx_check_predecessor) = oneshot::channel();
+        let (tx_successor_list, _rx_successor_list) = oneshot::channel();
+        let (tx_web_interface, rx_web_interface) = oneshot::channel();
+        setup(Some(ring[0].address.clone()), &own_address, tx_grpc_thread,
+              tx_check_predecessor, tx_successor_list, tx_web_interface, 0, 1, seed_all_fingers, None, None, Duration::ZERO, SUCCESSOR_LIST_LENGTH_DEFAULT).await.unwrap();
+        let service = ChordService::new(rx_grpc_thread, &test_config(&own_address)).await.unwrap();
+        tokio::spawn(async move {
+            Server::builder()
+                .add_service(ChordServer::new(service))
+                .serve_with_incoming(TcpListenerStream::new(listener))
+                .await
+        });
+        (own_address, rx_web_interface.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn seeded_fingers_shorten_lookups_right_after_join() {
+        let ring = spawn_test_ring(8).await;
+        let keys: Vec<HashPos> = (0..64u64).map(|i| i * (HashPos::MAX / 64)).collect();
+        let successor_index = |address: &Address| ring.iter()
+            .position(|node| hash(node.address.as_bytes()) >= hash(address.as_bytes()))
+            .unwrap_or(0);
+
+        let mut hop_counts = Vec::new();
+        let mut used_successor_indices = Vec::new();
+        for seed_all_fingers in [true, false] {
+            // both joiners need different successors, the ring nodes don't know the first one yet
+            let listener = loop {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let index = successor_index(&listener.local_addr().unwrap().to_string());
+                if !used_successor_indices.contains(&index) {
+                    used_successor_indices.push(index);
+                    break listener;
+                }
+            };
+            let (own_address, finger_table_arc) = join_and_serve(&ring, listener, seed_all_fingers).await;
+            let uninitialized_finger_count = finger_table_arc.lock().unwrap().fingers.iter()
+                .filter(|finger| finger.is_uninitialized())
+                .count();
+            assert_eq!(uninitialized_finger_count == 0, seed_all_fingers);
+
+            let mut client = connect_with_retry(&own_address).await.unwrap();
+            let mut hop_count = 0;
+            for key in &keys {
+                let response = client.trace_lookup(Request::new((*key).into())).await.unwrap().into_inner();
+                hop_count += response.path.len() - 1;
+            }
+            hop_counts.push(hop_count);
+        }
+        info!("Lookups right after join took {} hops with seeded fingers and {} hops without", hop_counts[0], hop_counts[1]);
+        assert!(hop_counts[0] < hop_counts[1], "seeded: {}, unseeded: {}", hop_counts[0], hop_counts[1]);
+    }
+}
@@ -1,64 +1,181 @@
 use std::error::Error;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
 
-use log::{error, info};
+use log::{error, info, warn};
 use tokio::signal;
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::oneshot::Receiver;
-use tokio_stream::iter;
+use tokio::time::timeout;
+use tokio_stream::{iter, StreamExt};
 use tonic::Request;
 
-use chord::utils::crypto::{hash, is_between};
-use chord::utils::types::KvStore;
+use crate::threads::chord::{ChordService, connect_with_retry, into_batches};
+use crate::threads::chord::chord_proto::KvPairMsg;
+use crate::utils::types::Address;
 
-use crate::node::successor_list::SuccessorList;
-use crate::threads::chord::{connect_to_first_reachable_node, connect_with_retry};
-use crate::threads::chord::chord_proto::{Empty, KvPairMsg};
-use crate::utils::crypto::HashRingKey;
-use crate::utils::types::{Address, HashPos};
+/// Waits for SIGINT or SIGTERM and then lets the node leave the ring gracefully, handing over all
+/// of its pairs to its successor. The node can only leave once its setup completed and the gRPC
+/// service is running, which is signaled by the service being sent over rx.
+pub async fn shutdown_handoff(rx: Receiver<Arc<ChordService>>) -> Result<(), Box<dyn Error>> {
+    let chord_service = rx.await?;
 
-pub async fn shutdown_handoff(local_grpc_service_address: Address, rx: Receiver<Arc<Mutex<KvStore>>>) -> Result<(), Box<dyn Error>> {
-    let kv_store_arc = rx.await.unwrap();
-    let one = HashPos::one();
+    let mut sigterm = signal(SignalKind::terminate())?;
+    info!("Shutdown handoff thread ready...");
+    tokio::select! {
+        ctrl_c_result = signal::ctrl_c() => {
+            if let Err(err) = ctrl_c_result {
+                error!("Unable to listen for shutdown signal: {}", err);
+                return Ok(());
+            }
+        }
+        _ = sigterm.recv() => {}
+    }
+    info!("Preparing shutdown...");
+    if let Err(status) = chord_service.leave().await {
+        error!("Shutting down without a completed handoff, data is lost: {}", status);
+    }
+    Ok(())
+}
 
+/// Streams all pairs to the first successor that accepts the whole handoff within the deadline.
+/// If a successor is unreachable or too slow, the full handoff is repeated with the next
+/// successor in the list. Returns the successor that completed the handoff, if any. The pairs are
+/// sent in stream messages of at most handoff_batch_size pairs.
+pub(crate) async fn handoff_to_first_available_successor(successors: &Vec<Address>, pairs: Vec<KvPairMsg>, handoff_deadline: Duration,
+                                                         handoff_batch_size: usize) -> Option<Address> {
+    let total = pairs.len();
+    let batches = into_batches(pairs, handoff_batch_size);
+    for successor_address in successors {
+        let sent_counter = Arc::new(AtomicUsize::new(0));
+        let sent_counter_clone = sent_counter.clone();
+        let stream = iter(batches.clone())
+            .map(move |batch| {
+                sent_counter_clone.fetch_add(batch.pairs.len(), Ordering::Relaxed);
+                batch
+            });
 
-    let mut local_grpc_client = connect_with_retry(&local_grpc_service_address)
-        .await
-        .unwrap();
-    info!("Shutdown handoff thread ready...");
-    match signal::ctrl_c().await {
-        Ok(()) => {
-            info!("Preparing shutdown...");
-            let successor_list: SuccessorList = local_grpc_client.get_successor_list(Request::new(Empty {}))
-                .await
-                .unwrap().into_inner().into();
-
-            let (mut successor_client, successor_address) = connect_to_first_reachable_node(&successor_list.successors)
-                .await
-                .unwrap();
-            info!("Selected successor for handoff");
-
-            let mut counter = 0;
-            let foo: Vec<KvPairMsg> = {
-                let bar = kv_store_arc.lock().unwrap();
-                bar.iter()
-                    .filter(move |(key, _)| is_between(hash(*key), one + 1, one, false, false))
-                    .inspect(|_| { counter += 1; })
-                    .map(|(k, (v, expiration_date))| {
-                        KvPairMsg {
-                            key: k.to_vec(),
-                            value: v.to_string(),
-                            expiration_date: expiration_date.clone()
-                        }
-                    })
-                    .collect()
-            };
+        let handoff_result = timeout(handoff_deadline, async {
+            let mut successor_client = connect_with_retry(successor_address).await?;
+            successor_client.handoff(Request::new(stream)).await
+        }).await;
 
-            let _ = successor_client.handoff(Request::new(iter(foo))).await;
-            info!("Transfered {} key-value-pairs to {}", counter, successor_address);
+        let sent = sent_counter.load(Ordering::Relaxed);
+        match handoff_result {
+            Ok(Ok(_)) => {
+                info!("Transferred {} key-value-pairs to {}", total, successor_address);
+                return Some(successor_address.clone());
+            }
+            Ok(Err(status)) => {
+                warn!("Handoff to {} failed: {}. {} of {} pairs sent, {} remain, trying next successor", successor_address, status, sent, total, total - sent);
+            }
+            Err(_) => {
+                warn!("Handoff to {} did not complete within {:?}. {} of {} pairs sent, {} remain, trying next successor", successor_address, handoff_deadline, sent, total, total - sent);
+            }
         }
-        Err(err) => {
-            error!("Unable to listen for shutdown signal: {}", err);
+    }
+    error!("No successor accepted the handoff, {} key-value-pairs remain untransferred", total);
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use crate::threads::chord::tests::spawn_test_node;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_successor_is_skipped_after_deadline() {
+        // accepts connections but never answers, so a handoff to it can never complete
+        let slow_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let slow_address = slow_listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            loop {
+                sockets.push(slow_listener.accept().await.unwrap());
+            }
+        });
+        let next_successor = spawn_test_node(None).await;
+
+        let pairs: Vec<KvPairMsg> = (0..10u8)
+            .map(|i| KvPairMsg { key: [i; 32].to_vec(), value: i.to_string(), expiration_date: u64::MAX })
+            .collect();
+        let successors = vec![slow_address, next_successor.address.clone()];
+
+        assert_eq!(handoff_to_first_available_successor(&successors, pairs, Duration::from_millis(500), 1).await, Some(next_successor.address.clone()));
+        assert_eq!(next_successor.kv_store.lock().unwrap().len(), 10);
+    }
+
+    #[tokio::test]
+    async fn received_handoff_is_accounted_in_received_bytes() {
+        let node = spawn_test_node(None).await;
+        let pairs = vec![
+            KvPairMsg { key: vec![1; 32], value: "first".to_string(), expiration_date: u64::MAX },
+            KvPairMsg { key: vec![2; 32], value: "second".to_string(), expiration_date: u64::MAX },
+        ];
+
+        assert!(handoff_to_first_available_successor(&vec![node.address.clone()], pairs, Duration::from_secs(5), 1).await.is_some());
+        assert_eq!(node.stats.handoff_bytes_received.load(Ordering::Relaxed), (32 + 5 + 32 + 6) as u64);
+    }
+
+    /// forwards all connections to target, counting the bytes sent towards it
+    async fn spawn_counting_proxy(target: Address) -> (Address, Arc<AtomicU64>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let forwarded_bytes = Arc::new(AtomicU64::new(0));
+        let forwarded_bytes_clone = forwarded_bytes.clone();
+        tokio::spawn(async move {
+            loop {
+                let (inbound, _) = listener.accept().await.unwrap();
+                let outbound = TcpStream::connect(&target).await.unwrap();
+                let forwarded_bytes = forwarded_bytes_clone.clone();
+                let (mut inbound_read, mut inbound_write) = inbound.into_split();
+                let (mut outbound_read, mut outbound_write) = outbound.into_split();
+                // counted before forwarding, so all bytes of a request are counted once it is answered
+                tokio::spawn(async move {
+                    let mut buf = vec![0; 8192];
+                    while let Ok(n) = inbound_read.read(&mut buf).await {
+                        if n == 0 || outbound_write.write_all(&buf[..n]).await.is_err() {
+                            break;
+                        }
+                        forwarded_bytes.fetch_add(n as u64, Ordering::Relaxed);
+                    }
+                });
+                tokio::spawn(async move {
+                    let _ = tokio::io::copy(&mut outbound_read, &mut inbound_write).await;
+                });
+            }
+        });
+        (address, forwarded_bytes)
+    }
+
+    #[tokio::test]
+    async fn batched_handoff_sends_fewer_bytes_than_unbatched() {
+        let pairs: Vec<KvPairMsg> = (0..1_000u32)
+            .map(|i| {
+                let mut key = vec![0; 32];
+                key[..4].copy_from_slice(&i.to_be_bytes());
+                KvPairMsg { key, value: i.to_string(), expiration_date: u64::MAX }
+            })
+            .collect();
+
+        let mut sent_bytes = Vec::new();
+        for batch_size in [1, 256] {
+            let node = spawn_test_node(None).await;
+            let (proxy_address, forwarded_bytes) = spawn_counting_proxy(node.address.clone()).await;
+            assert_eq!(handoff_to_first_available_successor(&vec![proxy_address.clone()], pairs.clone(), Duration::from_secs(60), batch_size).await, Some(proxy_address));
+            assert_eq!(node.kv_store.lock().unwrap().len(), pairs.len());
+            sent_bytes.push(forwarded_bytes.load(Ordering::Relaxed));
         }
+        // every stream message carries its own length prefix, batching saves it for all but one
+        // message per batch
+        assert!(sent_bytes[1] < sent_bytes[0], "batched: {} bytes, unbatched: {} bytes", sent_bytes[1], sent_bytes[0]);
     }
-    Ok(())
 }
@@ -6,9 +6,8 @@ use tonic::Request;
 
 use crate::threads::chord::chord_proto::Empty;
 use crate::threads::chord::connect_with_retry;
-use crate::utils::constants::STABILIZE_SLEEP_MILLIS;
 
-pub async fn stabilize_periodically(local_grpc_service_address: String) -> ! {
+pub async fn stabilize_periodically(local_grpc_service_address: String, stabilize_interval: Duration) -> ! {
     info!("Starting up periodic stabilization thread");
     let mut client = connect_with_retry(&local_grpc_service_address).await.unwrap();
     debug!("Successfully connected to local grpc service");
@@ -17,6 +16,6 @@ pub async fn stabilize_periodically(local_grpc_service_address: String) -> ! {
             Err(error) => error!("An error occured during stabilization: {}", error),
             _ => {}
         }
-        sleep(Duration::from_millis(STABILIZE_SLEEP_MILLIS)).await;
+        sleep(stabilize_interval).await;
     }
 }
@@ -4,42 +4,152 @@ use log::{info, warn};
 
 use tokio::sync::oneshot::Receiver;
 use tokio::time::sleep;
-use tonic::Request;
-use chord::utils::constants::HEALTH_SLEEP_MILLIS;
-use crate::node::successor_list::{SUCCESSOR_LIST_SIZE, SuccessorList};
-use crate::threads::chord::connect_with_retry;
+use chord::utils::sync::LockOrRecover;
+use crate::node::successor_list::SuccessorList;
+use crate::threads::chord::{connect_with_retry, request_with_timeout, with_rpc_timeout};
 use crate::threads::chord::chord_proto::Empty;
 
 /// periodic successor list checking:
 /// this function fetches the successor's successor list and updates this node's successor list
-/// with the successor's successor list
-pub async fn check_successor_list_periodically(local_grpc_service_address: String, rx: Receiver<Arc<Mutex<SuccessorList>>>) -> ! {
+/// with the successor's successor list. It runs independently of stabilize and fix_fingers, so the
+/// list stays fresh even if those stall.
+/// A successor that misses health checks is only demoted once it kept failing for the grace period,
+/// and a demoted successor is only taken back into the list after the stable period.
+pub async fn check_successor_list_periodically(rx: Receiver<Arc<Mutex<SuccessorList>>>, health_interval: Duration,
+                                               grace_period: Duration, stable_period: Duration) -> ! {
     let successor_list_arc = rx.await.unwrap();
     info!("Starting up periodic successor list check thread");
 
-    let mut local_grpc_client = connect_with_retry(&local_grpc_service_address)
-        .await
-        .unwrap();
-
     loop {
-        let successor_list: SuccessorList = local_grpc_client.get_successor_list(Request::new(Empty {}))
-            .await
-            .unwrap().into_inner().into();
-
-        for i in 0..SUCCESSOR_LIST_SIZE {
-            match connect_with_retry(&successor_list.successors[i]).await {
-                Ok(mut successor_client) => {
-                    let successors_successor_list: SuccessorList = successor_client.get_successor_list(Request::new(Empty{}))
-                        .await
-                        .unwrap().into_inner().into();
-                    successor_list_arc.lock().unwrap().update_with_other_succ_list(successors_successor_list.clone());
-                    break;
-                },
-                Err(_) => {
-                    warn!("Cannot connect to {}-th successor, retrying with next...", i+1)
+        refresh_successor_list(&successor_list_arc, grace_period, stable_period).await;
+        sleep(health_interval).await;
+    }
+}
+
+/// Replaces the successor list by the first reachable successor followed by that successor's own
+/// successor list. Unreachable successors in front of it are dropped, so the list recovers even if
+/// several consecutive successors fail at the same time. Failing successors are kept until the
+/// grace period runs out, so briefly unavailable successors don't cause churn.
+pub(crate) async fn refresh_successor_list(successor_list_arc: &Arc<Mutex<SuccessorList>>,
+                                           grace_period: Duration, stable_period: Duration) {
+    let successors = successor_list_arc.lock_or_recover().successors.clone();
+
+    for (i, successor_address) in successors.iter().enumerate() {
+        let successor_list_result = match connect_with_retry(successor_address).await {
+            Ok(mut successor_client) => with_rpc_timeout(successor_client.get_successor_list(request_with_timeout(Empty {}))).await,
+            Err(status) => Err(status)
+        };
+        match successor_list_result {
+            Ok(response) => {
+                let successors_successor_list: SuccessorList = response.into_inner().into();
+                let mut successor_list_guard = successor_list_arc.lock_or_recover();
+                successor_list_guard.clear_suspicion(successor_address);
+                successor_list_guard.update_with_other_succ_list(successors_successor_list);
+                successor_list_guard.drop_quarantined(stable_period);
+                return;
+            }
+            Err(status) => {
+                warn!("Failed to fetch successor list of {}-th successor: {}", i + 1, status);
+                if !successor_list_arc.lock_or_recover().suspect(successor_address, grace_period) {
+                    info!("Keeping {}-th successor {} during its grace period", i + 1, successor_address);
+                    return;
                 }
+                warn!("Demoted {}-th successor {}, retrying with next...", i + 1, successor_address);
             }
         }
-        sleep(Duration::from_millis(HEALTH_SLEEP_MILLIS)).await;
+    }
+    warn!("All successors in successor list are unreachable");
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tokio::net::TcpListener;
+
+    use chord::utils::constants::SUCCESSOR_LIST_LENGTH_DEFAULT;
+
+    use crate::threads::chord::tests::{spawn_test_node, spawn_test_ring};
+
+    use super::*;
+
+    async fn unreachable_address() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn two_consecutive_failed_successors_are_skipped() {
+        let third_successor = spawn_test_node(None).await;
+        let second_successor_address = unreachable_address().await;
+        let first_successor_address = unreachable_address().await;
+
+        let successor_list_arc = Arc::new(Mutex::new(SuccessorList {
+            own_address: "127.0.0.1:6000".to_string(),
+            successors: vec![first_successor_address, second_successor_address, third_successor.address.clone()],
+            ..SuccessorList::default()
+        }));
+        refresh_successor_list(&successor_list_arc, Duration::ZERO, Duration::ZERO).await;
+
+        let successors = successor_list_arc.lock().unwrap().successors.clone();
+        assert_eq!(successors, vec![third_successor.address.clone()]);
+    }
+
+    #[tokio::test]
+    async fn list_longer_than_the_ring_wraps_around_without_duplicates() {
+        let nodes = spawn_test_ring(3).await;
+        for (i, node) in nodes.iter().enumerate() {
+            let mut successor_list_guard = node.successor_list.lock().unwrap();
+            successor_list_guard.length = 5;
+            successor_list_guard.successors = vec![nodes[(i + 1) % 3].address.clone()];
+        }
+
+        for _ in 0..3 {
+            for node in nodes.iter() {
+                refresh_successor_list(&node.successor_list, Duration::ZERO, Duration::ZERO).await;
+            }
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            let successors = node.successor_list.lock().unwrap().successors.clone();
+            assert_eq!(successors, vec![
+                nodes[(i + 1) % 3].address.clone(),
+                nodes[(i + 2) % 3].address.clone(),
+                node.address.clone(),
+            ]);
+        }
+    }
+
+    #[tokio::test]
+    async fn flapping_successor_is_kept_during_grace_period_and_quarantined_after_demotion() {
+        let second_successor = spawn_test_node(None).await;
+        // the flapping successor is down for now, we reuse its address once it comes back
+        let flapping_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let flapping_address = flapping_listener.local_addr().unwrap().to_string();
+        drop(flapping_listener);
+
+        let successor_list_arc = Arc::new(Mutex::new(SuccessorList::new(&"127.0.0.1:6000".to_string(), &flapping_address, SUCCESSOR_LIST_LENGTH_DEFAULT)));
+        successor_list_arc.lock().unwrap().successors.push(second_successor.address.clone());
+        let grace_period = Duration::from_millis(2_000);
+        let stable_period = Duration::from_secs(60);
+
+        // first missed check: the successor stays in place, but is suspected
+        refresh_successor_list(&successor_list_arc, grace_period, stable_period).await;
+        assert_eq!(successor_list_arc.lock().unwrap().successors[0], flapping_address);
+        assert!(successor_list_arc.lock().unwrap().suspected_since.contains_key(&flapping_address));
+
+        // still failing after the grace period: it gets demoted
+        sleep(grace_period).await;
+        refresh_successor_list(&successor_list_arc, grace_period, stable_period).await;
+        assert_eq!(successor_list_arc.lock().unwrap().successors[0], second_successor.address);
+        assert!(successor_list_arc.lock().unwrap().is_quarantined(&flapping_address, stable_period));
+
+        // the successor reports the flapping node in its list again, it is not re-promoted yet
+        second_successor.successor_list.lock().unwrap().successors = vec![flapping_address.clone(), second_successor.address.clone()];
+        refresh_successor_list(&successor_list_arc, grace_period, stable_period).await;
+        assert!(!successor_list_arc.lock().unwrap().successors.contains(&flapping_address));
+
+        // once stable for the stable period, it is accepted again
+        refresh_successor_list(&successor_list_arc, grace_period, Duration::ZERO).await;
+        assert!(successor_list_arc.lock().unwrap().successors.contains(&flapping_address));
     }
 }
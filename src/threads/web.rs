@@ -1,30 +1,84 @@
 use std::sync::{Arc, Mutex};
 
-use actix_web::{get, HttpResponse, Responder, web};
+use actix_web::{delete, get, HttpRequest, HttpResponse, put, Responder, web};
+use actix_web::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use actix_web::http::StatusCode;
 use actix_web::web::Query;
-use serde::Deserialize;
+use log::error;
+use serde::{Deserialize, Serialize};
 use tera::{Context, Tera};
-use tonic::Request;
+use tonic::{Code, Request, Status};
 
 use chord::utils::config::Config;
 use chord::utils::crypto;
-use chord::utils::types::HashPos;
+use chord::utils::sync::LockOrRecover;
+use chord::utils::types::{HashPos, Key};
 
 use crate::node::finger_table::FingerTable;
-use crate::threads::chord::chord_proto::{GetRequest, GetStatus, PutRequest};
-use crate::threads::chord::connect_with_retry;
+use crate::threads::chord::chord_proto::{DeleteRequest, DeleteStatus, Empty, GetRequest, GetStatus, PutRequest, StatsMsg, TtlUnit};
+use crate::threads::chord::connect;
 use crate::threads::client_api::perform_chord_look_up;
 
+/// number of leading hex digits shown for ring positions unless full precision is configured
+const POSITION_HEX_PREFIX_LENGTH: usize = 6;
+
+/// ttl in seconds of pairs put via the web interface without an explicit ttl
+const WEB_PUT_TTL_DEFAULT: u64 = 100_000;
+
+/// finger as rendered in the finger table of the web interface
+#[derive(Serialize)]
+struct FingerRow {
+    address: String,
+    position: String,
+    /// share of the ring between position 0 and the finger key
+    ring_percentage: String,
+}
+
 #[derive(Deserialize)]
 struct QueryParams {
     get_request_key: Option<String>,
     put_request_key: Option<String>,
     put_request_value: Option<String>,
+    /// in seconds, parsed by hand so that invalid input can be reported on the page
+    put_request_ttl: Option<String>,
+    put_request_replication: Option<String>,
+    delete_request_key: Option<String>,
+    /// number of successors holding replicas of the pair, they are deleted as well
+    delete_request_replication: Option<String>,
+}
+
+/// JSON body of PUT /kv/{key}
+#[derive(Deserialize)]
+struct KvPutBody {
+    value: String,
+    /// in seconds
+    ttl: Option<u64>,
+    replication: Option<u32>,
+}
+
+/// query of DELETE /kv/{key}
+#[derive(Deserialize)]
+struct KvDeleteQuery {
+    /// number of successors holding replicas of the pair, they are deleted as well
+    replication: Option<u32>,
+}
+
+/// JSON response of the /kv routes, status uses the same names as the html interface
+#[derive(Serialize, Default)]
+struct KvResponse {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effective_ttl: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 
 #[get("/")]
 pub async fn index(
+    request: HttpRequest,
     finger_table_data: web::Data<Arc<Mutex<FingerTable>>>,
     config: web::Data<Config>,
     local_grpc_address: web::Data<String>,
@@ -32,88 +86,571 @@ pub async fn index(
 ) -> impl Responder {
     let tera = Tera::new("static/html/**/*").unwrap();
     let mut context = Context::new();
+    let mut http_status = StatusCode::OK;
+    let mut etag_option = None;
 
     if query_params_option.is_some() {
-        match query_params_option.unwrap().0 {
+        let request_result = match query_params_option.unwrap().0 {
             QueryParams {
                 get_request_key: Some(get_input),
                 put_request_key: None,
-                put_request_value: None
+                put_request_value: None,
+                delete_request_key: None,
+                ..
             } => {
                 perform_get_and_update_context(&get_input, &local_grpc_address, &mut context)
-                    .await;
+                    .await
             }
             QueryParams {
                 get_request_key: None,
                 put_request_key: Some(put_key_input),
-                put_request_value: Some(put_value_input)
+                put_request_value: Some(put_value_input),
+                put_request_ttl,
+                put_request_replication,
+                delete_request_key: None,
+                ..
             } => {
-                perform_put_and_update_context(&put_key_input, put_value_input, &local_grpc_address, &mut context)
-                    .await;
+                match parse_put_options(put_request_ttl.as_deref(), put_request_replication.as_deref()) {
+                    Ok((ttl, replication)) => perform_put_and_update_context(&put_key_input, put_value_input, ttl, replication, &local_grpc_address, &mut context)
+                        .await
+                        .map(|_| None),
+                    Err(status) => Err(status)
+                }
             }
-            QueryParams { get_request_key: None, put_request_key: None, put_request_value: None } => {}
-            _ => { panic!("Invalid query params") }
+            QueryParams {
+                get_request_key: None,
+                put_request_key: None,
+                put_request_value: None,
+                delete_request_key: Some(delete_input),
+                delete_request_replication,
+                ..
+            } => {
+                match parse_replication(delete_request_replication.as_deref()) {
+                    Ok(replication) => perform_delete_and_update_context(&delete_input, replication, &local_grpc_address, &mut context)
+                        .await
+                        .map(|_| None),
+                    Err(status) => Err(status)
+                }
+            }
+            QueryParams { get_request_key: None, put_request_key: None, put_request_value: None, delete_request_key: None, .. } => Ok(None),
+            _ => Err(Status::invalid_argument("Invalid query params, send either a get, a put or a delete request"))
+        };
+
+        // a failed request must not take down the whole page, the error is rendered instead
+        match request_result {
+            Ok(etag) => etag_option = etag,
+            Err(status) => {
+                error!("Web request failed: {}", status);
+                context.insert("response_status", "ERROR");
+                context.insert("error_message", status.message());
+                http_status = http_status_of(&status);
+            }
+        }
+    }
+
+    // browsers polling the same key get a 304 as long as value and expiration are unchanged
+    if let Some(etag) = &etag_option {
+        let if_none_match = request.headers().get(IF_NONE_MATCH)
+            .and_then(|header| header.to_str().ok());
+        if if_none_match == Some(etag.as_str()) {
+            return HttpResponse::NotModified()
+                .insert_header((ETAG, etag.clone()))
+                .finish();
         }
     }
 
     context.insert("title", "Chord Node");
+    context.insert("put_ttl_default", &WEB_PUT_TTL_DEFAULT);
 
-    let finger_table_guard = finger_table_data.lock().unwrap();
+    let finger_table_guard = finger_table_data.lock_or_recover();
 
     context.insert("config", &config);
-    context.insert("fingers", &finger_table_guard.fingers);
-    context.insert("max_pos", &HashPos::MAX);
+    let finger_rows: Vec<FingerRow> = finger_table_guard.fingers.iter()
+        .map(|finger| FingerRow {
+            address: finger.address.clone(),
+            position: format_position(finger.key, config.full_precision_positions),
+            ring_percentage: format!("{:.4}%", ring_percentage(finger.key)),
+        })
+        .collect();
+    context.insert("fingers", &finger_rows);
 
     let rendered_html = tera.render("index.html", &context).unwrap();
 
-    HttpResponse::Ok()
+    let mut response_builder = HttpResponse::build(http_status);
+    if let Some(etag) = etag_option {
+        response_builder
+            .insert_header((ETAG, etag))
+            .insert_header((CACHE_CONTROL, "no-cache"));
+    }
+    response_builder
         .content_type("text/html")
         .body(rendered_html)
 }
 
-async fn perform_get_and_update_context(key: &String, local_grpc_address: &String, context: &mut Context) {
-    let mut key_array: [u8; 32] = [0; 32];
-    for (i, c) in key.chars().enumerate() {
-        key_array[i] = c as u8;
+/// readiness endpoint for orchestrators, only returns 200 once the node is fully integrated into
+/// the ring so that no traffic is routed to it prematurely
+#[get("/ready")]
+pub async fn ready(local_grpc_address: web::Data<String>) -> impl Responder {
+    let ready = match connect(&local_grpc_address).await {
+        Ok(mut local_grpc_client) => local_grpc_client.readiness(Request::new(Empty {}))
+            .await
+            .map(|response| response.into_inner().ready)
+            .unwrap_or(false),
+        Err(_) => false
+    };
+
+    if ready {
+        HttpResponse::Ok().body("ready")
+    } else {
+        HttpResponse::ServiceUnavailable().body("not ready")
+    }
+}
+
+/// node metrics in the Prometheus text format, taken from the get_stats rpc of the local node
+#[get("/metrics")]
+pub async fn metrics(local_grpc_address: web::Data<String>) -> impl Responder {
+    let stats_result = match connect(&local_grpc_address).await {
+        Ok(mut local_grpc_client) => local_grpc_client.get_stats(Request::new(Empty {}))
+            .await
+            .map(|response| response.into_inner()),
+        Err(err) => Err(Status::unavailable(err.to_string()))
+    };
+
+    match stats_result {
+        Ok(stats) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(render_metrics(&stats)),
+        Err(status) => {
+            error!("Unable to collect metrics: {}", status);
+            HttpResponse::ServiceUnavailable().body(status.message().to_string())
+        }
+    }
+}
+
+fn render_metrics(stats: &StatsMsg) -> String {
+    // every forwarded lookup is one hop from this node to the next
+    let find_successor_hops = stats.forwarded_lookup_latency.as_ref().map_or(0, |latency| latency.count);
+    let metric_rows = [
+        ("chord_get_total", "counter", "Get requests answered by this node", stats.get_total),
+        ("chord_get_expired_total", "counter", "Get requests that found an expired pair", stats.get_expired_total),
+        ("chord_put_total", "counter", "Put requests stored by this node", stats.put_total),
+        ("kv_store_size", "gauge", "Pairs currently stored by this node", stats.kv_store_size),
+        ("kv_store_expired_size", "gauge", "Expired pairs found by the last expiration sweep", stats.expired_kv_store_size),
+        ("fix_fingers_iterations_total", "counter", "Runs of fix_fingers", stats.fix_fingers_iterations_total),
+        ("stabilize_total", "counter", "Runs of stabilize", stats.stabilize_total),
+        ("find_successor_hops", "counter", "find_successor calls forwarded to other nodes", find_successor_hops),
+    ];
+    metric_rows.iter()
+        .map(|(name, metric_type, help, value)| format!("# HELP {0} {2}\n# TYPE {0} {1}\n{0} {3}\n", name, metric_type, help, value))
+        .collect()
+}
+
+/// full positions are unwieldy to compare in a table, a short hex prefix is enough to tell them apart
+fn format_position(pos: HashPos, full_precision: bool) -> String {
+    if full_precision {
+        pos.to_string()
+    } else {
+        let hex = format!("{:016x}", pos);
+        format!("{}…", &hex[..POSITION_HEX_PREFIX_LENGTH])
     }
+}
+
+fn ring_percentage(pos: HashPos) -> f64 {
+    pos as f64 / HashPos::MAX as f64 * 100.0
+}
+
+/// strong ETag of a stored pair, changes whenever the value or the expiration changes
+fn pair_etag(value: &str, expiration_date: u64) -> String {
+    let etag_input = [value.as_bytes(), &expiration_date.to_be_bytes()].concat();
+    format!("\"{:016x}\"", crypto::hash(&etag_input))
+}
 
+/// key as typed into the web interface, mapped to the 32 byte key of the DHT by hashing its UTF-8
+/// bytes, so keys of any length and with any characters map to distinct keys
+fn key_to_bytes(key: &str) -> Key {
+    crypto::hash_to_key(key.as_bytes())
+}
+
+/// performs the get request and returns the ETag of the found pair, if any
+async fn perform_get_and_update_context(key: &String, local_grpc_address: &String, context: &mut Context) -> Result<Option<String>, Status> {
+    let key_array = key_to_bytes(key);
     let hash_ring_pos: HashPos = crypto::hash(key_array.as_slice());
     let mut responsible_node_client = perform_chord_look_up(&hash_ring_pos, local_grpc_address.as_str())
-        .await;
+        .await?;
 
     let response = responsible_node_client.get(Request::new(GetRequest {
         key: key_array.to_vec(),
-    })).await.unwrap();
+    })).await?;
 
     match GetStatus::from_i32(response.get_ref().status) {
         Some(GetStatus::Ok) => {
             context.insert("response_status", "OK");
             context.insert("get_response", &response.get_ref().value);
+            Ok(Some(pair_etag(&response.get_ref().value, response.get_ref().expiration_date)))
         }
         Some(GetStatus::NotFound) => {
             context.insert("response_status", "NOT_FOUND");
+            Ok(None)
         }
         Some(GetStatus::Expired) => {
             context.insert("response_status", "EXPIRED");
+            Ok(None)
         }
-        _ => panic!("Received invalid get response status")
+        _ => Err(Status::internal("Received invalid get response status"))
     }
 }
 
-async fn perform_put_and_update_context(key: &String, value: String, local_grpc_address: &String, context: &mut Context) {
-    let mut key_array: [u8; 32] = [0; 32];
-    for (i, c) in key.chars().enumerate() {
-        key_array[i] = c as u8;
+/// ttl and replication of a put from the html form, empty inputs fall back to the defaults
+fn parse_put_options(ttl_option: Option<&str>, replication_option: Option<&str>) -> Result<(u64, u32), Status> {
+    let ttl = match ttl_option.map(str::trim).filter(|ttl| !ttl.is_empty()) {
+        Some(ttl) => ttl.parse::<u64>()
+            .map_err(|_| Status::invalid_argument(format!("Invalid ttl '{}', expected a number of seconds", ttl)))?,
+        None => WEB_PUT_TTL_DEFAULT
+    };
+    Ok((ttl, parse_replication(replication_option)?))
+}
+
+/// replication of a put or delete from the html form, an empty input means no replicas
+fn parse_replication(replication_option: Option<&str>) -> Result<u32, Status> {
+    match replication_option.map(str::trim).filter(|replication| !replication.is_empty()) {
+        Some(replication) => replication.parse::<u32>()
+            .map_err(|_| Status::invalid_argument(format!("Invalid replication '{}', expected a number of successors", replication))),
+        None => Ok(0)
     }
+}
 
+async fn perform_put_and_update_context(key: &String, value: String, ttl: u64, replication: u32, local_grpc_address: &String, context: &mut Context) -> Result<(), Status> {
+    let key_array = key_to_bytes(key);
     let hash_ring_pos: HashPos = crypto::hash(key_array.as_slice());
     let mut responsible_node_client = perform_chord_look_up(&hash_ring_pos, local_grpc_address.as_str())
-        .await;
+        .await?;
 
-    let _ = responsible_node_client.put(Request::new(PutRequest {
+    let response = responsible_node_client.put(Request::new(PutRequest {
         key: key_array.to_vec(),
-        ttl: 100000,
-        replication: 0,
+        ttl,
+        replication,
         value,
-    })).await.unwrap();
+        refresh_ttl_only_if_unchanged: false,
+        ttl_unit: TtlUnit::Seconds.into(),
+        is_replica: false,
+        primary: String::default(),
+    })).await?;
+    context.insert("put_response_status", "OK");
+    context.insert("put_effective_ttl", &response.get_ref().effective_ttl);
+    Ok(())
+}
+
+
+async fn perform_delete_and_update_context(key: &str, replication: u32, local_grpc_address: &str, context: &mut Context) -> Result<(), Status> {
+    let key_array = key_to_bytes(key);
+    let hash_ring_pos: HashPos = crypto::hash(key_array.as_slice());
+    let mut responsible_node_client = perform_chord_look_up(&hash_ring_pos, local_grpc_address)
+        .await?;
+
+    let response = responsible_node_client.delete(Request::new(DeleteRequest {
+        key: key_array.to_vec(),
+        replication,
+        is_replica: false,
+        primary: String::default(),
+    })).await?;
+
+    match DeleteStatus::from_i32(response.get_ref().status) {
+        Some(DeleteStatus::Ok) => context.insert("delete_response_status", "OK"),
+        Some(DeleteStatus::NotFound) => context.insert("delete_response_status", "NOT_FOUND"),
+        _ => return Err(Status::internal("Received invalid delete response status"))
+    }
+    Ok(())
+}
+
+#[get("/kv/{key}")]
+pub async fn kv_get(key: web::Path<String>, local_grpc_address: web::Data<String>) -> impl Responder {
+    into_kv_http_response(perform_kv_get(&key, &local_grpc_address).await)
+}
+
+#[put("/kv/{key}")]
+pub async fn kv_put(key: web::Path<String>, body: web::Json<KvPutBody>, local_grpc_address: web::Data<String>) -> impl Responder {
+    into_kv_http_response(perform_kv_put(&key, body.into_inner(), &local_grpc_address).await)
+}
+
+#[delete("/kv/{key}")]
+pub async fn kv_delete(key: web::Path<String>, query: Query<KvDeleteQuery>, local_grpc_address: web::Data<String>) -> impl Responder {
+    into_kv_http_response(perform_kv_delete(&key, query.replication.unwrap_or(0), &local_grpc_address).await)
+}
+
+/// rejected input, e.g. an invalid ttl or an oversized value, is the caller's fault, any other
+/// failure is reported as the node being unavailable
+fn http_status_of(status: &Status) -> StatusCode {
+    match status.code() {
+        Code::InvalidArgument => StatusCode::BAD_REQUEST,
+        _ => StatusCode::SERVICE_UNAVAILABLE
+    }
+}
+
+fn into_kv_http_response(result: Result<(StatusCode, KvResponse), Status>) -> HttpResponse {
+    match result {
+        Ok((http_status, kv_response)) => HttpResponse::build(http_status).json(kv_response),
+        Err(status) => {
+            error!("REST request failed: {}", status);
+            HttpResponse::build(http_status_of(&status)).json(KvResponse {
+                status: "ERROR",
+                error: Some(status.message().to_string()),
+                ..KvResponse::default()
+            })
+        }
+    }
+}
+
+async fn perform_kv_get(key: &str, local_grpc_address: &str) -> Result<(StatusCode, KvResponse), Status> {
+    let key_array = key_to_bytes(key);
+    let mut responsible_node_client = perform_chord_look_up(&crypto::hash(key_array.as_slice()), local_grpc_address)
+        .await?;
+
+    let response = responsible_node_client.get(Request::new(GetRequest {
+        key: key_array.to_vec(),
+    })).await?.into_inner();
+
+    match GetStatus::from_i32(response.status) {
+        Some(GetStatus::Ok) => Ok((StatusCode::OK, KvResponse { status: "OK", value: Some(response.value), ..KvResponse::default() })),
+        Some(GetStatus::NotFound) => Ok((StatusCode::NOT_FOUND, KvResponse { status: "NOT_FOUND", ..KvResponse::default() })),
+        Some(GetStatus::Expired) => Ok((StatusCode::NOT_FOUND, KvResponse { status: "EXPIRED", ..KvResponse::default() })),
+        _ => Err(Status::internal("Received invalid get response status"))
+    }
+}
+
+async fn perform_kv_put(key: &str, body: KvPutBody, local_grpc_address: &str) -> Result<(StatusCode, KvResponse), Status> {
+    let key_array = key_to_bytes(key);
+    let mut responsible_node_client = perform_chord_look_up(&crypto::hash(key_array.as_slice()), local_grpc_address)
+        .await?;
+
+    let response = responsible_node_client.put(Request::new(PutRequest {
+        key: key_array.to_vec(),
+        ttl: body.ttl.unwrap_or(WEB_PUT_TTL_DEFAULT),
+        replication: body.replication.unwrap_or(0),
+        value: body.value,
+        refresh_ttl_only_if_unchanged: false,
+        ttl_unit: TtlUnit::Seconds.into(),
+        is_replica: false,
+        primary: String::default(),
+    })).await?.into_inner();
+    Ok((StatusCode::OK, KvResponse { status: "OK", effective_ttl: Some(response.effective_ttl), ..KvResponse::default() }))
+}
+
+async fn perform_kv_delete(key: &str, replication: u32, local_grpc_address: &str) -> Result<(StatusCode, KvResponse), Status> {
+    let key_array = key_to_bytes(key);
+    let mut responsible_node_client = perform_chord_look_up(&crypto::hash(key_array.as_slice()), local_grpc_address)
+        .await?;
+
+    let response = responsible_node_client.delete(Request::new(DeleteRequest {
+        key: key_array.to_vec(),
+        replication,
+        is_replica: false,
+        primary: String::default(),
+    })).await?.into_inner();
+
+    match DeleteStatus::from_i32(response.status) {
+        Some(DeleteStatus::Ok) => Ok((StatusCode::OK, KvResponse { status: "OK", ..KvResponse::default() })),
+        Some(DeleteStatus::NotFound) => Ok((StatusCode::NOT_FOUND, KvResponse { status: "NOT_FOUND", ..KvResponse::default() })),
+        _ => Err(Status::internal("Received invalid delete response status"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use actix_web::App;
+    use actix_web::test as actix_test;
+    use tokio::net::TcpListener;
+
+    use chord::utils::time::now_millis;
+
+    use crate::threads::chord::tests::{put_request, serve_test_node, spawn_test_node, spawn_test_ring, test_config};
+
+    use super::*;
+
+    #[test]
+    fn etag_changes_with_value_and_expiration() {
+        let etag = pair_etag("value", 100);
+        assert_eq!(etag, pair_etag("value", 100));
+        assert_ne!(etag, pair_etag("other value", 100));
+        assert_ne!(etag, pair_etag("value", 200));
+    }
+
+    #[test]
+    fn positions_are_shortened_unless_full_precision_is_configured() {
+        let pos: HashPos = 0x1a2b3c4d5e6f7081;
+        assert_eq!(format_position(pos, false), "1a2b3c…");
+        assert_eq!(format_position(0, false), "000000…");
+        assert_eq!(format_position(pos, true), pos.to_string());
+    }
+
+    #[test]
+    fn ring_percentage_spans_the_whole_ring() {
+        assert_eq!(ring_percentage(0), 0.0);
+        assert_eq!(ring_percentage(HashPos::MAX / 2), 50.0);
+        assert_eq!(ring_percentage(HashPos::MAX), 100.0);
+    }
+
+    #[test]
+    fn long_keys_map_to_distinct_keys() {
+        let long_key = "k".repeat(100);
+        assert_eq!(key_to_bytes(&long_key), key_to_bytes(&"k".repeat(100)));
+        // keys used to be cut off after 32 characters
+        assert_ne!(key_to_bytes(&long_key), key_to_bytes(&long_key[..32]));
+    }
+
+    #[test]
+    fn multibyte_keys_are_not_mangled() {
+        // the low byte of 'ķ' (U+0137) is '7'
+        assert_ne!(key_to_bytes("ķey"), key_to_bytes("7ey"));
+        assert_eq!(key_to_bytes("ķey"), crypto::hash_to_key("ķey".as_bytes()));
+    }
+
+    #[actix_web::test]
+    async fn metrics_count_the_issued_puts() {
+        let node = spawn_test_node(None).await;
+        let mut client = connect(&node.address).await.unwrap();
+        for i in 0..3 {
+            client.put(Request::new(put_request(&[i; 32], "value", 100))).await.unwrap();
+        }
+
+        let app = actix_test::init_service(App::new()
+            .app_data(web::Data::new(node.address.clone()))
+            .service(metrics))
+            .await;
+        let body = actix_test::call_and_read_body(&app, actix_test::TestRequest::get().uri("/metrics").to_request()).await;
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("# TYPE chord_put_total counter\nchord_put_total 3\n"));
+        assert!(body.contains("kv_store_size 3\n"));
+        assert!(body.contains("chord_get_total 0\n"));
+    }
+
+    #[actix_web::test]
+    async fn kv_routes_put_get_and_delete_pairs() {
+        let nodes = spawn_test_ring(3).await;
+        let app = actix_test::init_service(App::new()
+            .app_data(web::Data::new(nodes[0].address.clone()))
+            .service(kv_get)
+            .service(kv_put)
+            .service(kv_delete))
+            .await;
+
+        let put_request = actix_test::TestRequest::put()
+            .uri("/kv/some-key")
+            .set_json(serde_json::json!({ "value": "some value", "ttl": 100 }))
+            .to_request();
+        let response = actix_test::call_service(&app, put_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body, serde_json::json!({ "status": "OK", "effective_ttl": 100 }));
+
+        let response = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/kv/some-key").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body, serde_json::json!({ "status": "OK", "value": "some value" }));
+
+        let response = actix_test::call_service(&app, actix_test::TestRequest::delete().uri("/kv/some-key").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body, serde_json::json!({ "status": "OK" }));
+
+        let response = actix_test::call_service(&app, actix_test::TestRequest::get().uri("/kv/some-key").to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body, serde_json::json!({ "status": "NOT_FOUND" }));
+
+        let response = actix_test::call_service(&app, actix_test::TestRequest::delete().uri("/kv/some-key").to_request()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn kv_delete_removes_the_replicas_of_the_pair() {
+        let nodes = spawn_test_ring(3).await;
+        let app = actix_test::init_service(App::new()
+            .app_data(web::Data::new(nodes[0].address.clone()))
+            .service(kv_put)
+            .service(kv_delete))
+            .await;
+        let replica_count = || nodes.iter().map(|node| node.replica_store.lock().unwrap().len()).sum::<usize>();
+
+        let put_request = actix_test::TestRequest::put()
+            .uri("/kv/some-key")
+            .set_json(serde_json::json!({ "value": "some value", "replication": 2 }))
+            .to_request();
+        assert_eq!(actix_test::call_service(&app, put_request).await.status(), StatusCode::OK);
+        assert_eq!(replica_count(), 2);
+
+        let delete_request = actix_test::TestRequest::delete().uri("/kv/some-key?replication=2").to_request();
+        assert_eq!(actix_test::call_service(&app, delete_request).await.status(), StatusCode::OK);
+        assert_eq!(replica_count(), 0);
+    }
+
+    #[actix_web::test]
+    async fn kv_put_of_an_oversized_value_is_a_bad_request() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let config = Config { max_value_bytes: 4, ..test_config(&address) };
+        let node = serve_test_node(listener, config, &address).await;
+        let app = actix_test::init_service(App::new()
+            .app_data(web::Data::new(node.address.clone()))
+            .service(kv_put))
+            .await;
+
+        let put_request = actix_test::TestRequest::put()
+            .uri("/kv/some-key")
+            .set_json(serde_json::json!({ "value": "too long", "ttl": 100 }))
+            .to_request();
+        let response = actix_test::call_service(&app, put_request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = actix_test::read_body_json(response).await;
+        assert_eq!(body["status"], "ERROR");
+        assert!(node.kv_store.lock().unwrap().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn html_put_stores_the_pair_with_the_given_ttl() {
+        let nodes = spawn_test_ring(1).await;
+        let app = actix_test::init_service(App::new()
+            .app_data(web::Data::new(nodes[0].finger_table.clone()))
+            .app_data(web::Data::new(test_config(&nodes[0].address)))
+            .app_data(web::Data::new(nodes[0].address.clone()))
+            .service(index))
+            .await;
+
+        let before_put = now_millis();
+        let put_request = actix_test::TestRequest::get()
+            .uri("/?put_request_key=some-key&put_request_value=some-value&put_request_ttl=60&put_request_replication=0")
+            .to_request();
+        let response = actix_test::call_service(&app, put_request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let after_put = now_millis();
+
+        let (value, expiration_date) = nodes[0].kv_store.lock().unwrap()
+            .get(&key_to_bytes("some-key"))
+            .cloned()
+            .unwrap();
+        assert_eq!(value, "some-value");
+        assert!(expiration_date >= before_put + 60_000 && expiration_date <= after_put + 60_000);
+    }
+
+    #[actix_web::test]
+    async fn html_put_with_invalid_ttl_renders_an_error() {
+        let nodes = spawn_test_ring(1).await;
+        let app = actix_test::init_service(App::new()
+            .app_data(web::Data::new(nodes[0].finger_table.clone()))
+            .app_data(web::Data::new(test_config(&nodes[0].address)))
+            .app_data(web::Data::new(nodes[0].address.clone()))
+            .service(index))
+            .await;
+
+        for query in ["put_request_ttl=soon&put_request_replication=0", "put_request_ttl=60&put_request_replication=-1"] {
+            let put_request = actix_test::TestRequest::get()
+                .uri(&format!("/?put_request_key=some-key&put_request_value=some-value&{}", query))
+                .to_request();
+            let response = actix_test::call_service(&app, put_request).await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            let body = String::from_utf8(actix_test::read_body(response).await.to_vec()).unwrap();
+            assert!(body.contains("Request failed: Invalid"), "{}", body);
+        }
+        assert!(nodes[0].kv_store.lock().unwrap().is_empty());
+    }
 }
@@ -1,20 +1,190 @@
 use std::str::FromStr;
 
-use clap::Parser;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use clap::{Parser, Subcommand};
 use ini::{Error, Ini};
 use log::LevelFilter;
 use serde::Serialize;
 
-use crate::utils::constants::POW_DIFFICULTY_DEFAULT;
+use crate::utils::constants::{ANNOUNCE_INTERVAL_MILLIS_DEFAULT, EXPIRATION_SWEEP_INTERVAL_MILLIS_DEFAULT, FIX_FINGERS_SLEEP_MILLIS, HANDOFF_BATCH_SIZE_DEFAULT, HANDOFF_DEADLINE_MILLIS_DEFAULT, HEALTH_SLEEP_MILLIS, MAX_CONCURRENT_HANDOFFS_DEFAULT, MAX_VALUE_BYTES_DEFAULT, PARTITION_CHECK_INTERVAL_MILLIS_DEFAULT, POW_DIFFICULTY_DEFAULT, PREDECESSOR_FAILURE_THRESHOLD_DEFAULT, REPLICATION_INTERVAL_MILLIS_DEFAULT, SNAPSHOT_INTERVAL_MILLIS_DEFAULT, STABILIZE_SLEEP_MILLIS, SUCCESSOR_GRACE_PERIOD_MILLIS_DEFAULT, SUCCESSOR_LIST_LENGTH_DEFAULT, SUCCESSOR_STABLE_PERIOD_MILLIS_DEFAULT};
+use crate::utils::proof_of_work::default_pow_threads;
 use crate::utils::types::Address;
 
+/// Order in which fix_fingers picks the finger to refresh next
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FingerFixStrategy {
+    /// fixes one finger after the other
+    #[default]
+    RoundRobin,
+    /// fixes a uniformly random finger
+    Random,
+    /// fixes a random finger, but lower fingers are chosen more often as they matter most for
+    /// correct routing
+    WeightedLow,
+}
+
+impl FromStr for FingerFixStrategy {
+    type Err = String;
+
+    fn from_str(strategy: &str) -> Result<Self, Self::Err> {
+        match strategy {
+            "round-robin" => Ok(FingerFixStrategy::RoundRobin),
+            "random" => Ok(FingerFixStrategy::Random),
+            "weighted-low" => Ok(FingerFixStrategy::WeightedLow),
+            _ => Err(format!("Unknown finger fix strategy {}, use round-robin, random or weighted-low", strategy))
+        }
+    }
+}
+
+/// How a node resolves keys it is not responsible for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LookupStrategy {
+    /// each node forwards the lookup to the next hop and waits for its answer
+    #[default]
+    Recursive,
+    /// the originating node queries the nodes on the path one after another itself
+    Iterative,
+}
+
+impl FromStr for LookupStrategy {
+    type Err = String;
+
+    fn from_str(strategy: &str) -> Result<Self, Self::Err> {
+        match strategy {
+            "recursive" => Ok(LookupStrategy::Recursive),
+            "iterative" => Ok(LookupStrategy::Iterative),
+            _ => Err(format!("Unknown lookup strategy {}, use recursive or iterative", strategy))
+        }
+    }
+}
+
+/// How keys are rendered in human readable debug output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyEncoding {
+    #[default]
+    Hex,
+    Base64,
+    /// space separated decimal values of the key bytes
+    DecimalBytes,
+}
+
+impl KeyEncoding {
+    pub fn encode(&self, key: &[u8]) -> String {
+        match self {
+            KeyEncoding::Hex => key.iter().map(|b| format!("{:02x}", b)).collect(),
+            KeyEncoding::Base64 => BASE64.encode(key),
+            KeyEncoding::DecimalBytes => key.iter().map(|b| b.to_string()).collect::<Vec<String>>().join(" "),
+        }
+    }
+}
+
+impl FromStr for KeyEncoding {
+    type Err = String;
+
+    fn from_str(encoding: &str) -> Result<Self, Self::Err> {
+        match encoding {
+            "hex" => Ok(KeyEncoding::Hex),
+            "base64" => Ok(KeyEncoding::Base64),
+            "decimal-bytes" => Ok(KeyEncoding::DecimalBytes),
+            _ => Err(format!("Unknown key encoding {}, use hex, base64 or decimal-bytes", encoding))
+        }
+    }
+}
+
 /// The config struct is initialized from a config file upon node start up
 /// Its fields is used in the main.rs and other locations in the code to configure the node
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, subcommand_negates_reqs = true)]
 pub struct Cli {
-    #[arg(short = 'c', long = "config")]
-    pub config_file: String,
+    #[arg(short = 'c', long = "config", required = true)]
+    pub config_file: Option<String>,
+    /// number of successors each node keeps track of, overrides successor_list_length of the config file
+    #[arg(long = "successor-list-length")]
+    pub successor_list_length: Option<usize>,
+    /// comma separated nodes to join and to rejoin after a partition, overrides seed_nodes of the config file
+    #[arg(long = "seed-nodes", value_delimiter = ',')]
+    pub seed_nodes: Option<Vec<Address>>,
+    /// largest value in bytes a put may store, overrides max_value_bytes of the config file
+    #[arg(long = "max-value-bytes")]
+    pub max_value_bytes: Option<usize>,
+    /// pause between two stabilize runs, overrides stabilize_interval_millis of the config file
+    #[arg(long = "stabilize-interval-ms")]
+    pub stabilize_interval_millis: Option<u64>,
+    /// pause between two fix_fingers runs, overrides fix_fingers_interval_millis of the config file
+    #[arg(long = "fix-fingers-interval-ms")]
+    pub fix_fingers_interval_millis: Option<u64>,
+    /// pause between two predecessor and successor health checks, overrides health_interval_millis
+    /// of the config file
+    #[arg(long = "health-interval-ms")]
+    pub health_interval_millis: Option<u64>,
+    /// answer gets for keys of other nodes by fetching them from the responsible node, same as
+    /// proxy_reads = true in the config file
+    #[arg(long = "proxy-reads")]
+    pub proxy_reads: bool,
+    /// store puts for keys of other nodes on the responsible node, same as proxy_writes = true in
+    /// the config file
+    #[arg(long = "proxy-writes")]
+    pub proxy_writes: bool,
+    /// salt of the ring positions, has to match the cluster, overrides cluster_salt of the config file
+    #[arg(long = "cluster-salt")]
+    pub cluster_salt: Option<String>,
+    /// certificate presented to other nodes, overrides tls_cert of the config file
+    #[arg(long = "tls-cert")]
+    pub tls_cert: Option<String>,
+    /// key of the certificate, overrides tls_key of the config file
+    #[arg(long = "tls-key")]
+    pub tls_key: Option<String>,
+    /// CA that signed the certificates of the cluster, overrides tls_ca of the config file
+    #[arg(long = "tls-ca")]
+    pub tls_ca: Option<String>,
+    /// instead of running a node, act as a client of a running node
+    #[command(subcommand)]
+    pub command: Option<ClientCommand>,
+}
+
+/// Operations of the client mode. Keys are mapped to DHT keys in the same way as in the web
+/// interface, so pairs stored with the client can be read there and vice versa.
+#[derive(Subcommand, Debug, Clone)]
+pub enum ClientCommand {
+    /// prints the status and the value stored under the key
+    Get {
+        /// gRPC address of any node of the ring
+        #[arg(long)]
+        node: Address,
+        #[arg(long)]
+        key: String,
+    },
+    /// stores the value under the key
+    Put {
+        /// gRPC address of any node of the ring
+        #[arg(long)]
+        node: Address,
+        #[arg(long)]
+        key: String,
+        #[arg(long)]
+        value: String,
+        /// time to live in seconds
+        #[arg(long, default_value_t = 3600)]
+        ttl: u64,
+        /// number of successors the pair is copied to
+        #[arg(long, default_value_t = 0)]
+        replication: u32,
+    },
+    /// removes the pair stored under the key
+    Delete {
+        /// gRPC address of any node of the ring
+        #[arg(long)]
+        node: Address,
+        #[arg(long)]
+        key: String,
+        /// number of successors the delete is forwarded to, should match the replication of the put
+        #[arg(long, default_value_t = 0)]
+        replication: u32,
+    },
 }
 
 
@@ -24,15 +194,106 @@ pub struct Config {
     pub p2p_address: Address,
     pub web_address: Address,
     pub join_address: Option<Address>,
+    pub seed_nodes: Vec<Address>,
+    pub partition_check_interval_millis: u64,
     pub pow_difficulty: usize,
+    pub pow_threads: usize,
     #[serde(skip_serializing)]
     pub log_level_filter: LevelFilter,
     pub dev_mode: bool,
+    pub handoff_deadline_millis: u64,
+    pub handoff_batch_size: usize,
+    pub max_concurrent_handoffs: usize,
+    #[serde(skip_serializing)]
+    pub cluster_salt: Option<String>,
+    pub max_ttl: Option<u64>,
+    pub max_value_bytes: usize,
+    pub successor_grace_period_millis: u64,
+    pub successor_stable_period_millis: u64,
+    pub successor_list_length: usize,
+    pub stabilize_interval_millis: u64,
+    pub fix_fingers_interval_millis: u64,
+    pub health_interval_millis: u64,
+    pub announce_interval_millis: u64,
+    pub reflection: bool,
+    pub finger_fix_strategy: FingerFixStrategy,
+    pub lookup_strategy: LookupStrategy,
+    pub audit_log: Option<String>,
+    pub profile: bool,
+    pub key_encoding: KeyEncoding,
+    pub serve_when_isolated: bool,
+    pub proxy_reads: bool,
+    pub proxy_writes: bool,
+    pub seed_all_fingers: bool,
+    pub dump_on_panic: Option<String>,
+    pub serve_stale: bool,
+    pub full_precision_positions: bool,
+    pub predecessor_failure_threshold: u32,
+    pub replication_interval_millis: u64,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub tls_ca: Option<String>,
+    pub metrics_address: Option<Address>,
+    pub data_dir: Option<String>,
+    pub snapshot_interval_millis: u64,
+    pub expiration_sweep_interval_millis: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            api_address: Address::default(),
+            p2p_address: Address::default(),
+            web_address: Address::default(),
+            join_address: None,
+            seed_nodes: Vec::new(),
+            partition_check_interval_millis: PARTITION_CHECK_INTERVAL_MILLIS_DEFAULT,
+            pow_difficulty: POW_DIFFICULTY_DEFAULT,
+            pow_threads: default_pow_threads(),
+            log_level_filter: LevelFilter::Info,
+            dev_mode: false,
+            handoff_deadline_millis: HANDOFF_DEADLINE_MILLIS_DEFAULT,
+            handoff_batch_size: HANDOFF_BATCH_SIZE_DEFAULT,
+            max_concurrent_handoffs: MAX_CONCURRENT_HANDOFFS_DEFAULT,
+            cluster_salt: None,
+            max_ttl: None,
+            max_value_bytes: MAX_VALUE_BYTES_DEFAULT,
+            successor_grace_period_millis: SUCCESSOR_GRACE_PERIOD_MILLIS_DEFAULT,
+            successor_stable_period_millis: SUCCESSOR_STABLE_PERIOD_MILLIS_DEFAULT,
+            successor_list_length: SUCCESSOR_LIST_LENGTH_DEFAULT,
+            stabilize_interval_millis: STABILIZE_SLEEP_MILLIS,
+            fix_fingers_interval_millis: FIX_FINGERS_SLEEP_MILLIS,
+            health_interval_millis: HEALTH_SLEEP_MILLIS,
+            announce_interval_millis: ANNOUNCE_INTERVAL_MILLIS_DEFAULT,
+            reflection: true,
+            finger_fix_strategy: FingerFixStrategy::default(),
+            lookup_strategy: LookupStrategy::default(),
+            audit_log: None,
+            profile: false,
+            key_encoding: KeyEncoding::default(),
+            serve_when_isolated: false,
+            proxy_reads: false,
+            proxy_writes: false,
+            seed_all_fingers: true,
+            dump_on_panic: None,
+            serve_stale: false,
+            full_precision_positions: false,
+            predecessor_failure_threshold: PREDECESSOR_FAILURE_THRESHOLD_DEFAULT,
+            replication_interval_millis: REPLICATION_INTERVAL_MILLIS_DEFAULT,
+            tls_cert: None,
+            tls_key: None,
+            tls_ca: None,
+            metrics_address: None,
+            data_dir: None,
+            snapshot_interval_millis: SNAPSHOT_INTERVAL_MILLIS_DEFAULT,
+            expiration_sweep_interval_millis: EXPIRATION_SWEEP_INTERVAL_MILLIS_DEFAULT,
+        }
+    }
 }
 
 impl Config {
-    pub fn load() -> Result<Config, Error> {
-        let conf = Ini::load_from_file(Cli::parse().config_file)?;
+    pub fn load(cli: Cli) -> Result<Config, Error> {
+        let conf = Ini::load_from_file(cli.config_file.as_ref().expect("Config file required"))?;
 
         let dht = conf.section(Some("dht"))
             .ok_or("'dht' section required")
@@ -56,15 +317,44 @@ impl Config {
             .unwrap()
             .to_string();
 
+        // metrics are served on the web interface unless an own address is configured
+        let metrics_address = dht
+            .get("metrics_address")
+            .map(|metrics_address| metrics_address.to_string());
+
         let join_address = dht
             .get("join_address")
             .map(|join_address_str| join_address_str.to_string());
 
+        // seed nodes are tried for the initial join if no join_address is set, and are asked
+        // periodically whether this node still belongs to their ring
+        let seed_nodes: Vec<Address> = cli.seed_nodes.clone()
+            .or_else(|| dht.get("seed_nodes")
+                .map(|seed_nodes| seed_nodes.split(',').map(|seed_node| seed_node.to_string()).collect()))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|seed_node| seed_node.trim().to_string())
+            .filter(|seed_node| !seed_node.is_empty())
+            .collect();
+
+        let partition_check_interval_millis = dht
+            .get("partition_check_interval_millis")
+            .map(|interval| interval.parse::<u64>().expect("Invalid partition check interval"))
+            .unwrap_or(PARTITION_CHECK_INTERVAL_MILLIS_DEFAULT);
+
         let pow_difficulty = dht
             .get("pow_difficulty")
             .map(|pow_difficulty| pow_difficulty.parse::<usize>().unwrap())
             .unwrap_or(POW_DIFFICULTY_DEFAULT);
 
+        let pow_threads = dht
+            .get("pow_threads")
+            .map(|pow_threads| pow_threads.parse::<usize>().expect("Invalid pow threads"))
+            .unwrap_or_else(default_pow_threads);
+        if pow_threads == 0 {
+            panic!("Pow threads must be at least 1");
+        }
+
         let log_level_filter = dht
             .get("log_level")
             .map(|log_level| LevelFilter::from_str(log_level))
@@ -77,6 +367,299 @@ impl Config {
             .map(|dev_mode| dev_mode.expect("Invalid dev mode argument, use true or false"))
             .unwrap_or(false);
 
-        Ok(Config { p2p_address, api_address, web_address, join_address, pow_difficulty, log_level_filter, dev_mode })
+        let handoff_deadline_millis = dht
+            .get("handoff_deadline_millis")
+            .map(|handoff_deadline_millis| handoff_deadline_millis.parse::<u64>().expect("Invalid handoff deadline"))
+            .unwrap_or(HANDOFF_DEADLINE_MILLIS_DEFAULT);
+
+        let handoff_batch_size = dht
+            .get("handoff_batch_size")
+            .map(|handoff_batch_size| handoff_batch_size.parse::<usize>().expect("Invalid handoff batch size"))
+            .unwrap_or(HANDOFF_BATCH_SIZE_DEFAULT);
+        if handoff_batch_size == 0 {
+            panic!("Handoff batch size must be at least 1");
+        }
+
+        let max_concurrent_handoffs = dht
+            .get("max_concurrent_handoffs")
+            .map(|max_concurrent_handoffs| max_concurrent_handoffs.parse::<usize>().expect("Invalid max concurrent handoffs"))
+            .unwrap_or(MAX_CONCURRENT_HANDOFFS_DEFAULT);
+        if max_concurrent_handoffs == 0 {
+            panic!("Max concurrent handoffs must be at least 1");
+        }
+
+        let cluster_salt = cli.cluster_salt.clone()
+            .or_else(|| dht.get("cluster_salt").map(|cluster_salt| cluster_salt.to_string()));
+
+        let max_ttl = dht
+            .get("max_ttl")
+            .map(|max_ttl| max_ttl.parse::<u64>().expect("Invalid max ttl"));
+
+        let successor_grace_period_millis = dht
+            .get("successor_grace_period_millis")
+            .map(|grace_period| grace_period.parse::<u64>().expect("Invalid successor grace period"))
+            .unwrap_or(SUCCESSOR_GRACE_PERIOD_MILLIS_DEFAULT);
+
+        let successor_stable_period_millis = dht
+            .get("successor_stable_period_millis")
+            .map(|stable_period| stable_period.parse::<u64>().expect("Invalid successor stable period"))
+            .unwrap_or(SUCCESSOR_STABLE_PERIOD_MILLIS_DEFAULT);
+
+        // values below 1 are clamped, a node always keeps at least its direct successor
+        let successor_list_length = cli.successor_list_length
+            .or_else(|| dht.get("successor_list_length")
+                .map(|length| length.parse::<usize>().expect("Invalid successor list length")))
+            .unwrap_or(SUCCESSOR_LIST_LENGTH_DEFAULT)
+            .max(1);
+
+        // low-churn deployments can slow the periodic maintenance down to cut chatter, an interval
+        // of 0 is clamped to 1 ms so the maintenance loops don't spin
+        let stabilize_interval_millis = cli.stabilize_interval_millis
+            .or_else(|| dht.get("stabilize_interval_millis")
+                .map(|interval| interval.parse::<u64>().expect("Invalid stabilize interval")))
+            .unwrap_or(STABILIZE_SLEEP_MILLIS)
+            .max(1);
+
+        let fix_fingers_interval_millis = cli.fix_fingers_interval_millis
+            .or_else(|| dht.get("fix_fingers_interval_millis")
+                .map(|interval| interval.parse::<u64>().expect("Invalid fix fingers interval")))
+            .unwrap_or(FIX_FINGERS_SLEEP_MILLIS)
+            .max(1);
+
+        let health_interval_millis = cli.health_interval_millis
+            .or_else(|| dht.get("health_interval_millis")
+                .map(|interval| interval.parse::<u64>().expect("Invalid health interval")))
+            .unwrap_or(HEALTH_SLEEP_MILLIS)
+            .max(1);
+
+        let max_value_bytes = cli.max_value_bytes
+            .or_else(|| dht.get("max_value_bytes")
+                .map(|max_bytes| max_bytes.parse::<usize>().expect("Invalid max value bytes")))
+            .unwrap_or(MAX_VALUE_BYTES_DEFAULT);
+
+        let announce_interval_millis = dht
+            .get("announce_interval_millis")
+            .map(|announce_interval| announce_interval.parse::<u64>().expect("Invalid announce interval"))
+            .unwrap_or(ANNOUNCE_INTERVAL_MILLIS_DEFAULT);
+
+        let reflection = dht
+            .get("reflection")
+            .map(bool::from_str)
+            .map(|reflection| reflection.expect("Invalid reflection argument, use true or false"))
+            .unwrap_or(true);
+
+        let finger_fix_strategy = dht
+            .get("finger_fix_strategy")
+            .map(|strategy| FingerFixStrategy::from_str(strategy).unwrap())
+            .unwrap_or_default();
+
+        let lookup_strategy = dht
+            .get("lookup_strategy")
+            .map(|strategy| LookupStrategy::from_str(strategy).unwrap())
+            .unwrap_or_default();
+
+        let audit_log = dht
+            .get("audit_log")
+            .map(|audit_log_path| audit_log_path.to_string());
+
+        let profile = dht
+            .get("profile")
+            .map(bool::from_str)
+            .map(|profile| profile.expect("Invalid profile argument, use true or false"))
+            .unwrap_or(false);
+
+        let key_encoding = dht
+            .get("key_encoding")
+            .map(|encoding| KeyEncoding::from_str(encoding).unwrap())
+            .unwrap_or_default();
+
+        let serve_when_isolated = dht
+            .get("serve_when_isolated")
+            .map(bool::from_str)
+            .map(|serve_when_isolated| serve_when_isolated.expect("Invalid serve_when_isolated argument, use true or false"))
+            .unwrap_or(false);
+
+        // without proxying, gets and puts for keys of other nodes fail with not responsible
+        let proxy_reads = cli.proxy_reads || dht
+            .get("proxy_reads")
+            .map(bool::from_str)
+            .map(|proxy_reads| proxy_reads.expect("Invalid proxy_reads argument, use true or false"))
+            .unwrap_or(false);
+
+        let proxy_writes = cli.proxy_writes || dht
+            .get("proxy_writes")
+            .map(bool::from_str)
+            .map(|proxy_writes| proxy_writes.expect("Invalid proxy_writes argument, use true or false"))
+            .unwrap_or(false);
+
+        let seed_all_fingers = dht
+            .get("seed_all_fingers")
+            .map(bool::from_str)
+            .map(|seed_all_fingers| seed_all_fingers.expect("Invalid seed_all_fingers argument, use true or false"))
+            .unwrap_or(true);
+
+        let dump_on_panic = dht
+            .get("dump_on_panic")
+            .map(|dump_path| dump_path.to_string());
+
+        let serve_stale = dht
+            .get("serve_stale")
+            .map(bool::from_str)
+            .map(|serve_stale| serve_stale.expect("Invalid serve_stale argument, use true or false"))
+            .unwrap_or(false);
+
+        let full_precision_positions = dht
+            .get("full_precision_positions")
+            .map(bool::from_str)
+            .map(|full_precision_positions| full_precision_positions.expect("Invalid full_precision_positions argument, use true or false"))
+            .unwrap_or(false);
+
+        let predecessor_failure_threshold = dht
+            .get("predecessor_failure_threshold")
+            .map(|threshold| threshold.parse::<u32>().expect("Invalid predecessor failure threshold"))
+            .unwrap_or(PREDECESSOR_FAILURE_THRESHOLD_DEFAULT);
+        if predecessor_failure_threshold == 0 {
+            panic!("Predecessor failure threshold must be at least 1");
+        }
+
+        let replication_interval_millis = dht
+            .get("replication_interval_millis")
+            .map(|interval| interval.parse::<u64>().expect("Invalid replication interval"))
+            .unwrap_or(REPLICATION_INTERVAL_MILLIS_DEFAULT);
+
+        // pairs are snapshotted to and reloaded from the data dir, nothing is persisted without it
+        let data_dir = dht
+            .get("data_dir")
+            .map(|data_dir| data_dir.to_string());
+
+        let snapshot_interval_millis = dht
+            .get("snapshot_interval_millis")
+            .map(|interval| interval.parse::<u64>().expect("Invalid snapshot interval"))
+            .unwrap_or(SNAPSHOT_INTERVAL_MILLIS_DEFAULT);
+
+        // expired pairs nobody reads again are removed by a background sweep, except with serve_stale
+        let expiration_sweep_interval_millis = dht
+            .get("expiration_sweep_interval_millis")
+            .map(|interval| interval.parse::<u64>().expect("Invalid expiration sweep interval"))
+            .unwrap_or(EXPIRATION_SWEEP_INTERVAL_MILLIS_DEFAULT);
+
+        let tls_cert = cli.tls_cert.clone().or_else(|| dht.get("tls_cert").map(|cert_path| cert_path.to_string()));
+        let tls_key = cli.tls_key.clone().or_else(|| dht.get("tls_key").map(|key_path| key_path.to_string()));
+        let tls_ca = cli.tls_ca.clone().or_else(|| dht.get("tls_ca").map(|ca_path| ca_path.to_string()));
+        if !(tls_cert.is_some() == tls_key.is_some() && tls_key.is_some() == tls_ca.is_some()) {
+            panic!("TLS requires tls_cert, tls_key and tls_ca to be set together");
+        }
+
+        Ok(Config { p2p_address, api_address, web_address, join_address, seed_nodes, partition_check_interval_millis, pow_difficulty, pow_threads, log_level_filter, dev_mode, handoff_deadline_millis, handoff_batch_size, max_concurrent_handoffs, cluster_salt, max_ttl, max_value_bytes,
+            successor_grace_period_millis, successor_stable_period_millis, successor_list_length, stabilize_interval_millis, fix_fingers_interval_millis, health_interval_millis, announce_interval_millis, reflection, finger_fix_strategy, lookup_strategy, audit_log, profile, key_encoding, serve_when_isolated, proxy_reads, proxy_writes, seed_all_fingers, dump_on_panic, serve_stale, full_precision_positions, predecessor_failure_threshold, replication_interval_millis,
+            tls_cert, tls_key, tls_ca, metrics_address, data_dir, snapshot_interval_millis, expiration_sweep_interval_millis })
+    }
+}
+
+/// Settings the client mode needs to reach a cluster: keys are hashed with the cluster's salt and
+/// nodes with TLS only accept clients with a certificate of the cluster CA. They are read from
+/// the config file, if one is given, and the flags override them.
+#[derive(Clone, Debug, Default)]
+pub struct ClientConfig {
+    pub cluster_salt: Option<String>,
+    pub tls_cert: Option<String>,
+    pub tls_key: Option<String>,
+    pub tls_ca: Option<String>,
+}
+
+impl ClientConfig {
+    pub fn load(cli: &Cli) -> Result<ClientConfig, Error> {
+        let conf = match &cli.config_file {
+            Some(config_file) => Ini::load_from_file(config_file)?,
+            None => Ini::new()
+        };
+        let dht_value = |name: &str| conf.section(Some("dht"))
+            .and_then(|dht| dht.get(name))
+            .map(|value| value.to_string());
+
+        let client_config = ClientConfig {
+            cluster_salt: cli.cluster_salt.clone().or_else(|| dht_value("cluster_salt")),
+            tls_cert: cli.tls_cert.clone().or_else(|| dht_value("tls_cert")),
+            tls_key: cli.tls_key.clone().or_else(|| dht_value("tls_key")),
+            tls_ca: cli.tls_ca.clone().or_else(|| dht_value("tls_ca")),
+        };
+        let tls = &client_config;
+        if !(tls.tls_cert.is_some() == tls.tls_key.is_some() && tls.tls_key.is_some() == tls.tls_ca.is_some()) {
+            panic!("TLS requires tls_cert, tls_key and tls_ca to be set together");
+        }
+        Ok(client_config)
+    }
+}
+
+/// Returns the value following the flag in the arguments of a command line tool in src/bin
+pub fn tool_flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a String> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .map(|i| args.get(i + 1).unwrap_or_else(|| panic!("Provide a value for {}", flag)))
+}
+
+/// Returns the arguments of a command line tool that are neither flags nor values of value_flags
+pub fn tool_positional_args<'a>(args: &'a [String], value_flags: &[&str]) -> Vec<&'a String> {
+    args.iter().enumerate()
+        .filter(|(i, arg)| !arg.starts_with("--") && (*i == 0 || !value_flags.contains(&args[i - 1].as_str())))
+        .map(|(_, arg)| arg)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn tool_args_separate_flag_values_from_positional_args() {
+        let args: Vec<String> = ["--json", "--cluster-salt", "salt", "http://127.0.0.1:5601", "http://127.0.0.1:5602"]
+            .iter().map(|arg| arg.to_string()).collect();
+        assert_eq!(tool_flag_value(&args, "--cluster-salt").unwrap(), "salt");
+        assert_eq!(tool_flag_value(&args, "--tls-ca"), None);
+        assert_eq!(tool_positional_args(&args, &["--cluster-salt"]), vec!["http://127.0.0.1:5601", "http://127.0.0.1:5602"]);
+    }
+
+    #[test]
+    fn interval_flags_override_the_config_file() {
+        let config_path = std::env::temp_dir().join(format!("chord-intervals-{}.ini", std::process::id()));
+        fs::write(&config_path, "[dht]\np2p_address = 127.0.0.1:5601\napi_address = 127.0.0.1:5501\n\
+            web_address = 127.0.0.1:5701\nstabilize_interval_millis = 2000\nhealth_interval_millis = 3000\n").unwrap();
+        let config_path = config_path.to_str().unwrap().to_string();
+
+        let cli = Cli::parse_from(["chord", "-c", &config_path, "--stabilize-interval-ms", "5000", "--fix-fingers-interval-ms", "250"]);
+        let config = Config::load(cli).unwrap();
+        assert_eq!(config.stabilize_interval_millis, 5000);
+        assert_eq!(config.fix_fingers_interval_millis, 250);
+        assert_eq!(config.health_interval_millis, 3000);
+
+        let config = Config::load(Cli::parse_from(["chord", "-c", &config_path])).unwrap();
+        assert_eq!(config.stabilize_interval_millis, 2000);
+        assert_eq!(config.fix_fingers_interval_millis, FIX_FINGERS_SLEEP_MILLIS);
+
+        let cli = Cli::parse_from(["chord", "-c", &config_path, "--stabilize-interval-ms", "0", "--health-interval-ms", "0"]);
+        let config = Config::load(cli).unwrap();
+        fs::remove_file(&config_path).unwrap();
+        assert_eq!(config.stabilize_interval_millis, 1);
+        assert_eq!(config.health_interval_millis, 1);
+    }
+
+    #[test]
+    fn client_config_reads_salt_and_tls_from_the_config_file_and_flags() {
+        let config_path = std::env::temp_dir().join(format!("chord-client-{}.ini", std::process::id()));
+        fs::write(&config_path, "[dht]\ncluster_salt = file-salt\ntls_cert = node.pem\ntls_key = node.key\ntls_ca = ca.pem\n").unwrap();
+        let config_path = config_path.to_str().unwrap().to_string();
+
+        let cli = Cli::parse_from(["chord", "-c", &config_path, "--cluster-salt", "flag-salt", "get", "--node", "127.0.0.1:5601", "--key", "key"]);
+        let client_config = ClientConfig::load(&cli).unwrap();
+        fs::remove_file(&config_path).unwrap();
+        assert_eq!(client_config.cluster_salt.as_deref(), Some("flag-salt"));
+        assert_eq!(client_config.tls_ca.as_deref(), Some("ca.pem"));
+
+        let cli = Cli::parse_from(["chord", "get", "--node", "127.0.0.1:5601", "--key", "key"]);
+        let client_config = ClientConfig::load(&cli).unwrap();
+        assert!(client_config.cluster_salt.is_none() && client_config.tls_cert.is_none());
     }
 }
@@ -1,9 +1,31 @@
 pub static FIX_FINGERS_SLEEP_MILLIS: u64 = 100;
 pub static STABILIZE_SLEEP_MILLIS: u64 = 1_000;
 pub static HEALTH_SLEEP_MILLIS: u64 = 1_000;
+pub static PREDECESSOR_FAILURE_THRESHOLD_DEFAULT: u32 = 3;
+pub static ANNOUNCE_INTERVAL_MILLIS_DEFAULT: u64 = 500;
+pub static REPLICATION_INTERVAL_MILLIS_DEFAULT: u64 = 5_000;
+pub static SNAPSHOT_INTERVAL_MILLIS_DEFAULT: u64 = 30_000;
+pub static EXPIRATION_SWEEP_INTERVAL_MILLIS_DEFAULT: u64 = 60_000;
+pub static EXPIRATION_SWEEP_BATCH_SIZE: usize = 1_000;
+pub static PARTITION_CHECK_INTERVAL_MILLIS_DEFAULT: u64 = 30_000;
+pub static DEAD_MARK_EXPIRY_MILLIS: u64 = 10_000;
 pub static POW_DIFFICULTY_DEFAULT: usize = 2;
 pub static POW_TOKEN_LIVE_TIME: u64 = 5;
-pub static POW_THREAD_NUM: usize = 32;
+pub static POW_DIFFICULTY_MAX: usize = 4;
+pub static NOTIFY_RATE_WINDOW_MILLIS: u64 = 10_000;
+pub static NOTIFY_RATE_THRESHOLD: usize = 50;
+pub static HANDOFF_DEADLINE_MILLIS_DEFAULT: u64 = 10_000;
+pub static GRPC_DRAIN_TIMEOUT_MILLIS: u64 = 10_000;
+pub static HANDOFF_BATCH_SIZE_DEFAULT: usize = 64;
+pub static MAX_CONCURRENT_HANDOFFS_DEFAULT: usize = 4;
+pub static SUCCESSOR_GRACE_PERIOD_MILLIS_DEFAULT: u64 = 3_000;
+pub static SUCCESSOR_STABLE_PERIOD_MILLIS_DEFAULT: u64 = 5_000;
+pub static SUCCESSOR_LIST_LENGTH_DEFAULT: usize = 3;
+pub static MAX_VALUE_BYTES_DEFAULT: usize = 1_048_576;
+pub static RING_CHANGE_WINDOW_MILLIS: u64 = 300_000;
+pub static RPC_TIMEOUT_MILLIS: u64 = 3_000;
+pub static LOOK_UP_MAX_RETRIES: usize = 3;
+pub static LOOK_UP_RETRY_SLEEP_MILLIS: u64 = 200;
 
 pub static DHT_PUT: u16 = 650;
 pub static DHT_GET: u16 = 651;
@@ -1,44 +1,93 @@
 use std::mem::size_of;
+use std::sync::OnceLock;
 
 use blake3::Hasher;
-use crate::utils::types::HashPos;
+use crate::utils::types::{HashPos, Key};
 
 
-pub trait HashRingKey {
-    fn size() -> usize;
-    fn one() -> HashPos;
-    fn two() -> HashPos;
-    fn finger_count() -> usize;
-}
+/// Width of the identifier space. Positions are stored as HashPos, but all ring arithmetic happens
+/// modulo 2^BITS, so smaller rings can be used to observe routing behavior with few nodes.
+pub trait RingParams {
+    const BITS: u32;
 
-impl HashRingKey for HashPos {
-    fn size() -> usize {
-        size_of::<HashPos>()
+    fn finger_count() -> usize {
+        Self::BITS as usize
+    }
+
+    /// largest position in the ring
+    fn max() -> HashPos {
+        HashPos::MAX >> (HashPos::BITS - Self::BITS)
     }
 
     fn one() -> HashPos {
-        HashPos::default() + 1
+        1
     }
 
     fn two() -> HashPos {
-        HashPos::default() + 2
+        2
     }
 
-    fn finger_count() -> usize {
-        HashPos::size() * 8
+    /// maps any value into the ring, e.g. a hash or the result of an addition
+    fn wrap(pos: HashPos) -> HashPos {
+        pos & Self::max()
     }
+
+    /// position of the input in this ring. is_between works on any ring as long as all positions
+    /// passed to it come from here or from wrap
+    fn hash(input: &[u8]) -> HashPos {
+        Self::wrap(hash_with_salt(input, hash_salt()))
+    }
+}
+
+/// the ring spanned by the full width of HashPos, used by all nodes
+pub struct DefaultRing;
+
+impl RingParams for DefaultRing {
+    const BITS: u32 = HashPos::BITS;
+}
+
+/// Optional cluster wide salt mixed into every hash, so that independent clusters map the same
+/// keys to different ring positions. It is set once at startup and has to be identical on all nodes
+static HASH_SALT: OnceLock<Vec<u8>> = OnceLock::new();
+
+pub fn set_hash_salt(salt: &[u8]) {
+    HASH_SALT.set(salt.to_vec()).expect("Hash salt can only be set once");
+}
+
+fn hash_salt() -> &'static [u8] {
+    HASH_SALT.get().map(|salt| salt.as_slice()).unwrap_or(&[])
+}
+
+/// Fingerprint of the configured salt, nodes compare it on join without revealing the salt itself
+pub fn hash_salt_fingerprint() -> HashPos {
+    hash_with_salt(&[], hash_salt())
 }
 
 /// Central hashing function used to map addresses to hash ring locations
 /// It is also used for POW token validation
 pub fn hash(input: &[u8]) -> HashPos {
+    DefaultRing::hash(input)
+}
+
+/// Maps a key of arbitrary length, e.g. a key typed into the web interface, to a 32 byte DHT key.
+/// The salt is not mixed in, the key is hashed again with the salt when it is placed on the ring
+pub fn hash_to_key(input: &[u8]) -> Key {
+    *blake3::hash(input).as_bytes()
+}
+
+pub fn hash_with_salt(input: &[u8], salt: &[u8]) -> HashPos {
     let mut hasher = Hasher::new();
+    hasher.update(salt);
     hasher.update(input);
     let hash = hasher.finalize();
     let bytes = *hash.as_bytes();
-    HashPos::from_le_bytes(bytes[0..HashPos::size()].try_into().unwrap())
+    HashPos::from_le_bytes(bytes[0..size_of::<HashPos>()].try_into().unwrap())
 }
 
+/// Checks whether pos lies on the arc from lower clockwise to upper, the flags exclude the
+/// respective bound. If lower > upper the arc wraps around position 0. If both are equal the arc
+/// has length zero, so only [lower, lower] contains a position. The whole ring is written as
+/// [x + 1, x], which is how ranges like (predecessor, self] are passed.
 pub fn is_between(pos: HashPos, lower: HashPos, upper: HashPos, left_open: bool, right_open: bool) -> bool {
     if lower < upper {
         if left_open && right_open {
@@ -61,7 +110,79 @@ pub fn is_between(pos: HashPos, lower: HashPos, upper: HashPos, left_open: bool,
             return lower <= pos || pos <= upper;
         }
     } else {
-        return !left_open && pos == lower;
+        // an excluded bound excludes the only position of a zero length arc
+        return !left_open && !right_open && pos == lower;
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (pos, lower, upper) cases paired with the expected results for [], [), (] and ()
+    fn assert_between_cases(cases: &[(HashPos, HashPos, HashPos, [bool; 4])]) {
+        for (pos, lower, upper, expected) in cases {
+            let flags = [(false, false), (false, true), (true, false), (true, true)];
+            for ((left_open, right_open), expected) in flags.iter().zip(expected) {
+                assert_eq!(is_between(*pos, *lower, *upper, *left_open, *right_open), *expected,
+                           "pos {} in {}{}, {}{}", pos, if *left_open { "(" } else { "[" }, lower, upper, if *right_open { ")" } else { "]" });
+            }
+        }
+    }
+
+    #[test]
+    fn is_between_without_wrap_around() {
+        assert_between_cases(&[
+            (10, 10, 20, [true, true, false, false]),
+            (15, 10, 20, [true, true, true, true]),
+            (20, 10, 20, [true, false, true, false]),
+            (9, 10, 20, [false, false, false, false]),
+            (21, 10, 20, [false, false, false, false]),
+            (0, 0, HashPos::MAX, [true, true, false, false]),
+            (HashPos::MAX, 0, HashPos::MAX, [true, false, true, false]),
+        ]);
+    }
+
+    #[test]
+    fn is_between_wrapping_around_zero() {
+        let lower = HashPos::MAX - 10;
+        assert_between_cases(&[
+            (lower, lower, 10, [true, true, false, false]),
+            (HashPos::MAX, lower, 10, [true, true, true, true]),
+            (0, lower, 10, [true, true, true, true]),
+            (10, lower, 10, [true, false, true, false]),
+            (11, lower, 10, [false, false, false, false]),
+            (lower - 1, lower, 10, [false, false, false, false]),
+            (HashPos::MAX / 2, lower, 10, [false, false, false, false]),
+        ]);
+    }
+
+    #[test]
+    fn is_between_with_equal_bounds() {
+        assert_between_cases(&[
+            (7, 7, 7, [true, false, false, false]),
+            (8, 7, 7, [false, false, false, false]),
+            (0, 0, 0, [true, false, false, false]),
+            (HashPos::MAX, 0, 0, [false, false, false, false]),
+        ]);
+    }
+
+    #[test]
+    fn whole_ring_is_written_as_wrapping_closed_range() {
+        // (own, own] of a node that is its own predecessor
+        for own in [0, 1, HashPos::MAX / 2, HashPos::MAX] {
+            for pos in [0, 1, own, own.wrapping_add(1), HashPos::MAX / 3, HashPos::MAX] {
+                assert!(is_between(pos, own.wrapping_add(1), own, false, false), "pos {} own {}", pos, own);
+            }
+        }
+    }
+
+    #[test]
+    fn salt_changes_hash() {
+        let key = b"same key in both clusters";
+        assert_eq!(hash_with_salt(key, b""), hash_with_salt(key, b""));
+        assert_ne!(hash_with_salt(key, b"cluster a"), hash_with_salt(key, b"cluster b"));
+        assert_ne!(hash_with_salt(key, b"cluster a"), hash_with_salt(key, b""));
+    }
+}
@@ -0,0 +1,90 @@
+use std::error::Error;
+use std::{fmt, io};
+
+use tonic::Status;
+
+/// Errors of a node's operations. RPC handlers convert them into a Status with a matching code,
+/// so malformed messages of a peer are rejected instead of panicking the task that serves them.
+#[derive(Debug)]
+pub enum ChordError {
+    /// a key or ring position received from a peer has the wrong number of bytes
+    InvalidLength { field: &'static str, expected: usize, actual: usize },
+    /// a peer could not be connected to
+    Transport(tonic::transport::Error),
+    /// a call to a peer failed
+    Rpc(Status),
+    /// a local file the node depends on, e.g. the audit log, could not be opened or written
+    Io(io::Error),
+}
+
+impl fmt::Display for ChordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChordError::InvalidLength { field, expected, actual } =>
+                write!(f, "{} must be {} bytes long, got {}", field, expected, actual),
+            ChordError::Transport(err) => write!(f, "Transport error: {}", err),
+            ChordError::Rpc(status) => write!(f, "Call failed: {}", status.message()),
+            ChordError::Io(err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl Error for ChordError {}
+
+impl From<Status> for ChordError {
+    fn from(status: Status) -> Self {
+        ChordError::Rpc(status)
+    }
+}
+
+impl From<tonic::transport::Error> for ChordError {
+    fn from(err: tonic::transport::Error) -> Self {
+        ChordError::Transport(err)
+    }
+}
+
+impl From<io::Error> for ChordError {
+    fn from(err: io::Error) -> Self {
+        ChordError::Io(err)
+    }
+}
+
+impl From<ChordError> for Status {
+    fn from(err: ChordError) -> Self {
+        match err {
+            ChordError::InvalidLength { .. } => Status::invalid_argument(err.to_string()),
+            ChordError::Transport(_) => Status::unavailable(err.to_string()),
+            // the status of a failed call is passed on unchanged
+            ChordError::Rpc(status) => status,
+            ChordError::Io(_) => Status::internal(err.to_string()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    #[test]
+    fn invalid_length_is_an_invalid_argument() {
+        let status: Status = ChordError::InvalidLength { field: "Key", expected: 32, actual: 3 }.into();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(status.message(), "Key must be 32 bytes long, got 3");
+    }
+
+    #[test]
+    fn failed_calls_keep_their_status() {
+        let status: Status = ChordError::from(Status::not_found("missing")).into();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "missing");
+    }
+
+    #[test]
+    fn local_io_errors_are_internal() {
+        let status: Status = ChordError::from(io::Error::new(io::ErrorKind::PermissionDenied, "audit.log")).into();
+        assert_eq!(status.code(), Code::Internal);
+    }
+}
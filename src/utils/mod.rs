@@ -4,3 +4,7 @@ pub mod types;
 pub mod config;
 pub mod proof_of_work;
 pub mod time;
+pub mod sync;
+pub mod error;
+pub mod validation;
+pub mod tls;
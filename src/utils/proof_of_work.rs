@@ -2,56 +2,63 @@ use std::fmt;
 use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use log::debug;
-use crate::utils::constants::{POW_THREAD_NUM, POW_TOKEN_LIVE_TIME};
+use crate::utils::constants::POW_TOKEN_LIVE_TIME;
 use crate::utils::time::{has_expired, now};
 use crate::utils::crypto::hash;
 
 extern crate rayon;
 
+/// The address of the node presenting the token is part of the hashed preimage, so a sniffed token
+/// can't be replayed by another node. The address isn't part of the token itself, the validating
+/// node takes it from the request.
 #[derive(Default, Clone)]
 pub struct PowToken {
     pub timestamp: u64,
     pub nonce: u64,
-    pub(crate) pow_difficulty: usize
+    pub pow_difficulty: usize
 }
 
 impl fmt::Display for PowToken {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let bytes = hash(self.serialize().as_slice()).to_be_bytes();
-        write!(f, "PowToken {{ timestamp: {}, nonce: {}, {:?} }}", self.timestamp, self.nonce, bytes)
+        write!(f, "PowToken {{ timestamp: {}, nonce: {}, pow_difficulty: {} }}", self.timestamp, self.nonce, self.pow_difficulty)
     }
 }
 
 
 impl PowToken {
 
-    fn serialize(&self, ) -> Vec<u8> {
+    fn serialize(&self, address: &str) -> Vec<u8> {
         let mut bytes = self.timestamp.to_be_bytes().to_vec();
         bytes.extend_from_slice(&self.nonce.to_be_bytes());
+        bytes.extend_from_slice(address.as_bytes());
         bytes
     }
 
-    fn check_trailing_zeros(&self) -> bool {
-        hash(self.serialize().as_slice()).to_be_bytes().iter().take(self.pow_difficulty).all(|&x| x == 0)
+    fn check_trailing_zeros(&self, address: &str, pow_difficulty: usize) -> bool {
+        hash(self.serialize(address).as_slice()).to_be_bytes().iter().take(pow_difficulty).all(|&x| x == 0)
     }
 
     fn has_expired(&self, ) -> bool {
-        let expiration_time = self.timestamp + POW_TOKEN_LIVE_TIME;
-        has_expired(&expiration_time)
+        let expiration_date = (self.timestamp + POW_TOKEN_LIVE_TIME) * 1000;
+        has_expired(&expiration_date)
     }
 
-    pub fn validate(&self, ) -> (bool, bool) {
-        (self.has_expired(), self.check_trailing_zeros())
+    /// Checks the token of the node at address against the difficulty the validating node currently
+    /// requires, not the difficulty the token was generated with. Returns (has_expired, valid).
+    pub fn validate(&self, address: &str, required_pow_difficulty: usize) -> (bool, bool) {
+        (self.has_expired(), self.check_trailing_zeros(address, required_pow_difficulty))
     }
 
-    pub fn generate(pow_difficulty: usize) -> Self {
+    /// Searches a token of the given difficulty for the node at address with pow_threads parallel
+    /// workers, each of them trying every pow_threads-th nonce.
+    pub fn generate(address: &str, pow_difficulty: usize, pow_threads: usize) -> Self {
         let timestamp = now().as_secs();
         let token = Arc::new(Mutex::new(PowToken { timestamp, nonce: 0, pow_difficulty }));
         let found = Arc::new(AtomicBool::new(false));
 
         let start = now().as_millis();
         rayon::scope(|s| {
-            for i in 0..POW_THREAD_NUM {
+            for i in 0..pow_threads {
                 let token_clone = Arc::clone(&token);
                 let found_clone = Arc::clone(&found);
 
@@ -59,13 +66,13 @@ impl PowToken {
                     let mut local_token = PowToken { timestamp, nonce: i as u64, pow_difficulty };
 
                     while !found_clone.load(Ordering::Relaxed) {
-                        if local_token.check_trailing_zeros() {
+                        if local_token.check_trailing_zeros(address, pow_difficulty) {
                             let mut shared_token = token_clone.lock().unwrap();
                             *shared_token = local_token.clone();
                             found_clone.store(true, Ordering::Relaxed);
                             break;
                         }
-                        local_token.nonce += POW_THREAD_NUM as u64;
+                        local_token.nonce += pow_threads as u64;
 
                         if local_token.has_expired() {
                             break;
@@ -83,14 +90,48 @@ impl PowToken {
 }
 
 
+/// one POW worker per available core
+pub fn default_pow_threads() -> usize {
+    std::thread::available_parallelism()
+        .map(|parallelism| parallelism.get())
+        .unwrap_or(1)
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const ADDRESS: &str = "127.0.0.1:5601";
+
     #[test]
     fn test() {
-        let token = PowToken::generate(2);
+        let token = PowToken::generate(ADDRESS, 2, default_pow_threads());
         println!("{}", token);
     }
+
+    #[test]
+    fn generation_succeeds_with_one_and_with_many_threads() {
+        for pow_threads in [1, 64] {
+            let token = PowToken::generate(ADDRESS, 1, pow_threads);
+            assert_eq!(token.validate(ADDRESS, 1), (false, true), "generated with {} threads", pow_threads);
+        }
+    }
+
+    #[test]
+    fn token_is_invalid_if_the_required_difficulty_is_higher() {
+        let token = (0..).map(|nonce| PowToken { timestamp: now().as_secs(), nonce, pow_difficulty: 0 })
+            .find(|token| !token.check_trailing_zeros(ADDRESS, 1))
+            .unwrap();
+        assert_eq!(token.validate(ADDRESS, 0), (false, true));
+        assert_eq!(token.validate(ADDRESS, 1), (false, false));
+    }
+
+    #[test]
+    fn token_is_invalid_for_another_address() {
+        let token = PowToken::generate(ADDRESS, 2, default_pow_threads());
+        assert_eq!(token.validate(ADDRESS, 2), (false, true));
+        assert_eq!(token.validate("127.0.0.1:5602", 2), (false, false));
+    }
 }
 
@@ -0,0 +1,44 @@
+use std::sync::{Mutex, MutexGuard};
+
+use log::warn;
+
+/// Locking that survives poisoning. A panic while holding one of the node's shared data
+/// structures would otherwise make every later lock().unwrap() panic as well, so that a single
+/// failed handler bricks the node for good.
+pub trait LockOrRecover<T> {
+    /// locks the mutex, recovering the guard if a thread panicked while holding it
+    fn lock_or_recover(&self) -> MutexGuard<'_, T>;
+}
+
+impl<T> LockOrRecover<T> for Mutex<T> {
+    fn lock_or_recover(&self) -> MutexGuard<'_, T> {
+        self.lock().unwrap_or_else(|poison_error| {
+            warn!("Recovered a lock poisoned by a panicking thread, the protected data may be partially updated");
+            self.clear_poison();
+            poison_error.into_inner()
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn poisoned_lock_is_recovered() {
+        let mutex = Arc::new(Mutex::new(1));
+        let mutex_clone = mutex.clone();
+        let _ = std::thread::spawn(move || {
+            let mut guard = mutex_clone.lock().unwrap();
+            *guard = 2;
+            panic!("handler panics while holding the lock");
+        }).join();
+        assert!(mutex.is_poisoned());
+
+        assert_eq!(*mutex.lock_or_recover(), 2);
+        assert!(!mutex.is_poisoned());
+    }
+}
@@ -1,10 +1,64 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use log::warn;
+
+/// Expiration dates are absolute wall clock timestamps as they are exchanged between nodes during
+/// handoffs. To keep TTL math sane if the system clock is stepped backwards, now() never returns a
+/// value smaller than the latest one it returned before: after a backward step time stands still
+/// until the wall clock has caught up again. This way keys written during the step don't get
+/// expiration dates in the past of the corrected clock, which would expire them prematurely.
+static LATEST_NOW_MILLIS: AtomicU64 = AtomicU64::new(0);
+static IN_BACKWARD_STEP: AtomicBool = AtomicBool::new(false);
+
 pub fn now() -> Duration {
-    SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+    let wall_clock = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    never_backwards(wall_clock, &LATEST_NOW_MILLIS, &IN_BACKWARD_STEP)
+}
+
+fn never_backwards(wall_clock: Duration, latest_millis: &AtomicU64, in_backward_step: &AtomicBool) -> Duration {
+    let wall_clock_millis = wall_clock.as_millis() as u64;
+    let previous_millis = latest_millis.fetch_max(wall_clock_millis, Ordering::SeqCst);
+    if wall_clock_millis >= previous_millis {
+        in_backward_step.store(false, Ordering::Relaxed);
+        return wall_clock;
+    }
+    if !in_backward_step.swap(true, Ordering::Relaxed) {
+        warn!("System clock went backwards by {} ms, holding time until it catches up", previous_millis - wall_clock_millis);
+    }
+    Duration::from_millis(previous_millis)
+}
+
+/// milliseconds since the unix epoch, the precision expiration dates are stored with
+pub fn now_millis() -> u64 {
+    now().as_millis() as u64
 }
 
 pub fn has_expired(expiration_date: &u64) -> bool {
-    now().as_secs() > expiration_date.clone()
+    now_millis() > *expiration_date
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backward_step_does_not_expire_keys_prematurely() {
+        let latest_millis = AtomicU64::new(0);
+        let in_backward_step = AtomicBool::new(false);
+        let ttl = 60;
+
+        assert_eq!(never_backwards(Duration::from_secs(1_000), &latest_millis, &in_backward_step).as_secs(), 1_000);
+
+        // the clock is stepped back by 100 seconds, a key is written while the clock is wrong
+        let put_time = never_backwards(Duration::from_secs(901), &latest_millis, &in_backward_step);
+        assert_eq!(put_time.as_secs(), 1_000);
+        let expiration_date = put_time.as_secs() + ttl;
+
+        // after the clock is corrected the key must still be alive for the rest of its ttl
+        let corrected = never_backwards(Duration::from_secs(1_002), &latest_millis, &in_backward_step);
+        assert_eq!(corrected.as_secs(), 1_002);
+        assert!(corrected.as_secs() <= expiration_date);
+    }
+}
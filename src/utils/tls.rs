@@ -0,0 +1,32 @@
+use std::fs;
+use std::io;
+
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity, ServerTlsConfig};
+
+use crate::utils::types::Address;
+
+/// Loads the node's certificate and key and the cluster CA from PEM files. Nodes authenticate
+/// each other mutually: the same identity is presented as server and as client, and peers are
+/// only accepted if their certificate was signed by the CA.
+pub fn load_tls_configs(cert_path: &str, key_path: &str, ca_path: &str) -> io::Result<(ServerTlsConfig, ClientTlsConfig)> {
+    let identity = Identity::from_pem(fs::read(cert_path)?, fs::read(key_path)?);
+    let ca = Certificate::from_pem(fs::read(ca_path)?);
+    let server_tls_config = ServerTlsConfig::new()
+        .identity(identity.clone())
+        .client_ca_root(ca.clone());
+    let client_tls_config = ClientTlsConfig::new()
+        .identity(identity)
+        .ca_certificate(ca);
+    Ok((server_tls_config, client_tls_config))
+}
+
+/// Opens a channel to the node at the address, over https if a TLS config is given and over plain
+/// http otherwise. The node and the command line tools connect through here.
+pub async fn connect_channel(address: &Address, tls_config_option: Option<&ClientTlsConfig>) -> Result<Channel, tonic::transport::Error> {
+    let endpoint = match tls_config_option {
+        Some(tls_config) => Endpoint::from_shared(format!("https://{}", address))?
+            .tls_config(tls_config.clone())?,
+        None => Endpoint::from_shared(format!("http://{}", address))?
+    };
+    endpoint.connect().await
+}
@@ -1,13 +1,22 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 // todo: change to u128
 pub type HashPos = u64;
 
+/// milliseconds since the unix epoch
 pub type ExpirationDate = u64;
 
 pub type Key = [u8; 32];
 pub type Value = String;
 
 pub type Address = String;
-pub type KvStore = HashMap<Key, (Value, u64)>;
+
+/// The empty address marks finger entries and handles that don't point to a node yet. Always check
+/// for it with this function instead of comparing against Address::default() directly.
+pub fn is_uninitialized(address: &Address) -> bool {
+    address.is_empty()
+}
+
+/// ordered by key, so the store can be walked in bounded chunks with a key as cursor
+pub type KvStore = BTreeMap<Key, (Value, u64)>;
 
@@ -0,0 +1,326 @@
+//! Checks of the ring invariants on the summaries of all nodes of a cluster: every node's
+//! predecessor is the node right in front of it, fingers point to the node responsible for their
+//! key and successor lists hold the next nodes of the ring.
+
+use serde::Serialize;
+
+use crate::chord_proto::{AddressMsg, HashPosMsg, NodeSummaryMsg};
+use crate::utils::crypto;
+use crate::utils::types::{Address, HashPos};
+
+/// whether a node's predecessor is the node right in front of it in the ring
+#[derive(Serialize, Debug)]
+pub struct PredecessorCheck {
+    pub node: Address,
+    pub predecessor: Option<Address>,
+    pub expected_predecessor: Address,
+    pub correct: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct WrongFinger {
+    pub node: Address,
+    pub index: usize,
+    pub finger_key: HashPos,
+    pub expected: Address,
+    pub actual: Address,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SuccessorListMismatch {
+    pub node: Address,
+    pub index: usize,
+    pub expected: Address,
+    pub actual: Option<Address>,
+}
+
+/// a summary field that couldn't be decoded, the rest of the summary is still checked if possible
+#[derive(Serialize, Debug)]
+pub struct MalformedSummary {
+    pub node: Address,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub malformed_summaries: Vec<MalformedSummary>,
+    pub predecessors: Vec<PredecessorCheck>,
+    pub wrong_fingers: Vec<WrongFinger>,
+    pub successor_list_mismatches: Vec<SuccessorListMismatch>,
+}
+
+/// checks the summaries against the ring they form, in whatever order they are given. Summaries
+/// without a valid position are reported and left out of the ring, no summaries at all are invalid.
+pub fn validate(node_summaries: &[NodeSummaryMsg]) -> ValidationReport {
+    let mut malformed_summaries = Vec::new();
+    let mut positioned_summaries: Vec<(HashPos, NodeSummaryMsg)> = Vec::new();
+    for node_summary in node_summaries {
+        match node_summary.pos.as_ref().map(into_hash_pos) {
+            Some(Ok(pos)) => positioned_summaries.push((pos, node_summary.clone())),
+            Some(Err(reason)) => malformed_summaries.push(MalformedSummary { node: node_summary.url.clone(), reason }),
+            None => malformed_summaries.push(MalformedSummary {
+                node: node_summary.url.clone(),
+                reason: "Summary has no position".to_string(),
+            }),
+        }
+    }
+    positioned_summaries.sort_by_key(|(pos, _)| *pos);
+
+    let (node_ids, node_summaries): (Vec<HashPos>, Vec<NodeSummaryMsg>) = positioned_summaries.into_iter().unzip();
+
+    // check predecessors
+    let mut predecessors = Vec::new();
+    for i in 0..node_summaries.len() {
+        let expected_predecessor: &String = &node_summaries[i].url;
+        let next_node = &node_summaries[(i + 1) % node_summaries.len()];
+        let predecessor = next_node.predecessor.as_ref().map(|predecessor| predecessor.address.clone());
+        predecessors.push(PredecessorCheck {
+            node: next_node.url.clone(),
+            correct: predecessor.as_ref() == Some(expected_predecessor),
+            predecessor,
+            expected_predecessor: expected_predecessor.clone(),
+        });
+    }
+
+    // validate finger entries
+    let mut wrong_fingers = Vec::new();
+    for node_summary in node_summaries.iter() {
+        for (j, finger) in node_summary.finger_entries.iter().enumerate() {
+            let finger_key: HashPos = match finger.id.parse::<HashPos>() {
+                Ok(finger_key) => finger_key,
+                Err(_) => {
+                    malformed_summaries.push(MalformedSummary {
+                        node: node_summary.url.clone(),
+                        reason: format!("{}-th finger has an invalid id: {:?}", j, finger.id),
+                    });
+                    continue;
+                }
+            };
+            let node_key_pointed_to = crypto::hash(finger.address.as_bytes());
+            let actually_responsible_node_index = get_responsible_node_index_for_key(finger_key, &node_ids);
+            if node_key_pointed_to.ne(&node_ids[actually_responsible_node_index]) {
+                wrong_fingers.push(WrongFinger {
+                    node: node_summary.url.clone(),
+                    index: j,
+                    finger_key,
+                    expected: node_summaries[actually_responsible_node_index].url.clone(),
+                    actual: finger.address.clone(),
+                });
+            }
+        }
+    }
+
+    // validate successor lists
+    let mut successor_list_mismatches = Vec::new();
+    for (i, node_summary) in node_summaries.iter().enumerate() {
+        let successors: &[AddressMsg] = node_summary.successor_list.as_ref()
+            .map(|successor_list| successor_list.successors.as_slice())
+            .unwrap_or(&[]);
+        if successors.is_empty() {
+            successor_list_mismatches.push(SuccessorListMismatch {
+                node: node_summary.url.clone(),
+                index: 0,
+                expected: node_summaries[(i + 1) % node_summaries.len()].url.clone(),
+                actual: None,
+            });
+        }
+        for (j, successor_according_to_list) in successors.iter().enumerate() {
+            let actual_successor_address = &node_summaries[(i + j + 1) % node_summaries.len()].url;
+            if successor_according_to_list.address.ne(actual_successor_address) {
+                successor_list_mismatches.push(SuccessorListMismatch {
+                    node: node_summary.url.clone(),
+                    index: j,
+                    expected: actual_successor_address.clone(),
+                    actual: Some(successor_according_to_list.address.clone()),
+                });
+            }
+        }
+    }
+
+    ValidationReport {
+        valid: !node_summaries.is_empty()
+            && malformed_summaries.is_empty()
+            && predecessors.iter().all(|check| check.correct)
+            && wrong_fingers.is_empty()
+            && successor_list_mismatches.is_empty(),
+        malformed_summaries,
+        predecessors,
+        wrong_fingers,
+        successor_list_mismatches,
+    }
+}
+
+/// index of the first node at or after the key, the ids are sorted and not empty
+fn get_responsible_node_index_for_key(key: HashPos, sorted_node_ids: &[HashPos]) -> usize {
+    sorted_node_ids.iter()
+        .position(|&node| key <= node)
+        .unwrap_or(0)
+}
+
+fn into_hash_pos(pos_msg: &HashPosMsg) -> Result<HashPos, String> {
+    let bytes: [u8; std::mem::size_of::<HashPos>()] = pos_msg.key.as_slice().try_into()
+        .map_err(|_| format!("Position has {} bytes instead of {}", pos_msg.key.len(), std::mem::size_of::<HashPos>()))?;
+    Ok(HashPos::from_be_bytes(bytes))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::chord_proto::{FingerEntryDebugMsg, SuccessorListMsg};
+
+    use super::*;
+
+    /// summaries of a ring of the given nodes in which every node points to the next one
+    fn ring_summaries(urls: &[&str]) -> Vec<NodeSummaryMsg> {
+        let mut urls: Vec<String> = urls.iter().map(|url| url.to_string()).collect();
+        urls.sort_by_key(|url| crypto::hash(url.as_bytes()));
+        let n = urls.len();
+        (0..n).map(|i| {
+            let pos = crypto::hash(urls[i].as_bytes());
+            NodeSummaryMsg {
+                url: urls[i].clone(),
+                pos: Some(HashPosMsg { key: pos.to_be_bytes().to_vec() }),
+                predecessor: Some(FingerEntryDebugMsg { id: String::new(), address: urls[(i + n - 1) % n].clone() }),
+                finger_entries: vec![FingerEntryDebugMsg {
+                    id: pos.wrapping_add(1).to_string(),
+                    address: urls[(i + 1) % n].clone(),
+                }],
+                successor_list: Some(SuccessorListMsg {
+                    own_address: Some(AddressMsg { address: urls[i].clone() }),
+                    successors: vec![AddressMsg { address: urls[(i + 1) % n].clone() }],
+                }),
+                link_rtts: vec![],
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn consistent_ring_is_valid() {
+        let report = validate(&ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603", "127.0.0.1:5604"]));
+        assert!(report.valid);
+        assert!(report.predecessors.iter().all(|check| check.correct));
+        assert!(report.wrong_fingers.is_empty());
+        assert!(report.successor_list_mismatches.is_empty());
+    }
+
+    #[test]
+    fn order_of_the_summaries_does_not_matter() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603"]);
+        node_summaries.reverse();
+        assert!(validate(&node_summaries).valid);
+    }
+
+    #[test]
+    fn single_node_ring_is_valid() {
+        assert!(validate(&ring_summaries(&["127.0.0.1:5601"])).valid);
+    }
+
+    #[test]
+    fn wrong_predecessor_is_reported() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603"]);
+        let third = node_summaries[2].url.clone();
+        node_summaries[1].predecessor.as_mut().unwrap().address = third.clone();
+
+        let report = validate(&node_summaries);
+        assert!(!report.valid);
+        let wrong: Vec<&PredecessorCheck> = report.predecessors.iter().filter(|check| !check.correct).collect();
+        assert_eq!(wrong.len(), 1);
+        assert_eq!(wrong[0].node, node_summaries[1].url);
+        assert_eq!(wrong[0].predecessor, Some(third));
+        assert_eq!(wrong[0].expected_predecessor, node_summaries[0].url);
+    }
+
+    #[test]
+    fn finger_pointing_to_the_wrong_node_is_reported() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603"]);
+        node_summaries[0].finger_entries[0].address = node_summaries[0].url.clone();
+
+        let report = validate(&node_summaries);
+        assert!(!report.valid);
+        assert_eq!(report.wrong_fingers.len(), 1);
+        assert_eq!(report.wrong_fingers[0].node, node_summaries[0].url);
+        assert_eq!(report.wrong_fingers[0].expected, node_summaries[1].url);
+        assert_eq!(report.wrong_fingers[0].actual, node_summaries[0].url);
+    }
+
+    #[test]
+    fn successor_list_skipping_a_node_is_reported() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603"]);
+        let third = node_summaries[2].url.clone();
+        node_summaries[0].successor_list.as_mut().unwrap().successors = vec![AddressMsg { address: third.clone() }];
+
+        let report = validate(&node_summaries);
+        assert!(!report.valid);
+        assert_eq!(report.successor_list_mismatches.len(), 1);
+        assert_eq!(report.successor_list_mismatches[0].index, 0);
+        assert_eq!(report.successor_list_mismatches[0].expected, node_summaries[1].url);
+        assert_eq!(report.successor_list_mismatches[0].actual, Some(third));
+    }
+
+    #[test]
+    fn missing_successor_list_is_reported() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602"]);
+        node_summaries[1].successor_list = None;
+
+        let report = validate(&node_summaries);
+        assert!(!report.valid);
+        assert_eq!(report.successor_list_mismatches[0].node, node_summaries[1].url);
+        assert_eq!(report.successor_list_mismatches[0].actual, None);
+    }
+
+    #[test]
+    fn no_summaries_are_invalid() {
+        assert!(!validate(&[]).valid);
+    }
+
+    #[test]
+    fn malformed_position_is_reported_instead_of_panicking() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602"]);
+        node_summaries[0].pos = Some(HashPosMsg { key: vec![1, 2, 3] });
+        node_summaries[1].pos = None;
+
+        let report = validate(&node_summaries);
+        assert!(!report.valid);
+        assert_eq!(report.malformed_summaries.len(), 2);
+        assert_eq!(report.malformed_summaries[0].node, node_summaries[0].url);
+        assert_eq!(report.malformed_summaries[1].node, node_summaries[1].url);
+    }
+
+    #[test]
+    fn malformed_finger_id_is_reported_instead_of_panicking() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603"]);
+        node_summaries[0].finger_entries[0].id = "not a position".to_string();
+
+        let report = validate(&node_summaries);
+        assert!(!report.valid);
+        assert!(report.wrong_fingers.is_empty());
+        assert_eq!(report.malformed_summaries.len(), 1);
+        assert_eq!(report.malformed_summaries[0].node, node_summaries[0].url);
+    }
+
+    #[test]
+    fn json_report_lists_each_kind_of_error() {
+        let mut node_summaries = ring_summaries(&["127.0.0.1:5601", "127.0.0.1:5602", "127.0.0.1:5603"]);
+        let (first, second, third) = (node_summaries[0].url.clone(), node_summaries[1].url.clone(), node_summaries[2].url.clone());
+        node_summaries[1].predecessor = None;
+        node_summaries[1].finger_entries[0].address = first.clone();
+        node_summaries[2].successor_list.as_mut().unwrap().successors[0].address = second.clone();
+
+        let report = serde_json::to_value(validate(&node_summaries)).unwrap();
+
+        assert_eq!(report["valid"], false);
+        assert_eq!(report["predecessors"].as_array().unwrap().len(), 3);
+        assert_eq!(report["predecessors"][0]["node"], second);
+        assert_eq!(report["predecessors"][0]["predecessor"], serde_json::Value::Null);
+        assert_eq!(report["predecessors"][0]["expected_predecessor"], first);
+        assert_eq!(report["predecessors"][0]["correct"], false);
+        assert_eq!(report["wrong_fingers"][0]["node"], second);
+        assert_eq!(report["wrong_fingers"][0]["index"], 0);
+        assert_eq!(report["wrong_fingers"][0]["expected"], third);
+        assert_eq!(report["wrong_fingers"][0]["actual"], first);
+        assert_eq!(report["successor_list_mismatches"][0]["node"], third);
+        assert_eq!(report["successor_list_mismatches"][0]["expected"], first);
+        assert_eq!(report["successor_list_mismatches"][0]["actual"], second);
+    }
+}